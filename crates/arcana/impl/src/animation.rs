@@ -0,0 +1,189 @@
+//! Sprite animations imported from Aseprite files.
+
+use std::future::{ready, Ready};
+
+use arcana_importers::texture::{TextureData, TextureDecodeError};
+use edict::Component;
+
+use crate::{
+    assets::BobBuilder,
+    texture::{upload_texture, Texture},
+};
+
+/// Frame of the sprite animation.
+#[derive(Clone, Copy, Debug, serde::Deserialize)]
+pub struct SpriteFrame {
+    /// Position of the frame in the sheet in pixels.
+    pub x: u32,
+    pub y: u32,
+
+    /// Size of the frame in pixels.
+    pub width: u32,
+    pub height: u32,
+
+    /// Duration of the frame in milliseconds.
+    pub duration: u32,
+}
+
+/// Direction in which tagged frames are played.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SpriteDirection {
+    Forward,
+    Reverse,
+    PingPong,
+}
+
+/// Named range of frames.
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct SpriteTag {
+    pub name: String,
+
+    /// First frame of the tag.
+    pub from: u32,
+
+    /// Last frame of the tag, inclusive.
+    pub to: u32,
+
+    pub direction: SpriteDirection,
+}
+
+#[derive(serde::Deserialize)]
+pub struct SpriteAnimationMeta {
+    frames: Vec<SpriteFrame>,
+    tags: Vec<SpriteTag>,
+}
+
+/// Sprite animation asset.
+///
+/// Contains sprite sheet with all frames and their timings.
+#[derive(Clone)]
+pub struct SpriteAnimation {
+    pub sheet: Texture,
+    pub frames: Vec<SpriteFrame>,
+    pub tags: Vec<SpriteTag>,
+}
+
+impl Component for SpriteAnimation {
+    fn name() -> &'static str {
+        "SpriteAnimation"
+    }
+}
+
+impl SpriteAnimation {
+    /// Returns tag by name.
+    pub fn tag(&self, name: &str) -> Option<&SpriteTag> {
+        self.tags.iter().find(|tag| tag.name == name)
+    }
+
+    /// Returns index of the frame to show after `elapsed` milliseconds
+    /// since animation start.
+    ///
+    /// Plays frames of the tag if specified, all frames otherwise.
+    /// Animation is looped.
+    pub fn frame_at(&self, tag: Option<&SpriteTag>, elapsed: u64) -> usize {
+        if self.frames.is_empty() {
+            return 0;
+        }
+
+        let last = self.frames.len() - 1;
+
+        let (from, to, direction) = match tag {
+            None => (0, last, SpriteDirection::Forward),
+            Some(tag) => (
+                (tag.from as usize).min(last),
+                (tag.to as usize).min(last),
+                tag.direction,
+            ),
+        };
+
+        // Sequence of frame indices in playing order.
+        let sequence = match direction {
+            SpriteDirection::Forward => (from..=to).collect::<Vec<_>>(),
+            SpriteDirection::Reverse => (from..=to).rev().collect(),
+            SpriteDirection::PingPong => (from..=to).chain((from + 1..to).rev()).collect(),
+        };
+
+        let total = sequence
+            .iter()
+            .map(|&idx| self.frames[idx].duration as u64)
+            .sum::<u64>();
+
+        if total == 0 {
+            return sequence[0];
+        }
+
+        let mut time = elapsed % total;
+        for &idx in &sequence {
+            let duration = self.frames[idx].duration as u64;
+            if time < duration {
+                return idx;
+            }
+            time -= duration;
+        }
+
+        sequence[sequence.len() - 1]
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SpriteAnimationDecodeError {
+    #[error("Sprite animation data is truncated")]
+    Truncated,
+
+    #[error("Failed to decode sprite animation metadata: {0}")]
+    Meta(#[from] serde_json::Error),
+
+    #[error("Failed to decode sprite sheet: {0}")]
+    Sheet(#[from] TextureDecodeError),
+}
+
+impl argosy::Asset for SpriteAnimation {
+    type Decoded = (SpriteAnimationMeta, TextureData);
+    type DecodeError = SpriteAnimationDecodeError;
+    type BuildError = mev::OutOfMemory;
+    type Fut = Ready<Result<Self::Decoded, SpriteAnimationDecodeError>>;
+
+    fn name() -> &'static str {
+        "sprite-animation"
+    }
+
+    fn decode(bytes: Box<[u8]>, _: &argosy::Loader) -> Self::Fut {
+        ready(decode_sprite_animation(&bytes))
+    }
+}
+
+/// Decodes data produced by Aseprite animation importer.
+/// It is little-endian `u32` length of JSON metadata,
+/// the metadata itself and sheet as texture artifact.
+fn decode_sprite_animation(
+    bytes: &[u8],
+) -> Result<(SpriteAnimationMeta, TextureData), SpriteAnimationDecodeError> {
+    let (len, rest) = bytes
+        .split_first_chunk::<4>()
+        .ok_or(SpriteAnimationDecodeError::Truncated)?;
+    let len = u32::from_le_bytes(*len) as usize;
+
+    if rest.len() < len {
+        return Err(SpriteAnimationDecodeError::Truncated);
+    }
+
+    let (meta, sheet) = rest.split_at(len);
+    let meta = serde_json::from_slice(meta)?;
+    let sheet = TextureData::decode(sheet)?;
+
+    Ok((meta, sheet))
+}
+
+impl argosy::AssetBuild<BobBuilder<'_>> for SpriteAnimation {
+    fn build(builder: &mut BobBuilder, decoded: Self::Decoded) -> Result<Self, mev::OutOfMemory> {
+        let (meta, sheet) = decoded;
+        let image = upload_texture(builder, "sprite-sheet", sheet)?;
+
+        Ok(SpriteAnimation {
+            sheet: Texture { image },
+            frames: meta.frames,
+            tags: meta.tags,
+        })
+    }
+}
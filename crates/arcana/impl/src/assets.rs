@@ -1,10 +1,19 @@
 //! Utils to work with `argosy` asset pipeline.
 
+use std::path::{Path, PathBuf};
+
 pub use argosy::{
     proc::{Asset, AssetField},
     AssetId,
 };
 use argosy::{AssetDriver, AssetFuture, DriveAsset, LoadedAssetDriver};
+use edict::World;
+use hashbrown::{HashMap, HashSet};
+
+use crate::{
+    events::{EventId, Events},
+    local_name_hash_id,
+};
 
 pub trait Asset: argosy::Asset + for<'a> argosy::AssetBuild<BobBuilder<'a>> {}
 impl<A> Asset for A where A: argosy::Asset + for<'a> argosy::AssetBuild<BobBuilder<'a>> {}
@@ -12,8 +21,54 @@ impl<A> Asset for A where A: argosy::Asset + for<'a> argosy::AssetBuild<BobBuild
 pub struct Assets {
     loader: argosy::Loader,
 
-    load_queue: Vec<AssetDriver<Bob>>,
-    build_queue: Vec<LoadedAssetDriver<Bob>>,
+    /// Loaders with fresh artifacts of assets reloaded after source changes.
+    /// Main loader keeps serving assets that did not change.
+    reload_loaders: HashMap<AssetId, argosy::Loader>,
+
+    /// Store used to import sources and lookup asset IDs.
+    store: Option<argosy_store::Store>,
+
+    /// Directory with asset sources.
+    root: PathBuf,
+
+    /// Path to the store info file.
+    info_path: PathBuf,
+
+    /// Cache of imported sources.
+    paths: HashMap<(PathBuf, String), (AssetId, PathBuf)>,
+
+    /// Functions to load asset again with the type it was loaded before.
+    reloaders: HashMap<AssetId, fn(&mut Assets, AssetId)>,
+
+    /// Watcher for asset sources.
+    watch: Option<Watch>,
+
+    /// Assets that are being reloaded.
+    reloading: HashSet<AssetId>,
+
+    /// Assets that were reloaded and built since last drain.
+    reloaded: Vec<AssetId>,
+
+    load_queue: Vec<(AssetId, AssetDriver<Bob>)>,
+    build_queue: Vec<(AssetId, LoadedAssetDriver<Bob>)>,
+}
+
+struct Watch {
+    _watcher: notify::RecommendedWatcher,
+    changes: flume::Receiver<PathBuf>,
+}
+
+/// Event emitted when asset is reloaded after its source has changed.
+/// Payload is [`AssetReloaded`].
+pub const ASSET_RELOADED: EventId = local_name_hash_id!(ASSET_RELOADED => EventId);
+
+/// Payload of [`ASSET_RELOADED`] event.
+///
+/// Users that cache GPU resources created from the asset
+/// should drop them and load the asset again.
+#[derive(Clone, Copy, Debug)]
+pub struct AssetReloaded {
+    pub id: AssetId,
 }
 
 /// Builder for a Bob asset.
@@ -29,34 +84,261 @@ impl DriveAsset for Bob {
     type Builder<'a> = BobBuilder<'a>;
 }
 
+/// Directory with asset sources relative to project root.
+pub const ASSETS_DIR: &str = "assets";
+
+/// Directory with asset store data relative to project root.
+pub const STORE_DIR: &str = ".assets";
+
+/// Asset store info file relative to project root.
+pub const STORE_INFO: &str = ".assets/argosy.toml";
+
+/// Error that may occur when opening asset store or importing an asset.
+#[derive(Debug, thiserror::Error)]
+pub enum AssetStoreError {
+    #[error("Failed to create asset store: {0}")]
+    Create(String),
+
+    #[error("Failed to open asset store: {0}")]
+    Open(String),
+
+    #[error("Failed to import asset '{path}': {reason}")]
+    Import { path: PathBuf, reason: String },
+
+    #[error("Asset store is not configured")]
+    NoStore,
+
+    #[error("Failed to watch asset sources: {0}")]
+    Watch(String),
+}
+
 impl Assets {
+    /// Creates assets manager without any asset sources.
     pub fn new() -> Self {
-        // let store_path = Path::new(env!("CARGO_MANIFEST_DIR"))
-        //     .parent()
-        //     .unwrap()
-        //     .join("argosy.toml");
-
-        // if !store_path.exists() {
-        //     let info = argosy_store::StoreInfo {
-        //         artifacts: Some(Path::new("target/artifacts").to_owned()),
-        //         external: Some(Path::new("target/external").to_owned()),
-        //         temp: Some(Path::new("target/temp").to_owned()),
-        //         importers: vec![Path::new("target/debug/arcana_importers").to_owned()],
-        //     };
-        //     info.write(&store_path).unwrap();
-        // }
-
-        // let store = argosy_store::Store::find(&store_path).unwrap();
+        let loader = argosy::Loader::builder().build();
+
+        Assets {
+            loader,
+            reload_loaders: HashMap::new(),
+            store: None,
+            root: PathBuf::new(),
+            info_path: PathBuf::new(),
+            paths: HashMap::new(),
+            reloaders: HashMap::new(),
+            watch: None,
+            reloading: HashSet::new(),
+            reloaded: Vec::new(),
+            load_queue: Vec::new(),
+            build_queue: Vec::new(),
+        }
+    }
+
+    /// Creates assets manager with project-local asset store.
+    ///
+    /// Asset sources are looked up in `assets` directory next to `Arcana.toml`.
+    /// Imported artifacts, external and temporary files are stored in `.assets` directory.
+    /// Sources are imported with importers from `arcana-importers` crate.
+    pub fn with_root(root: &Path) -> Result<Self, AssetStoreError> {
+        let info_path = root.join(STORE_INFO);
+        let store_dir = root.join(STORE_DIR);
+
+        if !info_path.exists() {
+            let info = argosy_store::StoreInfo {
+                artifacts: Some(store_dir.join("artifacts")),
+                external: Some(store_dir.join("external")),
+                temp: Some(store_dir.join("temp")),
+                importers: Vec::new(),
+            };
+
+            std::fs::create_dir_all(&store_dir)
+                .map_err(|err| AssetStoreError::Create(err.to_string()))?;
+            info.write(&info_path)
+                .map_err(|err| AssetStoreError::Create(err.to_string()))?;
+        }
 
+        // Loader and lookup use separate handles to the same store directories.
         let loader = argosy::Loader::builder()
-            // .with(store)
+            .with(open_store(&info_path)?)
             .build();
+        let store = open_store(&info_path)?;
 
-        Assets {
+        Ok(Assets {
             loader,
+            reload_loaders: HashMap::new(),
+            store: Some(store),
+            root: root.join(ASSETS_DIR),
+            info_path,
+            paths: HashMap::new(),
+            reloaders: HashMap::new(),
+            watch: None,
+            reloading: HashSet::new(),
+            reloaded: Vec::new(),
             load_queue: Vec::new(),
             build_queue: Vec::new(),
+        })
+    }
+
+    /// Returns directory with asset sources.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Starts watching asset sources.
+    ///
+    /// Changed sources are imported again and
+    /// assets loaded from them are reloaded on next [`Assets::build`].
+    pub fn watch(&mut self) -> Result<(), AssetStoreError> {
+        if self.store.is_none() {
+            return Err(AssetStoreError::NoStore);
         }
+
+        if self.watch.is_some() {
+            return Ok(());
+        }
+
+        let (tx, rx) = flume::unbounded();
+
+        let mut watcher = notify::recommended_watcher(
+            move |result: notify::Result<notify::Event>| match result {
+                Ok(event) => {
+                    if event.kind.is_modify() || event.kind.is_create() {
+                        for path in event.paths {
+                            let _ = tx.send(path);
+                        }
+                    }
+                }
+                Err(err) => {
+                    tracing::error!("Asset watcher error: {err}");
+                }
+            },
+        )
+        .map_err(|err| AssetStoreError::Watch(err.to_string()))?;
+
+        std::fs::create_dir_all(&self.root)
+            .map_err(|err| AssetStoreError::Watch(err.to_string()))?;
+
+        notify::Watcher::watch(&mut watcher, &self.root, notify::RecursiveMode::Recursive)
+            .map_err(|err| AssetStoreError::Watch(err.to_string()))?;
+
+        self.watch = Some(Watch {
+            _watcher: watcher,
+            changes: rx,
+        });
+
+        Ok(())
+    }
+
+    /// Returns IDs of assets that were reloaded since last call.
+    pub fn drain_reloaded(&mut self) -> std::vec::Drain<'_, AssetId> {
+        self.reloaded.drain(..)
+    }
+
+    /// Imports changed sources again and starts reloading affected assets.
+    fn reload_changed(&mut self) {
+        let Some(watch) = &self.watch else {
+            return;
+        };
+
+        let changed = watch
+            .changes
+            .try_iter()
+            .filter_map(|path| {
+                let path = dunce::simplified(&path).to_owned();
+                let path = path.strip_prefix(&self.root).ok()?;
+
+                // Changed import options affect their source.
+                match path.to_str().and_then(|p| p.strip_suffix(".import.json")) {
+                    Some(source) => Some(PathBuf::from(source)),
+                    None => Some(path.to_owned()),
+                }
+            })
+            .collect::<HashSet<_>>();
+
+        if changed.is_empty() {
+            return;
+        }
+
+        let keys = self
+            .paths
+            .keys()
+            .filter(|(path, _)| changed.contains(path))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if keys.is_empty() {
+            return;
+        }
+
+        let mut ids = Vec::new();
+        for (path, target) in keys {
+            self.paths.remove(&(path.clone(), target.clone()));
+
+            match self.import(&path, &target) {
+                Ok((id, _)) => {
+                    tracing::info!("Asset source '{}' changed", path.display());
+                    ids.push(id);
+                }
+                Err(err) => {
+                    tracing::error!("{err}");
+                }
+            }
+        }
+
+        if ids.is_empty() {
+            return;
+        }
+
+        // Loader caches loaded assets.
+        // Changed assets are loaded with a fresh one so that new artifacts are loaded,
+        // while other assets and their handles stay in the main loader.
+        let loader = match open_store(&self.info_path) {
+            Ok(store) => argosy::Loader::builder().with(store).build(),
+            Err(err) => {
+                tracing::error!("{err}");
+                return;
+            }
+        };
+
+        for &id in &ids {
+            self.reload_loaders.insert(id, loader.clone());
+        }
+
+        for id in ids {
+            if let Some(reload) = self.reloaders.get(&id).copied() {
+                self.reloading.insert(id);
+                reload(self, id);
+            }
+        }
+    }
+
+    /// Finds asset ID for the source path.
+    ///
+    /// Path is relative to assets directory.
+    /// Source is imported into `target` format if it wasn't yet.
+    pub fn find_path(&mut self, path: &Path, target: &str) -> Result<AssetId, AssetStoreError> {
+        let (id, _) = self.import(path, target)?;
+        Ok(id)
+    }
+
+    fn import(&mut self, path: &Path, target: &str) -> Result<(AssetId, PathBuf), AssetStoreError> {
+        let key = (path.to_owned(), target.to_owned());
+        if let Some((id, artifact)) = self.paths.get(&key) {
+            return Ok((*id, artifact.clone()));
+        }
+
+        let store = self.store.as_ref().ok_or(AssetStoreError::NoStore)?;
+
+        let source = self.root.join(path);
+        let (id, artifact) =
+            store
+                .store(&source, target)
+                .map_err(|err| AssetStoreError::Import {
+                    path: path.to_owned(),
+                    reason: err.to_string(),
+                })?;
+
+        self.paths.insert(key, (id, artifact.clone()));
+        Ok((id, artifact))
     }
 
     pub fn build(
@@ -64,11 +346,13 @@ impl Assets {
         device: &mev::Device,
         queue: &mut mev::Queue,
     ) -> Result<(), mev::DeviceError> {
+        self.reload_changed();
+
         self.load_queue
-            .retain_mut(|driver| match driver.poll_loaded() {
+            .retain_mut(|(id, driver)| match driver.poll_loaded() {
                 None => true,
                 Some(loaded) => {
-                    self.build_queue.push(loaded);
+                    self.build_queue.push((*id, loaded));
                     false
                 }
             });
@@ -85,8 +369,12 @@ impl Assets {
                 encoder: encoder.copy(),
             };
 
-            for loaded in self.build_queue.drain(..) {
+            for (id, loaded) in self.build_queue.drain(..) {
                 loaded.build(&mut builder);
+
+                if self.reloading.remove(&id) {
+                    self.reloaded.push(id);
+                }
             }
         }
 
@@ -129,10 +417,115 @@ impl Assets {
     where
         A: Asset,
     {
-        let handle = self.loader.load(id);
+        let loader = self.reload_loaders.get(&id).unwrap_or(&self.loader);
+        let handle = loader.load(id);
         let driver = handle.clone().driver::<Bob>();
-        self.load_queue.push(driver);
+        self.load_queue.push((id, driver));
+
+        self.reloaders.insert(id, |assets, id| {
+            let _ = assets.load_with_id::<A>(id);
+        });
 
         handle.ready()
     }
+
+    /// Loads asset by source path relative to assets directory.
+    ///
+    /// Source is imported into format matching asset name.
+    pub fn load_path<A>(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<AssetFuture<A>, AssetStoreError>
+    where
+        A: Asset,
+    {
+        let id = self.find_path(path.as_ref(), <A as argosy::Asset>::name())?;
+        Ok(self.load_with_id(id))
+    }
+}
+
+fn open_store(info_path: &Path) -> Result<argosy_store::Store, AssetStoreError> {
+    let mut store = argosy_store::Store::find(info_path)
+        .map_err(|err| AssetStoreError::Open(err.to_string()))?;
+
+    for importer in arcana_importers::importers() {
+        store.register_importer(importer);
+    }
+    Ok(store)
+}
+
+/// Builds loaded assets and emits [`ASSET_RELOADED`] events
+/// for assets reloaded after source changes.
+///
+/// Does nothing if there is no [`Assets`] resource.
+pub fn build_assets(
+    world: &mut World,
+    device: &mev::Device,
+    queue: &mut mev::Queue,
+) -> Result<(), mev::DeviceError> {
+    let Some(mut assets) = world.get_resource_mut::<Assets>() else {
+        return Ok(());
+    };
+
+    assets.build(device, queue)?;
+
+    let reloaded = assets.drain_reloaded().collect::<Vec<_>>();
+    drop(assets);
+
+    if reloaded.is_empty() {
+        return Ok(());
+    }
+
+    if let Some(mut events) = world.get_resource_mut::<Events>() {
+        for id in reloaded {
+            if let Err(err) = events.emit(ASSET_RELOADED, AssetReloaded { id }) {
+                tracing::error!("{err}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn import_aseprite_texture() {
+    use argosy::Asset as _;
+
+    let root = std::env::temp_dir().join(format!("arcana-assets-test-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join(ASSETS_DIR).join("sprites")).unwrap();
+    std::fs::copy(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/ball.aseprite"),
+        root.join(ASSETS_DIR).join("sprites/ball.aseprite"),
+    )
+    .unwrap();
+
+    let mut assets = Assets::with_root(&root).unwrap();
+    let (id, artifact) = assets
+        .import(
+            Path::new("sprites/ball.aseprite"),
+            crate::texture::Texture::name(),
+        )
+        .unwrap();
+
+    // Same path resolves to the same asset.
+    assert_eq!(
+        assets
+            .find_path(Path::new("sprites/ball.aseprite"), "texture")
+            .unwrap(),
+        id
+    );
+
+    // Decoding doesn't require GPU device.
+    let bytes = std::fs::read(artifact).unwrap().into_boxed_slice();
+    let texture =
+        futures::executor::block_on(crate::texture::Texture::decode(bytes, &assets.loader))
+            .unwrap();
+
+    // Mip chain is generated at import time.
+    assert_eq!((texture.width, texture.height), (4, 4));
+    assert_eq!(texture.levels.len(), 3);
+    assert_eq!(texture.levels[0].len(), 4 * 4 * 4);
+
+    let _ = std::fs::remove_dir_all(&root);
 }
@@ -0,0 +1,141 @@
+use edict::flow::FlowEntity;
+use hashbrown::HashMap;
+
+use crate::{local_name_hash_id, name, plugin::CodeInfo, stid::Stid, Name};
+
+use super::{CodeDesc, CodeId, IntoPureCode, PureCode};
+
+/// Conversion codes by source and target value types.
+///
+/// Conversion is a pure code with single input and single output.
+/// Editor inserts conversion node when wire connects value pins
+/// of different types and conversion between them is registered.
+#[derive(Clone, Default)]
+pub struct Conversions {
+    codes: HashMap<(Stid, Stid), CodeInfo>,
+}
+
+impl Conversions {
+    pub fn new() -> Self {
+        Conversions {
+            codes: HashMap::new(),
+        }
+    }
+
+    /// Returns conversions provided by the engine.
+    pub fn builtin() -> Self {
+        let mut conversions = Conversions::new();
+        for (info, _) in builtin_conversions() {
+            conversions.add(info);
+        }
+        conversions
+    }
+
+    /// Registers conversion code.
+    /// Replaces previously registered conversion between same types.
+    ///
+    /// Returns false if code is not a conversion.
+    pub fn add(&mut self, info: CodeInfo) -> bool {
+        match info.desc {
+            CodeDesc::Pure {
+                ref inputs,
+                ref outputs,
+            } if inputs.len() == 1 && outputs.len() == 1 => {
+                self.codes.insert((inputs[0], outputs[0]), info);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns conversion code from one type to another.
+    pub fn get(&self, from: Stid, to: Stid) -> Option<&CodeInfo> {
+        self.codes.get(&(from, to))
+    }
+}
+
+/// Returns conversions provided by the engine with their pure codes.
+pub fn builtin_conversions() -> Vec<(CodeInfo, PureCode)> {
+    vec![
+        conversion(
+            local_name_hash_id!(f32_to_f64 => CodeId),
+            name!(f32_to_f64),
+            f32_to_f64,
+        ),
+        conversion(
+            local_name_hash_id!(i32_to_i64 => CodeId),
+            name!(i32_to_i64),
+            i32_to_i64,
+        ),
+        conversion(
+            local_name_hash_id!(u32_to_u64 => CodeId),
+            name!(u32_to_u64),
+            u32_to_u64,
+        ),
+        conversion(
+            local_name_hash_id!(i32_to_f64 => CodeId),
+            name!(i32_to_f64),
+            i32_to_f64,
+        ),
+        conversion(
+            local_name_hash_id!(u32_to_f64 => CodeId),
+            name!(u32_to_f64),
+            u32_to_f64,
+        ),
+        conversion(
+            local_name_hash_id!(vec2_to_vec3 => CodeId),
+            name!(vec2_to_vec3),
+            vec2_to_vec3,
+        ),
+    ]
+}
+
+fn conversion<A, B>(
+    id: CodeId,
+    name: Name,
+    f: impl IntoPureCode<(A,), (B,)>,
+) -> (CodeInfo, PureCode) {
+    let (desc, code) = f.into_pure_code();
+    (CodeInfo { id, name, desc }, code)
+}
+
+fn f32_to_f64(_: FlowEntity, value: f32) -> (f64,) {
+    (value.into(),)
+}
+
+fn i32_to_i64(_: FlowEntity, value: i32) -> (i64,) {
+    (value.into(),)
+}
+
+fn u32_to_u64(_: FlowEntity, value: u32) -> (u64,) {
+    (value.into(),)
+}
+
+fn i32_to_f64(_: FlowEntity, value: i32) -> (f64,) {
+    (value.into(),)
+}
+
+fn u32_to_f64(_: FlowEntity, value: u32) -> (f64,) {
+    (value.into(),)
+}
+
+/// Extends vector with zero z coordinate.
+fn vec2_to_vec3(_: FlowEntity, value: na::Vector2<f32>) -> (na::Vector3<f32>,) {
+    (value.push(0.0),)
+}
+
+#[test]
+fn builtin_conversions_are_registered() {
+    use crate::stid::WithStid;
+
+    let conversions = Conversions::builtin();
+
+    let info = conversions.get(f32::stid(), f64::stid()).unwrap();
+    assert_eq!(info.name, name!(f32_to_f64));
+
+    assert!(conversions
+        .get(na::Vector2::<f32>::stid(), na::Vector3::<f32>::stid())
+        .is_some());
+
+    assert!(conversions.get(f64::stid(), f32::stid()).is_none());
+}
@@ -0,0 +1,181 @@
+use arcana_names::Name;
+use hashbrown::HashMap;
+
+use crate::{events::EventId, stid::Stid};
+
+use super::{CodeDesc, CodeId, InputId, OutputId};
+
+/// Kind of the node pin.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CodePin {
+    /// Flow pin.
+    Flow,
+
+    /// Value pin of specified type.
+    Value(Stid),
+}
+
+/// Node of the code graph.
+///
+/// Node pins are numbered as described by [`CodeDesc`].
+/// Flows come first, values follow.
+/// Event node has single outflow followed by event values.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct CodeNode {
+    /// Code executed by the node.
+    pub id: CodeId,
+    pub name: Name,
+    pub desc: CodeDesc,
+}
+
+impl CodeNode {
+    /// Returns number of input pins, including inflows.
+    pub fn inputs(&self) -> usize {
+        match self.desc {
+            CodeDesc::Event { .. } => 0,
+            CodeDesc::Pure { ref inputs, .. } => inputs.len(),
+            CodeDesc::Flow {
+                inflows,
+                ref inputs,
+                ..
+            } => inflows + inputs.len(),
+        }
+    }
+
+    /// Returns number of output pins, including outflows.
+    pub fn outputs(&self) -> usize {
+        match self.desc {
+            CodeDesc::Event { ref outputs, .. } => 1 + outputs.len(),
+            CodeDesc::Pure { ref outputs, .. } => outputs.len(),
+            CodeDesc::Flow {
+                outflows,
+                ref outputs,
+                ..
+            } => outflows + outputs.len(),
+        }
+    }
+
+    /// Returns kind of the input pin.
+    pub fn input_pin(&self, input: usize) -> Option<CodePin> {
+        match self.desc {
+            CodeDesc::Event { .. } => None,
+            CodeDesc::Pure { ref inputs, .. } => inputs.get(input).copied().map(CodePin::Value),
+            CodeDesc::Flow {
+                inflows,
+                ref inputs,
+                ..
+            } => {
+                if input < inflows {
+                    Some(CodePin::Flow)
+                } else {
+                    inputs.get(input - inflows).copied().map(CodePin::Value)
+                }
+            }
+        }
+    }
+
+    /// Returns kind of the output pin.
+    pub fn output_pin(&self, output: usize) -> Option<CodePin> {
+        match self.desc {
+            CodeDesc::Event { ref outputs, .. } => {
+                if output == 0 {
+                    Some(CodePin::Flow)
+                } else {
+                    outputs.get(output - 1).copied().map(CodePin::Value)
+                }
+            }
+            CodeDesc::Pure { ref outputs, .. } => outputs.get(output).copied().map(CodePin::Value),
+            CodeDesc::Flow {
+                outflows,
+                ref outputs,
+                ..
+            } => {
+                if output < outflows {
+                    Some(CodePin::Flow)
+                } else {
+                    outputs.get(output - outflows).copied().map(CodePin::Value)
+                }
+            }
+        }
+    }
+
+    /// Returns range of input pins with values.
+    /// Each of them must be connected for the node to execute.
+    pub(super) fn value_inputs(&self) -> std::ops::Range<usize> {
+        match self.desc {
+            CodeDesc::Event { .. } => 0..0,
+            CodeDesc::Pure { ref inputs, .. } => 0..inputs.len(),
+            CodeDesc::Flow {
+                inflows,
+                ref inputs,
+                ..
+            } => inflows..inflows + inputs.len(),
+        }
+    }
+}
+
+/// Connection from node output to node input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct Wire {
+    pub from: OutputId,
+    pub to: InputId,
+}
+
+/// Code graph that can be executed without the editor.
+///
+/// Editor produces it from its own representation
+/// and game loads it from the game data.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CodeGraph {
+    pub name: Name,
+
+    /// Nodes by index.
+    /// Indices are used in [`InputId`] and [`OutputId`].
+    pub nodes: HashMap<usize, CodeNode>,
+
+    /// Connections between nodes.
+    /// Each input has at most one wire.
+    pub wires: Vec<Wire>,
+}
+
+impl CodeGraph {
+    /// Returns new empty code graph.
+    pub fn new(name: Name) -> Self {
+        CodeGraph {
+            name,
+            nodes: HashMap::new(),
+            wires: Vec::new(),
+        }
+    }
+
+    /// Returns node by index.
+    pub fn node(&self, node: usize) -> Option<&CodeNode> {
+        self.nodes.get(&node)
+    }
+
+    /// Returns output connected to the input.
+    pub fn input_source(&self, input: InputId) -> Option<OutputId> {
+        self.wires
+            .iter()
+            .find(|wire| wire.to == input)
+            .map(|wire| wire.from)
+    }
+
+    /// Returns first input connected to the output.
+    /// Outflows are connected to at most one inflow.
+    pub fn output_target(&self, output: OutputId) -> Option<InputId> {
+        self.wires
+            .iter()
+            .find(|wire| wire.from == output)
+            .map(|wire| wire.to)
+    }
+
+    /// Returns index of the node that handles the event
+    /// and number of event values.
+    pub fn event_node(&self, event: EventId) -> Option<(usize, usize)> {
+        self.nodes.iter().find_map(|(&idx, node)| match node.desc {
+            CodeDesc::Event { id, ref outputs } if id == event => Some((idx, outputs.len())),
+            _ => None,
+        })
+    }
+}
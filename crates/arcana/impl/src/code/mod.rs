@@ -1,4 +1,13 @@
 //! Building blocks for visual programming.
+//!
+//! Codes are graphs of nodes provided by plugins.
+//! They are executed by the runtime when events are emitted to entities
+//! with [`Code`] component.
+
+mod convert;
+mod graph;
+mod run;
+mod validate;
 
 use std::{any::Any, future::Future, marker::PhantomData, pin::Pin};
 
@@ -11,15 +20,22 @@ use crate::{
     stid::{Stid, WithStid},
 };
 
+pub use self::{
+    convert::{builtin_conversions, Conversions},
+    graph::{CodeGraph, CodeNode, CodePin, Wire},
+    run::{on_code_event, run_code_events, Code},
+    validate::{CodeDiagnostic, CodeDiagnostics},
+};
+
 make_id!(pub CodeId);
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct InputId {
     pub node: usize,
     pub input: usize,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct OutputId {
     pub node: usize,
     pub output: usize,
@@ -81,6 +97,12 @@ impl OutputCache {
             self.values.insert(id, boxed);
         }
     }
+
+    /// Sets output value directly.
+    /// Used to feed event values into event nodes.
+    pub fn set_value(&mut self, id: OutputId, value: Box<dyn Any>) {
+        self.values.insert(id, value);
+    }
 }
 
 /// Type of pure code function.
@@ -89,12 +111,34 @@ impl OutputCache {
 /// Its execution may occur at any point or not occur at all.
 pub type PureCode = fn(entity: FlowEntity, inputs: &[Input], outputs: &mut [Output]);
 
+/// Asynchronous part of the flow code.
+/// It is called with the entity from a flow spawned for it
+/// and returns future that resolves to the output flow index.
+pub type AwaitCode = Box<
+    dyn for<'a> FnOnce(FlowEntity<'a>) -> Pin<Box<dyn Future<Output = usize> + Send + 'a>> + Send,
+>;
+
 pub enum Continuation {
     /// Continue execution with given output flow.
     Continue(usize),
 
-    /// Continue execution with given output flow when given future resolves.
-    Await(Pin<Box<dyn Future<Output = usize> + Send>>),
+    /// Continue execution with given output flow when future resolves.
+    ///
+    /// Execution is cancelled if the entity is despawned
+    /// or its `Code` component is removed before that.
+    Await(AwaitCode),
+}
+
+impl Continuation {
+    /// Returns continuation that awaits future produced by `f`.
+    pub fn await_with<F>(f: F) -> Self
+    where
+        F: for<'a> FnOnce(FlowEntity<'a>) -> Pin<Box<dyn Future<Output = usize> + Send + 'a>>
+            + Send
+            + 'static,
+    {
+        Continuation::Await(Box::new(f))
+    }
 }
 
 /// Type of code function.
@@ -0,0 +1,812 @@
+//! Code graph interpreter.
+
+use std::any::Any;
+
+use edict::{flow::FlowEntity, spawn_block, Component, Entities, EntityId, World};
+use hashbrown::{HashMap, HashSet};
+use smallvec::SmallVec;
+
+use crate::events::{EntityEventQueue, EventId};
+
+use super::{
+    CodeDesc, CodeGraph, CodeId, Continuation, FlowCode, Input, InputId, OutputCache, OutputId,
+    PureCode,
+};
+
+/// Component that makes entity run code graph
+/// when events are emitted to it.
+#[derive(Clone, Copy, Component)]
+#[edict(name = "Code")]
+pub struct Code {
+    id: CodeId,
+}
+
+impl Code {
+    pub fn new(id: CodeId) -> Self {
+        Code { id }
+    }
+
+    pub fn id(&self) -> CodeId {
+        self.id
+    }
+}
+
+/// Outflow of awaited flow code to continue execution from.
+struct Resume {
+    entity: EntityId,
+    code: CodeId,
+    outflow: OutputId,
+}
+
+/// Resource with outflows of awaited flow codes
+/// that are ready to continue execution.
+struct CodeResumeQueue {
+    tx: flume::Sender<Resume>,
+    rx: flume::Receiver<Resume>,
+}
+
+impl CodeResumeQueue {
+    fn new() -> Self {
+        let (tx, rx) = flume::unbounded();
+        CodeResumeQueue { tx, rx }
+    }
+}
+
+/// Collects pure nodes that must be executed to produce given inputs
+/// in dependencies-first order.
+fn schedule_pure_inputs(
+    node: usize,
+    inputs: impl Iterator<Item = usize>,
+    graph: &CodeGraph,
+) -> Vec<usize> {
+    let mut scheduled = HashSet::new();
+    let mut queue = Vec::new();
+    let mut schedule = Vec::new();
+
+    for input in inputs {
+        if let Some(producer) = graph.input_source(InputId { node, input }) {
+            queue.push(producer.node);
+        }
+    }
+
+    while let Some(node) = queue.pop() {
+        if scheduled.contains(&node) {
+            continue;
+        }
+
+        let mut delay = false;
+
+        match graph.node(node) {
+            Some(code_node) => match code_node.desc {
+                CodeDesc::Pure { ref inputs, .. } => {
+                    for input in 0..inputs.len() {
+                        if let Some(producer) = graph.input_source(InputId { node, input }) {
+                            if !scheduled.contains(&producer.node) {
+                                delay = true;
+                                queue.push(producer.node);
+                            }
+                        }
+                    }
+                }
+                _ => continue,
+            },
+            None => continue,
+        }
+
+        if delay {
+            queue.push(node);
+        } else {
+            scheduled.insert(node);
+            schedule.push(node);
+        }
+    }
+
+    schedule
+}
+
+/// Collects values for node inputs from the cache.
+fn collect_inputs<'a>(
+    node: usize,
+    inputs: impl Iterator<Item = usize>,
+    graph: &CodeGraph,
+    cache: &'a OutputCache,
+) -> Option<SmallVec<[Input<'a>; 8]>> {
+    inputs
+        .map(|input| {
+            let source = graph.input_source(InputId { node, input })?;
+            cache.input(source)
+        })
+        .collect()
+}
+
+/// Execute specific pure code.
+fn execute_pure(
+    entity: FlowEntity,
+    node: usize,
+    graph: &CodeGraph,
+    cache: &mut OutputCache,
+    pure: &HashMap<CodeId, PureCode>,
+) {
+    let Some(code_node) = graph.node(node) else {
+        tracing::error!("Pure node {node:?} was not found");
+        return;
+    };
+
+    match code_node.desc {
+        CodeDesc::Pure {
+            ref inputs,
+            ref outputs,
+        } => {
+            let Some(&pure_code) = pure.get(&code_node.id) else {
+                tracing::error!("Pure code {:?} is not found", code_node.id);
+                return;
+            };
+
+            let mut outputs = (0..outputs.len())
+                .map(|output| cache.take_output(OutputId { node, output }))
+                .collect::<SmallVec<[_; 8]>>();
+
+            {
+                let Some(inputs) = collect_inputs(node, 0..inputs.len(), graph, cache) else {
+                    tracing::error!("Code node {node:?} has missing inputs");
+                    return;
+                };
+
+                pure_code(entity, &inputs, &mut outputs);
+            }
+
+            for (output, slot) in outputs.into_iter().enumerate() {
+                cache.put_output(OutputId { node, output }, slot);
+            }
+        }
+        _ => {
+            tracing::error!("Node {node:?} is not pure");
+        }
+    }
+}
+
+/// Execute specific flow code.
+/// Returns outflow to continue with.
+///
+/// If flow code awaits, outflow is sent to `resume` when ready.
+fn execute_flow(
+    mut entity: FlowEntity,
+    code: CodeId,
+    pin: InputId,
+    graph: &CodeGraph,
+    cache: &mut OutputCache,
+    pure: &HashMap<CodeId, PureCode>,
+    flow: &HashMap<CodeId, FlowCode>,
+    resume: &flume::Sender<Resume>,
+) -> Option<usize> {
+    let Some(code_node) = graph.node(pin.node) else {
+        tracing::error!("Flow node {:?} was not found", pin.node);
+        return None;
+    };
+
+    match code_node.desc {
+        CodeDesc::Flow {
+            inflows,
+            outflows,
+            ref inputs,
+            ref outputs,
+        } => {
+            // Check flow connection.
+            if pin.input >= inflows {
+                tracing::error!("Flow {:?} doesn't have inflow {}", pin.node, pin.input);
+                return None;
+            }
+
+            // Grab code function.
+            let Some(&flow_code) = flow.get(&code_node.id) else {
+                tracing::error!("Flow code {:?} is not found", code_node.id);
+                return None;
+            };
+
+            let values = inflows..inflows + inputs.len();
+
+            // Schedule pure deps.
+            let schedule = schedule_pure_inputs(pin.node, values.clone(), graph);
+
+            // Execute pure deps.
+            for node in schedule {
+                execute_pure(entity.reborrow(), node, graph, cache, pure);
+            }
+
+            // Collect outputs.
+            // Value outputs are numbered after outflows.
+            let mut outputs = (0..outputs.len())
+                .map(|output| {
+                    cache.take_output(OutputId {
+                        node: pin.node,
+                        output: outflows + output,
+                    })
+                })
+                .collect::<SmallVec<[_; 8]>>();
+
+            let continuation = {
+                // Collect inputs.
+                let Some(inputs) = collect_inputs(pin.node, values, graph, cache) else {
+                    tracing::error!("Code node {:?} has missing inputs", pin.node);
+                    return None;
+                };
+
+                flow_code(pin, entity.reborrow(), &inputs, &mut outputs)
+            };
+
+            for (output, slot) in outputs.into_iter().enumerate() {
+                cache.put_output(
+                    OutputId {
+                        node: pin.node,
+                        output: outflows + output,
+                    },
+                    slot,
+                )
+            }
+
+            match continuation {
+                Continuation::Continue(output) => Some(output),
+                Continuation::Await(awaited) => {
+                    let node = pin.node;
+                    let resume = resume.clone();
+
+                    spawn_block!(for entity -> {
+                        let output = awaited(entity.reborrow()).await;
+
+                        // Entity and its code are checked when resumed.
+                        let _ = resume.send(Resume {
+                            entity: entity.id(),
+                            code,
+                            outflow: OutputId { node, output },
+                        });
+                    });
+
+                    None
+                }
+            }
+        }
+        _ => {
+            tracing::error!("Node {:?} is not flow", pin.node);
+            None
+        }
+    }
+}
+
+/// Execute code after specific outflow.
+fn execute_code_after(
+    mut entity: FlowEntity,
+    code: CodeId,
+    mut outflow: OutputId,
+    graph: &CodeGraph,
+    cache: &mut OutputCache,
+    pure: &HashMap<CodeId, PureCode>,
+    flow: &HashMap<CodeId, FlowCode>,
+    resume: &flume::Sender<Resume>,
+) {
+    loop {
+        let Some(code_node) = graph.node(outflow.node) else {
+            tracing::error!("Code node {:?} was not found", outflow.node);
+            return;
+        };
+
+        match code_node.desc {
+            CodeDesc::Pure { .. } => {
+                tracing::error!("Node {:?} is not event or flow", outflow.node);
+                return;
+            }
+            CodeDesc::Event { .. } => {
+                if outflow.output > 0 {
+                    tracing::error!("Events dont have outflow {:?}", outflow.output);
+                    return;
+                }
+            }
+            CodeDesc::Flow { outflows, .. } => {
+                if outflow.output >= outflows {
+                    tracing::error!(
+                        "Flow {:?} doesn't have outflow {:?}",
+                        outflow.node,
+                        outflow.output
+                    );
+                    return;
+                }
+            }
+        }
+
+        let Some(inflow) = graph.output_target(outflow) else {
+            // Leaf pin.
+            break;
+        };
+
+        let next = execute_flow(
+            entity.reborrow(),
+            code,
+            inflow,
+            graph,
+            cache,
+            pure,
+            flow,
+            resume,
+        );
+
+        match next {
+            None => break,
+            Some(output) => {
+                outflow = OutputId {
+                    node: inflow.node,
+                    output,
+                };
+            }
+        }
+    }
+}
+
+/// Starts code execution from event node.
+///
+/// Event values are placed into event node outputs
+/// and code flow continues from its outflow.
+pub fn on_code_event(
+    entity: EntityId,
+    event: EventId,
+    values: Vec<Box<dyn Any + Send + Sync>>,
+    world: &mut World,
+    codes: &HashMap<CodeId, CodeGraph>,
+    pure: &HashMap<CodeId, PureCode>,
+    flow: &HashMap<CodeId, FlowCode>,
+    cache: &mut HashMap<EntityId, OutputCache>,
+) {
+    let Ok(Some(Code { id, .. })) = world.try_get_cloned::<Code>(entity) else {
+        tracing::error!("Entity {:?} was despawned", entity);
+        return;
+    };
+
+    let Some(graph) = codes.get(&id) else {
+        tracing::error!("Code {id:?} is not found");
+        return;
+    };
+
+    let Some((node, outputs)) = graph.event_node(event) else {
+        tracing::error!("Event {event:?} is not found");
+        return;
+    };
+
+    if values.len() != outputs {
+        tracing::error!(
+            "Event {event:?} has {} values, but code node expects {}",
+            values.len(),
+            outputs
+        );
+        return;
+    }
+
+    let cache = cache.entry(entity).or_insert_with(OutputCache::new);
+
+    for (idx, value) in values.into_iter().enumerate() {
+        cache.set_value(
+            OutputId {
+                node,
+                output: 1 + idx,
+            },
+            value,
+        );
+    }
+
+    let resume = world.with_resource(CodeResumeQueue::new).tx.clone();
+    let guard = edict::tls::Guard::new(world);
+
+    let Ok(entity) = guard.entity(entity) else {
+        return;
+    };
+
+    execute_code_after(
+        entity,
+        id,
+        OutputId { node, output: 0 },
+        graph,
+        cache,
+        pure,
+        flow,
+        &resume,
+    );
+}
+
+/// Continues code execution after awaited flow code.
+///
+/// Execution is cancelled if the entity was despawned
+/// or its `Code` component was removed or replaced.
+fn on_code_resume(
+    resume: Resume,
+    world: &mut World,
+    codes: &HashMap<CodeId, CodeGraph>,
+    pure: &HashMap<CodeId, PureCode>,
+    flow: &HashMap<CodeId, FlowCode>,
+    cache: &mut HashMap<EntityId, OutputCache>,
+) {
+    match world.try_get_cloned::<Code>(resume.entity) {
+        Ok(Some(Code { id })) if id == resume.code => {}
+        _ => {
+            tracing::debug!("Code of entity {:?} was cancelled", resume.entity);
+            return;
+        }
+    }
+
+    let Some(graph) = codes.get(&resume.code) else {
+        tracing::error!("Code {:?} is not found", resume.code);
+        return;
+    };
+
+    let cache = cache.entry(resume.entity).or_insert_with(OutputCache::new);
+
+    let tx = world.with_resource(CodeResumeQueue::new).tx.clone();
+    let guard = edict::tls::Guard::new(world);
+
+    let Ok(entity) = guard.entity(resume.entity) else {
+        return;
+    };
+
+    execute_code_after(
+        entity,
+        resume.code,
+        resume.outflow,
+        graph,
+        cache,
+        pure,
+        flow,
+        &tx,
+    );
+}
+
+/// Runs code for events emitted to entities.
+///
+/// First continues codes after awaited flow codes that are ready.
+/// Then for every entity with `Code` component
+/// takes events from its queue and starts matching event nodes.
+///
+/// Cached outputs of entities without `Code` are dropped.
+pub fn run_code_events(
+    world: &mut World,
+    codes: &HashMap<CodeId, CodeGraph>,
+    pure: &HashMap<CodeId, PureCode>,
+    flow: &HashMap<CodeId, FlowCode>,
+    cache: &mut HashMap<EntityId, OutputCache>,
+) {
+    cache.retain(|&entity, _| matches!(world.try_get_cloned::<Code>(entity), Ok(Some(_))));
+
+    let resumes = world
+        .with_resource(CodeResumeQueue::new)
+        .rx
+        .try_iter()
+        .collect::<Vec<_>>();
+
+    for resume in resumes {
+        on_code_resume(resume, world, codes, pure, flow, cache);
+    }
+
+    let mut events = Vec::new();
+
+    for (e, _, queue) in world.view_mut::<(Entities, &Code, &mut EntityEventQueue)>() {
+        events.extend(queue.drain().map(|event| (e.id(), event)));
+    }
+
+    for (entity, event) in events {
+        on_code_event(
+            entity,
+            event.id,
+            event.values,
+            world,
+            codes,
+            pure,
+            flow,
+            cache,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        code::{builtin::START_EVENT, CodeNode, Output, Wire},
+        local_name_hash_id, name,
+        stid::WithStid,
+    };
+
+    use super::*;
+
+    #[derive(Clone, Copy, Component)]
+    struct Stored(u32);
+
+    const PRODUCE: CodeId = local_name_hash_id!(PRODUCE => CodeId);
+    const DOUBLE: CodeId = local_name_hash_id!(DOUBLE => CodeId);
+    const STORE: CodeId = local_name_hash_id!(STORE => CodeId);
+    const CODE: CodeId = local_name_hash_id!(CODE => CodeId);
+
+    fn produce(_: InputId, _: FlowEntity, _: &[Input], outputs: &mut [Output]) -> Continuation {
+        outputs[0].set(21u32);
+        Continuation::Continue(0)
+    }
+
+    fn double(_: FlowEntity, inputs: &[Input], outputs: &mut [Output]) {
+        outputs[0].set(inputs[0].get::<u32>() * 2);
+    }
+
+    fn store(
+        _: InputId,
+        mut entity: FlowEntity,
+        inputs: &[Input],
+        _: &mut [Output],
+    ) -> Continuation {
+        let _ = entity.insert(Stored(*inputs[0].get::<u32>()));
+        Continuation::Continue(0)
+    }
+
+    /// Flow node value output feeds pure node input
+    /// which feeds value input of the next flow node.
+    #[test]
+    fn flow_outputs_follow_outflows() {
+        let node = |id: CodeId, desc: CodeDesc| CodeNode {
+            id,
+            name: name!(node),
+            desc,
+        };
+
+        let wire = |from: usize, output: usize, to: usize, input: usize| Wire {
+            from: OutputId { node: from, output },
+            to: InputId { node: to, input },
+        };
+
+        let mut graph = CodeGraph::new(name!(values));
+        graph.nodes.insert(
+            0,
+            node(
+                CODE,
+                CodeDesc::Event {
+                    id: START_EVENT,
+                    outputs: Vec::new(),
+                },
+            ),
+        );
+        graph.nodes.insert(
+            1,
+            node(
+                PRODUCE,
+                CodeDesc::Flow {
+                    inflows: 1,
+                    outflows: 1,
+                    inputs: Vec::new(),
+                    outputs: vec![u32::stid()],
+                },
+            ),
+        );
+        graph.nodes.insert(
+            2,
+            node(
+                DOUBLE,
+                CodeDesc::Pure {
+                    inputs: vec![u32::stid()],
+                    outputs: vec![u32::stid()],
+                },
+            ),
+        );
+        graph.nodes.insert(
+            3,
+            node(
+                STORE,
+                CodeDesc::Flow {
+                    inflows: 1,
+                    outflows: 1,
+                    inputs: vec![u32::stid()],
+                    outputs: Vec::new(),
+                },
+            ),
+        );
+        graph.wires.push(wire(0, 0, 1, 0));
+        graph.wires.push(wire(1, 0, 3, 0));
+        graph.wires.push(wire(1, 1, 2, 0));
+        graph.wires.push(wire(2, 0, 3, 1));
+        assert!(graph.validate().is_empty());
+
+        let mut codes = HashMap::new();
+        codes.insert(CODE, graph);
+
+        let mut pure = HashMap::new();
+        pure.insert(DOUBLE, double as PureCode);
+
+        let mut flow = HashMap::new();
+        flow.insert(PRODUCE, produce as FlowCode);
+        flow.insert(STORE, store as FlowCode);
+
+        let mut world = World::new();
+        let entity = world.spawn((Code::new(CODE),)).id();
+        let mut cache = HashMap::new();
+
+        on_code_event(
+            entity,
+            START_EVENT,
+            Vec::new(),
+            &mut world,
+            &codes,
+            &pure,
+            &flow,
+            &mut cache,
+        );
+
+        let stored = world.try_get_cloned::<Stored>(entity).unwrap();
+        assert_eq!(stored.map(|s| s.0), Some(42));
+    }
+
+    #[derive(Clone, Copy, Component)]
+    struct Woke;
+
+    /// Runs code that sleeps for a second on start and then marks entity with [`Woke`].
+    struct SleepTest {
+        world: World,
+        flows: edict::flow::Flows,
+        rate: gametime::ClockRate,
+        codes: HashMap<CodeId, CodeGraph>,
+        flow: HashMap<CodeId, FlowCode>,
+        cache: HashMap<EntityId, OutputCache>,
+        entity: EntityId,
+    }
+
+    impl SleepTest {
+        const SLEEP: CodeId = local_name_hash_id!(SLEEP => CodeId);
+        const WAKE: CodeId = local_name_hash_id!(WAKE => CodeId);
+        const CODE: CodeId = local_name_hash_id!(CODE => CodeId);
+
+        fn sleep(_: InputId, _: FlowEntity, _: &[Input], _: &mut [Output]) -> Continuation {
+            Continuation::await_with(|mut entity| {
+                Box::pin(async move {
+                    crate::flow::sleep(gametime::timespan!(1 s), entity.world()).await;
+                    0
+                })
+            })
+        }
+
+        fn wake(_: InputId, mut entity: FlowEntity, _: &[Input], _: &mut [Output]) -> Continuation {
+            let _ = entity.insert(Woke);
+            Continuation::Continue(0)
+        }
+
+        fn new() -> Self {
+            let flow_node = |id: CodeId, name: &str| CodeNode {
+                id,
+                name: arcana_names::Name::from_str(name).unwrap(),
+                desc: CodeDesc::Flow {
+                    inflows: 1,
+                    outflows: 1,
+                    inputs: Vec::new(),
+                    outputs: Vec::new(),
+                },
+            };
+
+            let mut graph = CodeGraph::new(arcana_names::Name::from_str("sleep").unwrap());
+            graph.nodes.insert(
+                0,
+                CodeNode {
+                    id: Self::CODE,
+                    name: arcana_names::Name::from_str("start").unwrap(),
+                    desc: CodeDesc::Event {
+                        id: START_EVENT,
+                        outputs: Vec::new(),
+                    },
+                },
+            );
+            graph.nodes.insert(1, flow_node(Self::SLEEP, "sleep"));
+            graph.nodes.insert(2, flow_node(Self::WAKE, "wake"));
+            graph.wires.push(Wire {
+                from: OutputId { node: 0, output: 0 },
+                to: InputId { node: 1, input: 0 },
+            });
+            graph.wires.push(Wire {
+                from: OutputId { node: 1, output: 0 },
+                to: InputId { node: 2, input: 0 },
+            });
+
+            let mut codes = HashMap::new();
+            codes.insert(Self::CODE, graph);
+
+            let mut flow = HashMap::new();
+            flow.insert(Self::SLEEP, Self::sleep as FlowCode);
+            flow.insert(Self::WAKE, Self::wake as FlowCode);
+
+            let mut world = World::new();
+            crate::flow::init_flows(&mut world);
+
+            let mut rate = gametime::ClockRate::new();
+            world.insert_resource(rate.step(gametime::TimeSpan::ZERO));
+
+            let entity = world.spawn((Code::new(Self::CODE),)).id();
+
+            let mut test = SleepTest {
+                world,
+                flows: edict::flow::Flows::new(),
+                rate,
+                codes,
+                flow,
+                cache: HashMap::new(),
+                entity,
+            };
+
+            on_code_event(
+                entity,
+                START_EVENT,
+                Vec::new(),
+                &mut test.world,
+                &test.codes,
+                &HashMap::new(),
+                &test.flow,
+                &mut test.cache,
+            );
+
+            test
+        }
+
+        /// Advances time by 100 ms and runs codes and flows.
+        fn tick(&mut self) {
+            let step = self.rate.step(gametime::timespan!(100 ms));
+            self.world.insert_resource(step);
+
+            run_code_events(
+                &mut self.world,
+                &self.codes,
+                &HashMap::new(),
+                &self.flow,
+                &mut self.cache,
+            );
+
+            crate::flow::wake_flows(&mut self.world);
+            self.flows.execute(&mut self.world);
+        }
+
+        fn woke(&self) -> bool {
+            matches!(self.world.try_get_cloned::<Woke>(self.entity), Ok(Some(_)))
+        }
+    }
+
+    #[test]
+    fn code_resumes_after_sleep() {
+        let mut test = SleepTest::new();
+
+        for _ in 0..5 {
+            test.tick();
+            assert!(!test.woke());
+        }
+
+        for _ in 0..15 {
+            test.tick();
+        }
+        assert!(test.woke());
+    }
+
+    #[test]
+    fn code_cancelled_when_removed() {
+        let mut test = SleepTest::new();
+
+        for _ in 0..5 {
+            test.tick();
+        }
+
+        let _ = test.world.remove::<Code>(test.entity);
+
+        for _ in 0..15 {
+            test.tick();
+        }
+        assert!(!test.woke());
+        assert!(test.cache.is_empty());
+    }
+
+    #[test]
+    fn code_cancelled_when_despawned() {
+        let mut test = SleepTest::new();
+
+        for _ in 0..5 {
+            test.tick();
+        }
+
+        let _ = test.world.despawn(test.entity);
+
+        for _ in 0..15 {
+            test.tick();
+        }
+        assert!(test.cache.is_empty());
+    }
+}
@@ -0,0 +1,346 @@
+use std::fmt;
+
+use hashbrown::{HashMap, HashSet};
+
+use crate::stid::Stid;
+
+use super::{CodeDesc, CodeGraph, CodePin, InputId, Wire};
+
+/// Problem found in the code graph node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CodeDiagnostic {
+    /// Wire connects pin that does not exist.
+    InvalidWire(Wire),
+
+    /// Input has more than one wire.
+    MultipleWires { input: usize },
+
+    /// Outflow leads to more than one inflow.
+    MultipleOutflowWires { output: usize },
+
+    /// Flow pin is connected to value pin.
+    FlowMismatch { input: usize },
+
+    /// Value pin is connected to value pin of different type.
+    TypeMismatch {
+        input: usize,
+        expected: Stid,
+        found: Stid,
+    },
+
+    /// Value input is not connected.
+    MissingInput { input: usize },
+
+    /// Pure node depends on its own output.
+    PureCycle,
+}
+
+impl fmt::Display for CodeDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodeDiagnostic::InvalidWire(wire) => write!(
+                f,
+                "Wire from {}:{} to {}:{} connects missing pin",
+                wire.from.node, wire.from.output, wire.to.node, wire.to.input
+            ),
+            CodeDiagnostic::MultipleWires { input } => {
+                write!(f, "Input {input} has multiple wires")
+            }
+            CodeDiagnostic::MultipleOutflowWires { output } => {
+                write!(f, "Outflow {output} leads to multiple inflows")
+            }
+            CodeDiagnostic::FlowMismatch { input } => {
+                write!(f, "Input {input} connects flow and value pins")
+            }
+            CodeDiagnostic::TypeMismatch {
+                input,
+                expected,
+                found,
+            } => write!(f, "Input {input} expects {expected:?} but got {found:?}"),
+            CodeDiagnostic::MissingInput { input } => {
+                write!(f, "Input {input} is not connected")
+            }
+            CodeDiagnostic::PureCycle => write!(f, "Pure node depends on itself"),
+        }
+    }
+}
+
+/// Diagnostics of the code graph by node.
+///
+/// Code graph with any diagnostics must not be executed.
+#[derive(Clone, Debug, Default)]
+pub struct CodeDiagnostics {
+    nodes: HashMap<usize, Vec<CodeDiagnostic>>,
+}
+
+impl CodeDiagnostics {
+    pub fn new() -> Self {
+        CodeDiagnostics {
+            nodes: HashMap::new(),
+        }
+    }
+
+    /// Returns true if code graph has no problems.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns diagnostics of the node.
+    pub fn node(&self, node: usize) -> &[CodeDiagnostic] {
+        self.nodes.get(&node).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns iterator over all diagnostics with their nodes.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &CodeDiagnostic)> + '_ {
+        self.nodes
+            .iter()
+            .flat_map(|(&node, diagnostics)| diagnostics.iter().map(move |d| (node, d)))
+    }
+
+    fn push(&mut self, node: usize, diagnostic: CodeDiagnostic) {
+        self.nodes.entry(node).or_default().push(diagnostic);
+    }
+}
+
+impl CodeGraph {
+    /// Checks the code graph before execution.
+    ///
+    /// Reports wires between incompatible pins,
+    /// outflows leading to multiple inflows,
+    /// value inputs without wires and cycles of pure nodes.
+    pub fn validate(&self) -> CodeDiagnostics {
+        let mut diagnostics = CodeDiagnostics::new();
+        let mut connected = HashSet::new();
+        let mut outflows = HashSet::new();
+
+        for wire in &self.wires {
+            let from = self
+                .node(wire.from.node)
+                .and_then(|node| node.output_pin(wire.from.output));
+
+            let to = self
+                .node(wire.to.node)
+                .and_then(|node| node.input_pin(wire.to.input));
+
+            let (Some(from), Some(to)) = (from, to) else {
+                // Report to the node that exists.
+                let node = match self.node(wire.to.node) {
+                    Some(_) => wire.to.node,
+                    None => wire.from.node,
+                };
+                diagnostics.push(node, CodeDiagnostic::InvalidWire(*wire));
+                continue;
+            };
+
+            let input = wire.to.input;
+
+            // Inflows may be reached from many outflows.
+            if to != CodePin::Flow && !connected.insert(wire.to) {
+                diagnostics.push(wire.to.node, CodeDiagnostic::MultipleWires { input });
+            }
+
+            match (from, to) {
+                (CodePin::Flow, CodePin::Flow) => {
+                    // Execution continues with single inflow.
+                    if !outflows.insert(wire.from) {
+                        diagnostics.push(
+                            wire.from.node,
+                            CodeDiagnostic::MultipleOutflowWires {
+                                output: wire.from.output,
+                            },
+                        );
+                    }
+                }
+                (CodePin::Value(found), CodePin::Value(expected)) => {
+                    if found != expected {
+                        diagnostics.push(
+                            wire.to.node,
+                            CodeDiagnostic::TypeMismatch {
+                                input,
+                                expected,
+                                found,
+                            },
+                        );
+                    }
+                }
+                _ => diagnostics.push(wire.to.node, CodeDiagnostic::FlowMismatch { input }),
+            }
+        }
+
+        for (&idx, node) in &self.nodes {
+            for input in node.value_inputs() {
+                if !connected.contains(&InputId { node: idx, input }) {
+                    diagnostics.push(idx, CodeDiagnostic::MissingInput { input });
+                }
+            }
+        }
+
+        for node in self.pure_cycles() {
+            diagnostics.push(node, CodeDiagnostic::PureCycle);
+        }
+
+        diagnostics
+    }
+
+    /// Returns pure nodes that are part of a cycle.
+    fn pure_cycles(&self) -> HashSet<usize> {
+        let is_pure = |node: usize| {
+            matches!(
+                self.node(node),
+                Some(code_node) if matches!(code_node.desc, CodeDesc::Pure { .. })
+            )
+        };
+
+        // Pure dependencies of each pure node.
+        let mut deps: HashMap<usize, Vec<usize>> = HashMap::new();
+        for wire in &self.wires {
+            if is_pure(wire.from.node) && is_pure(wire.to.node) {
+                deps.entry(wire.to.node).or_default().push(wire.from.node);
+            }
+        }
+
+        let mut cycles = HashSet::new();
+        let mut visited = HashSet::new();
+        let mut stack = Vec::new();
+
+        for &node in deps.keys() {
+            visit_pure(node, &deps, &mut visited, &mut stack, &mut cycles);
+        }
+
+        cycles
+    }
+}
+
+/// Depth-first walk over pure dependencies.
+/// Nodes on the `stack` that are reached again form a cycle.
+fn visit_pure(
+    node: usize,
+    deps: &HashMap<usize, Vec<usize>>,
+    visited: &mut HashSet<usize>,
+    stack: &mut Vec<usize>,
+    cycles: &mut HashSet<usize>,
+) {
+    if let Some(pos) = stack.iter().position(|&n| n == node) {
+        cycles.extend(stack[pos..].iter().copied());
+        return;
+    }
+
+    if !visited.insert(node) {
+        return;
+    }
+
+    stack.push(node);
+    for &dep in deps.get(&node).into_iter().flatten() {
+        visit_pure(dep, deps, visited, stack, cycles);
+    }
+    stack.pop();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        code::{CodeId, CodeNode, OutputId},
+        local_name_hash_id, name,
+        stid::WithStid,
+    };
+
+    use super::*;
+
+    fn pure(inputs: Vec<Stid>, outputs: Vec<Stid>) -> CodeNode {
+        CodeNode {
+            id: local_name_hash_id!(pure => CodeId),
+            name: name!(pure),
+            desc: CodeDesc::Pure { inputs, outputs },
+        }
+    }
+
+    fn flow(outflows: usize) -> CodeNode {
+        CodeNode {
+            id: local_name_hash_id!(flow => CodeId),
+            name: name!(flow),
+            desc: CodeDesc::Flow {
+                inflows: 1,
+                outflows,
+                inputs: Vec::new(),
+                outputs: Vec::new(),
+            },
+        }
+    }
+
+    fn wire(from: usize, to: usize) -> Wire {
+        Wire {
+            from: OutputId {
+                node: from,
+                output: 0,
+            },
+            to: InputId { node: to, input: 0 },
+        }
+    }
+
+    #[test]
+    fn validate_reports_per_node() {
+        let mut graph = CodeGraph::new(name!(test));
+
+        // 0 -> 1 has mismatched types.
+        graph.nodes.insert(0, pure(vec![], vec![f32::stid()]));
+        graph.nodes.insert(1, pure(vec![u32::stid()], vec![]));
+        graph.wires.push(wire(0, 1));
+
+        // 2 is not connected.
+        graph.nodes.insert(2, pure(vec![f32::stid()], vec![]));
+
+        // 3 and 4 depend on each other.
+        graph
+            .nodes
+            .insert(3, pure(vec![f32::stid()], vec![f32::stid()]));
+        graph
+            .nodes
+            .insert(4, pure(vec![f32::stid()], vec![f32::stid()]));
+        graph.wires.push(wire(3, 4));
+        graph.wires.push(wire(4, 3));
+
+        let diagnostics = graph.validate();
+
+        assert!(diagnostics.node(0).is_empty());
+        assert_eq!(
+            diagnostics.node(1),
+            [CodeDiagnostic::TypeMismatch {
+                input: 0,
+                expected: u32::stid(),
+                found: f32::stid(),
+            }]
+        );
+        assert_eq!(
+            diagnostics.node(2),
+            [CodeDiagnostic::MissingInput { input: 0 }]
+        );
+        assert_eq!(diagnostics.node(3), [CodeDiagnostic::PureCycle]);
+        assert_eq!(diagnostics.node(4), [CodeDiagnostic::PureCycle]);
+    }
+
+    #[test]
+    fn validate_reports_outflow_to_many_inflows() {
+        let mut graph = CodeGraph::new(name!(test));
+
+        // Outflow of 0 leads to both 1 and 2.
+        graph.nodes.insert(0, flow(1));
+        graph.nodes.insert(1, flow(1));
+        graph.nodes.insert(2, flow(1));
+        graph.wires.push(wire(0, 1));
+        graph.wires.push(wire(0, 2));
+
+        // Inflow of 2 may be reached from many outflows.
+        graph.nodes.insert(3, flow(1));
+        graph.wires.push(wire(3, 2));
+
+        let diagnostics = graph.validate();
+
+        assert_eq!(
+            diagnostics.node(0),
+            [CodeDiagnostic::MultipleOutflowWires { output: 0 }]
+        );
+        assert!(diagnostics.node(1).is_empty());
+        assert!(diagnostics.node(2).is_empty());
+        assert!(diagnostics.node(3).is_empty());
+    }
+}
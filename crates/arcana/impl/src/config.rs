@@ -0,0 +1,49 @@
+//! Plugin configuration resources.
+//!
+//! Configs are resources that are edited in the editor
+//! as values of the data model and stored in the project.
+//! Game applies stored values to the resources on start.
+
+use edict::World;
+
+use crate::{
+    make_id,
+    model::{TypeModel, Value},
+};
+
+make_id!(pub ConfigId);
+
+/// Resource that is configured with values of the data model.
+///
+/// Value produced by [`Config::to_value`]
+/// must match the model returned by [`TypeModel::model`].
+pub trait Config: TypeModel + Default + Send + Sync + 'static {
+    /// Returns value that represents the config.
+    fn to_value(&self) -> Value;
+
+    /// Updates config from the value.
+    /// Fields that are missing or have unexpected kind are left unchanged.
+    fn set_value(&mut self, value: &Value);
+}
+
+/// Function that applies config value to the world.
+pub type ConfigFn = Box<dyn Fn(&mut World, &Value)>;
+
+/// Returns function that applies value to the config resource.
+/// Resource is inserted with default value if missing.
+pub fn config_fn<T>() -> ConfigFn
+where
+    T: Config,
+{
+    Box::new(|world, value| {
+        world.with_resource(T::default).set_value(value);
+    })
+}
+
+/// Returns field of the record value.
+pub fn config_field<'a>(value: &'a Value, name: &str) -> Option<&'a Value> {
+    match value {
+        Value::Map(fields) => fields.get(name),
+        _ => None,
+    }
+}
@@ -1,12 +1,24 @@
 //! Event system for the Arcana game engine.
 //!
+//! Events are emitted into [`Events`] resource under [`EventId`].
+//! There are two ways to handle an event. Subscribe and Listen.
+//!
+//! Subscribers are callbacks executed immediately when event is emitted.
+//! Listeners fetch events when actively polled from systems or flows.
+//!
+//! Events may also be emitted to specific entity with [`emit_to`].
+//! Such events are queued in [`EntityEventQueue`] component of the entity
+//! and are consumed by the entity's code.
 
 use std::{
     any::{Any, TypeId},
-    collections::VecDeque,
+    collections::{vec_deque, VecDeque},
+    task::{Poll, Waker},
 };
 
-use hashbrown::HashMap;
+use edict::{flow::FlowWorld, Component, EntityId, NoSuchEntity, World};
+use hashbrown::{HashMap, HashSet};
+use slab::Slab;
 
 use crate::make_id;
 
@@ -19,6 +31,18 @@ make_id! {
     pub EventId
 }
 
+/// Error returned when event is accessed with payload type
+/// different from the one it was emitted or subscribed with.
+#[derive(Debug, thiserror::Error)]
+#[error("Event {event:?} was emitted with different payload type")]
+pub struct PayloadTypeMismatch {
+    pub event: EventId,
+}
+
+/// Maximum number of events of one ID kept for listeners.
+/// Listeners that fall behind further skip oldest events.
+pub const EVENTS_CAPACITY: usize = 1000;
+
 pub struct Event<T: ?Sized = dyn Any> {
     value: T,
 }
@@ -35,13 +59,117 @@ impl Event {
     }
 }
 
+/// Identifies subscriber callback.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SubscriberId {
+    event: EventId,
+    idx: usize,
+}
+
 /// Listener for a specific event type.
+///
+/// Listener has a cursor into events of its ID.
+/// Polling returns events emitted after the cursor and advances it.
 pub struct EventListener {
     id: EventId,
-    last_event_idx: u64,
+    cursor: u64,
 }
 
-trait AnyEvents: Any {
+impl EventListener {
+    /// Returns ID of events this listener listens to.
+    pub fn id(&self) -> EventId {
+        self.id
+    }
+
+    /// Returns all events emitted since last poll.
+    ///
+    /// If listener fell behind more than [`EVENTS_CAPACITY`] events,
+    /// oldest events are skipped and reported by [`Received::skipped`].
+    ///
+    /// Returns error if events with this ID were emitted with different payload type.
+    pub fn poll<'a, T>(
+        &mut self,
+        events: &'a Events,
+    ) -> Result<Received<'a, T>, PayloadTypeMismatch>
+    where
+        T: Send + Sync + 'static,
+    {
+        let Some(typed) = events.typed::<T>(self.id)? else {
+            return Ok(Received {
+                skipped: 0,
+                iter: None,
+            });
+        };
+
+        let skipped = typed.offset.saturating_sub(self.cursor);
+        let start = (self.cursor.max(typed.offset) - typed.offset) as usize;
+        self.cursor = typed.next_idx();
+
+        Ok(Received {
+            skipped,
+            iter: Some(typed.events.range(start..)),
+        })
+    }
+
+    /// Returns next event emitted after the cursor.
+    /// Skips events that are no longer available.
+    ///
+    /// Returns error if events with this ID were emitted with different payload type.
+    pub fn next<'a, T>(&mut self, events: &'a Events) -> Result<Option<&'a T>, PayloadTypeMismatch>
+    where
+        T: Send + Sync + 'static,
+    {
+        let Some(typed) = events.typed::<T>(self.id)? else {
+            return Ok(None);
+        };
+
+        if self.cursor < typed.offset {
+            tracing::warn!(
+                "Listener for {:?} skipped {} events",
+                self.id,
+                typed.offset - self.cursor
+            );
+            self.cursor = typed.offset;
+        }
+
+        let Some(event) = typed.events.get((self.cursor - typed.offset) as usize) else {
+            return Ok(None);
+        };
+        self.cursor += 1;
+        Ok(Some(&event.value))
+    }
+}
+
+/// Events received by listener.
+pub struct Received<'a, T> {
+    skipped: u64,
+    iter: Option<vec_deque::Iter<'a, Event<T>>>,
+}
+
+impl<T> Received<'_, T> {
+    /// Returns number of events that were skipped
+    /// because listener fell behind.
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+}
+
+impl<'a, T> Iterator for Received<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        Some(&self.iter.as_mut()?.next()?.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.iter {
+            None => (0, Some(0)),
+            Some(iter) => iter.size_hint(),
+        }
+    }
+}
+
+trait AnyEvents: Any + Send + Sync {
     fn next_idx(&self) -> u64;
 }
 
@@ -61,14 +189,17 @@ impl dyn AnyEvents {
     }
 }
 
+type Subscriber<T> = Box<dyn FnMut(&T) + Send + Sync>;
+
 pub struct TypedEvents<T> {
     offset: u64,
     events: VecDeque<Event<T>>,
+    subscribers: Slab<Subscriber<T>>,
 }
 
 impl<T> AnyEvents for TypedEvents<T>
 where
-    T: 'static,
+    T: Send + Sync + 'static,
 {
     fn next_idx(&self) -> u64 {
         self.offset + self.events.len() as u64
@@ -78,37 +209,378 @@ where
 impl<T> TypedEvents<T> {
     pub fn new(offset: u64) -> Self {
         TypedEvents {
-            offset: 0,
+            offset,
             events: VecDeque::new(),
+            subscribers: Slab::new(),
         }
     }
 
     pub fn emit(&mut self, value: T) {
-        while self.events.len() >= 1000 {
+        for (_, subscriber) in self.subscribers.iter_mut() {
+            subscriber(&value);
+        }
+
+        while self.events.len() >= EVENTS_CAPACITY {
             self.events.pop_front();
             self.offset += 1;
         }
         self.events.push_back(Event { value });
     }
+
+    fn next_idx(&self) -> u64 {
+        self.offset + self.events.len() as u64
+    }
 }
 
 /// Events container.
+///
+/// Stored as a resource in the `World`.
 pub struct Events {
     map: HashMap<EventId, Box<dyn AnyEvents>>,
+
+    /// Events emitted since last dispatch.
+    emitted: HashSet<EventId>,
+
+    /// Wakers of flows waiting for events.
+    wakers: HashMap<EventId, Vec<Waker>>,
 }
 
 impl Events {
+    pub fn new() -> Self {
+        Events {
+            map: HashMap::new(),
+            emitted: HashSet::new(),
+            wakers: HashMap::new(),
+        }
+    }
+
     /// Emit event with value payload.
-    pub fn emit<T: 'static>(&mut self, event: EventId, value: T) {
+    ///
+    /// Subscribers are called immediately.
+    /// Listeners will receive the event when polled.
+    ///
+    /// Returns error if event was emitted or subscribed with different payload type.
+    pub fn emit<T>(&mut self, event: EventId, value: T) -> Result<(), PayloadTypeMismatch>
+    where
+        T: Send + Sync + 'static,
+    {
+        let typed_events = self.typed_mut::<T>(event)?;
+        typed_events.emit(value);
+        self.emitted.insert(event);
+        Ok(())
+    }
+
+    /// Creates new listener for the event.
+    /// It will receive events emitted after this call.
+    pub fn listen(&self, event: EventId) -> EventListener {
+        let cursor = self.map.get(&event).map_or(0, |events| events.next_idx());
+        EventListener { id: event, cursor }
+    }
+
+    /// Subscribes callback to the event.
+    /// Callback is called each time event is emitted.
+    ///
+    /// Returns error if event was emitted or subscribed with different payload type.
+    pub fn subscribe<T>(
+        &mut self,
+        event: EventId,
+        callback: impl FnMut(&T) + Send + Sync + 'static,
+    ) -> Result<SubscriberId, PayloadTypeMismatch>
+    where
+        T: Send + Sync + 'static,
+    {
+        let typed_events = self.typed_mut::<T>(event)?;
+        let idx = typed_events.subscribers.insert(Box::new(callback));
+        Ok(SubscriberId { event, idx })
+    }
+
+    /// Removes subscriber callback.
+    ///
+    /// Returns error if subscriber was added with different payload type.
+    pub fn unsubscribe<T>(&mut self, id: SubscriberId) -> Result<(), PayloadTypeMismatch>
+    where
+        T: Send + Sync + 'static,
+    {
+        if let Some(typed_events) = self.typed_mut_opt::<T>(id.event)? {
+            let _ = typed_events.subscribers.try_remove(id.idx);
+        }
+        Ok(())
+    }
+
+    /// Wakes flows waiting for events emitted since last dispatch.
+    pub fn dispatch(&mut self) {
+        for event in self.emitted.drain() {
+            if let Some(wakers) = self.wakers.get_mut(&event) {
+                for waker in wakers.drain(..) {
+                    waker.wake();
+                }
+            }
+        }
+    }
+
+    fn add_waker(&mut self, event: EventId, waker: &Waker) {
+        let wakers = self.wakers.entry(event).or_default();
+        if !wakers.iter().any(|w| w.will_wake(waker)) {
+            wakers.push(waker.clone());
+        }
+    }
+
+    fn typed<T>(&self, event: EventId) -> Result<Option<&TypedEvents<T>>, PayloadTypeMismatch>
+    where
+        T: Send + Sync + 'static,
+    {
+        let Some(events) = self.map.get(&event) else {
+            return Ok(None);
+        };
+        if !events.is::<T>() {
+            return Err(PayloadTypeMismatch { event });
+        }
+        Ok(Some(unsafe { events.downcast_ref::<T>() }))
+    }
+
+    fn typed_mut_opt<T>(
+        &mut self,
+        event: EventId,
+    ) -> Result<Option<&mut TypedEvents<T>>, PayloadTypeMismatch>
+    where
+        T: Send + Sync + 'static,
+    {
+        let Some(events) = self.map.get_mut(&event) else {
+            return Ok(None);
+        };
+        if !events.is::<T>() {
+            return Err(PayloadTypeMismatch { event });
+        }
+        Ok(Some(unsafe { events.downcast_mut::<T>() }))
+    }
+
+    fn typed_mut<T>(&mut self, event: EventId) -> Result<&mut TypedEvents<T>, PayloadTypeMismatch>
+    where
+        T: Send + Sync + 'static,
+    {
         let events = self
             .map
             .entry(event)
             .or_insert_with(|| Box::new(TypedEvents::<T>::new(0)));
         if !events.is::<T>() {
-            let offset = events.next_idx();
-            *events = Box::new(TypedEvents::<T>::new(offset))
+            return Err(PayloadTypeMismatch { event });
         }
-        let typed_events = unsafe { events.downcast_mut::<T>() };
-        typed_events.emit(value);
+        Ok(unsafe { events.downcast_mut::<T>() })
+    }
+}
+
+/// Waits for next event of the listener and returns its clone.
+///
+/// Returns error if events with listener's ID were emitted with different payload type.
+pub async fn next_event<T>(
+    listener: &mut EventListener,
+    mut world: FlowWorld<'_>,
+) -> Result<T, PayloadTypeMismatch>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    world
+        .poll_fn(|world, cx| {
+            let mut events = world.expect_resource_mut::<Events>();
+
+            match listener.next::<T>(&events) {
+                Err(err) => Poll::Ready(Err(err)),
+                Ok(Some(value)) => Poll::Ready(Ok(value.clone())),
+                Ok(None) => {
+                    events.add_waker(listener.id, cx.waker());
+                    Poll::Pending
+                }
+            }
+        })
+        .await
+}
+
+/// Inserts [`Events`] resource into the world.
+pub fn init_events(world: &mut World) {
+    world.insert_resource(Events::new());
+}
+
+/// Dispatches events emitted since last call.
+/// Wakes flows waiting for them.
+///
+/// Runs once per frame after flows.
+pub fn dispatch_events(world: &mut World) {
+    if let Some(mut events) = world.get_resource_mut::<Events>() {
+        events.dispatch();
+    }
+}
+
+/// Event emitted to specific entity.
+pub struct EntityEvent {
+    /// Event ID.
+    pub id: EventId,
+
+    /// Event payload values.
+    /// Matches output values declared by event code node.
+    pub values: Vec<Box<dyn Any + Send + Sync>>,
+}
+
+/// Queue of events emitted to the entity.
+#[derive(Default, Component)]
+pub struct EntityEventQueue {
+    events: VecDeque<EntityEvent>,
+}
+
+impl EntityEventQueue {
+    pub fn new() -> Self {
+        EntityEventQueue {
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Returns true if there are no queued events.
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn push(&mut self, event: EntityEvent) {
+        self.events.push_back(event);
+    }
+
+    /// Takes all queued events.
+    pub fn drain(&mut self) -> vec_deque::Drain<'_, EntityEvent> {
+        self.events.drain(..)
+    }
+}
+
+/// Payload of events emitted to entities.
+/// Implemented for tuples of values.
+pub trait EventValues {
+    fn into_values(self) -> Vec<Box<dyn Any + Send + Sync>>;
+}
+
+macro_rules! event_values {
+    ($($a:ident)*) => {
+        impl<$($a,)*> EventValues for ($($a,)*)
+        where
+            $($a: Send + Sync + 'static,)*
+        {
+            fn into_values(self) -> Vec<Box<dyn Any + Send + Sync>> {
+                #![allow(non_snake_case)]
+
+                let ($($a,)*) = self;
+                vec![$(Box::new($a) as Box<dyn Any + Send + Sync>,)*]
+            }
+        }
+    };
+}
+
+for_tuple!(event_values);
+
+/// Emits event to specific entity.
+///
+/// Event is pushed into entity's [`EntityEventQueue`],
+/// which is inserted if missing.
+pub fn emit_to(
+    world: &mut World,
+    entity: EntityId,
+    event: EventId,
+    payload: impl EventValues,
+) -> Result<(), NoSuchEntity> {
+    let event = EntityEvent {
+        id: event,
+        values: payload.into_values(),
+    };
+
+    if let Ok(queue) = world.get::<&mut EntityEventQueue>(entity) {
+        queue.push(event);
+        return Ok(());
+    }
+
+    let mut queue = EntityEventQueue::new();
+    queue.push(event);
+    world.insert(entity, queue)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+
+    use crate::local_name_hash_id;
+
+    use super::*;
+
+    const TEST_EVENT: EventId = local_name_hash_id!(TEST_EVENT => EventId);
+
+    #[test]
+    fn subscribe_and_emit() {
+        let mut events = Events::new();
+
+        let sum = Arc::new(AtomicU32::new(0));
+        let id = events
+            .subscribe::<u32>(TEST_EVENT, {
+                let sum = sum.clone();
+                move |value| {
+                    sum.fetch_add(*value, Ordering::Relaxed);
+                }
+            })
+            .unwrap();
+
+        events.emit(TEST_EVENT, 1u32).unwrap();
+        events.emit(TEST_EVENT, 2u32).unwrap();
+        assert_eq!(sum.load(Ordering::Relaxed), 3);
+
+        events.unsubscribe::<u32>(id).unwrap();
+        events.emit(TEST_EVENT, 4u32).unwrap();
+        assert_eq!(sum.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn listen_and_poll() {
+        let mut events = Events::new();
+        events.emit(TEST_EVENT, 1u32).unwrap();
+
+        let mut listener = events.listen(TEST_EVENT);
+        events.emit(TEST_EVENT, 2u32).unwrap();
+        events.emit(TEST_EVENT, 3u32).unwrap();
+
+        let received = listener.poll::<u32>(&events).unwrap();
+        assert_eq!(received.skipped(), 0);
+        assert_eq!(received.copied().collect::<Vec<_>>(), [2, 3]);
+
+        assert_eq!(listener.poll::<u32>(&events).unwrap().count(), 0);
+
+        events.emit(TEST_EVENT, 4u32).unwrap();
+        assert_eq!(listener.next::<u32>(&events).unwrap(), Some(&4));
+        assert_eq!(listener.next::<u32>(&events).unwrap(), None);
+    }
+
+    #[test]
+    fn poll_skips_overflown() {
+        let mut events = Events::new();
+        let mut listener = events.listen(TEST_EVENT);
+
+        for i in 0..EVENTS_CAPACITY as u32 + 10 {
+            events.emit(TEST_EVENT, i).unwrap();
+        }
+
+        let mut received = listener.poll::<u32>(&events).unwrap();
+        assert_eq!(received.skipped(), 10);
+        assert_eq!(received.next(), Some(&10));
+    }
+
+    #[test]
+    fn payload_type_mismatch() {
+        let mut events = Events::new();
+        events.emit(TEST_EVENT, 1u32).unwrap();
+
+        let mut listener = events.listen(TEST_EVENT);
+        events.emit(TEST_EVENT, 2u32).unwrap();
+
+        assert!(events.emit(TEST_EVENT, "str").is_err());
+        assert!(events.subscribe::<f32>(TEST_EVENT, |_| {}).is_err());
+        assert!(listener.poll::<u64>(&events).is_err());
+        assert!(listener.next::<u64>(&events).is_err());
+
+        // Events of the original type are kept.
+        assert_eq!(listener.next::<u32>(&events).unwrap(), Some(&2));
     }
 }
@@ -0,0 +1,267 @@
+//! Fonts imported into signed distance field glyph atlases.
+
+use std::{
+    future::{ready, Ready},
+    sync::Arc,
+};
+
+use arcana_importers::texture::{TextureData, TextureDecodeError};
+use edict::Component;
+use hashbrown::HashMap;
+
+use crate::{
+    assets::BobBuilder,
+    texture::{upload_texture, Texture},
+};
+
+#[derive(serde::Deserialize)]
+pub struct GlyphMeta {
+    char: char,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    xmin: f32,
+    ymin: f32,
+    advance: f32,
+}
+
+#[derive(serde::Deserialize)]
+pub struct FontMeta {
+    size: f32,
+    spread: f32,
+    ascent: f32,
+    descent: f32,
+    line_height: f32,
+    glyphs: Vec<GlyphMeta>,
+}
+
+/// Glyph in the font atlas.
+#[derive(Clone, Copy, Debug)]
+pub struct Glyph {
+    /// Region of the atlas in normalized coordinates.
+    pub uv_min: [f32; 2],
+    pub uv_max: [f32; 2],
+
+    /// Offset of the top-left corner of the glyph from the pen position
+    /// in pixels of the rasterized font.
+    /// Y axis points down.
+    pub offset: [f32; 2],
+
+    /// Size of the glyph in pixels of the rasterized font.
+    pub extent: [f32; 2],
+
+    /// Horizontal advance after the glyph.
+    pub advance: f32,
+}
+
+/// Glyph quad produced by text layout.
+#[derive(Clone, Copy, Debug)]
+pub struct GlyphQuad {
+    /// Corners of the quad.
+    /// Origin is at top-left corner of the text and Y axis points down.
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+
+    /// Region of the atlas.
+    pub uv_min: [f32; 2],
+    pub uv_max: [f32; 2],
+}
+
+struct FontMetrics {
+    size: f32,
+    spread: f32,
+    ascent: f32,
+    descent: f32,
+    line_height: f32,
+    glyphs: HashMap<char, Glyph>,
+}
+
+/// Font asset.
+///
+/// Contains single-channel atlas with signed distance field of glyphs
+/// and metrics required to lay out text.
+/// Cloning is cheap.
+#[derive(Clone)]
+pub struct Font {
+    pub atlas: Texture,
+    metrics: Arc<FontMetrics>,
+}
+
+impl Component for Font {
+    fn name() -> &'static str {
+        "Font"
+    }
+}
+
+impl Font {
+    /// Size in pixels at which glyphs were rasterized.
+    pub fn size(&self) -> f32 {
+        self.metrics.size
+    }
+
+    /// Maximum distance in pixels of the rasterized font
+    /// encoded in the distance field.
+    pub fn spread(&self) -> f32 {
+        self.metrics.spread
+    }
+
+    /// Distance from baseline to the top of the line for font of given size.
+    pub fn ascent(&self, size: f32) -> f32 {
+        self.metrics.ascent * size / self.metrics.size
+    }
+
+    /// Distance from baseline to the bottom of the line for font of given size.
+    /// Typically negative.
+    pub fn descent(&self, size: f32) -> f32 {
+        self.metrics.descent * size / self.metrics.size
+    }
+
+    /// Distance between baselines for font of given size.
+    pub fn line_height(&self, size: f32) -> f32 {
+        self.metrics.line_height * size / self.metrics.size
+    }
+
+    /// Returns glyph for the character.
+    pub fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.metrics.glyphs.get(&c)
+    }
+
+    /// Checks if both values refer to the same font.
+    pub fn same(&self, other: &Font) -> bool {
+        Arc::ptr_eq(&self.metrics, &other.metrics)
+    }
+
+    /// Lays out text using font of given size.
+    ///
+    /// Lines are separated by `'\n'`.
+    /// Characters missing in the font are skipped.
+    pub fn layout(&self, text: &str, size: f32) -> Vec<GlyphQuad> {
+        let scale = size / self.metrics.size;
+        let mut quads = Vec::with_capacity(text.len());
+
+        let mut x = 0.0;
+        let mut baseline = self.metrics.ascent * scale;
+
+        for c in text.chars() {
+            if c == '\n' {
+                x = 0.0;
+                baseline += self.metrics.line_height * scale;
+                continue;
+            }
+
+            let Some(glyph) = self.metrics.glyphs.get(&c) else {
+                continue;
+            };
+
+            if glyph.extent[0] > 0.0 && glyph.extent[1] > 0.0 {
+                let min = [
+                    x + glyph.offset[0] * scale,
+                    baseline + glyph.offset[1] * scale,
+                ];
+
+                quads.push(GlyphQuad {
+                    min,
+                    max: [
+                        min[0] + glyph.extent[0] * scale,
+                        min[1] + glyph.extent[1] * scale,
+                    ],
+                    uv_min: glyph.uv_min,
+                    uv_max: glyph.uv_max,
+                });
+            }
+
+            x += glyph.advance * scale;
+        }
+
+        quads
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FontDecodeError {
+    #[error("Font data is truncated")]
+    Truncated,
+
+    #[error("Failed to decode font metadata: {0}")]
+    Meta(#[from] serde_json::Error),
+
+    #[error("Failed to decode font atlas: {0}")]
+    Atlas(#[from] TextureDecodeError),
+}
+
+impl argosy::Asset for Font {
+    type Decoded = (FontMeta, TextureData);
+    type DecodeError = FontDecodeError;
+    type BuildError = mev::OutOfMemory;
+    type Fut = Ready<Result<Self::Decoded, FontDecodeError>>;
+
+    fn name() -> &'static str {
+        "font"
+    }
+
+    fn decode(bytes: Box<[u8]>, _: &argosy::Loader) -> Self::Fut {
+        ready(decode_font(&bytes))
+    }
+}
+
+/// Decodes data produced by font importer.
+/// It is little-endian `u32` length of JSON metadata,
+/// the metadata itself and atlas as texture artifact.
+fn decode_font(bytes: &[u8]) -> Result<(FontMeta, TextureData), FontDecodeError> {
+    let (len, rest) = bytes
+        .split_first_chunk::<4>()
+        .ok_or(FontDecodeError::Truncated)?;
+    let len = u32::from_le_bytes(*len) as usize;
+
+    if rest.len() < len {
+        return Err(FontDecodeError::Truncated);
+    }
+
+    let (meta, atlas) = rest.split_at(len);
+    let meta = serde_json::from_slice(meta)?;
+    let atlas = TextureData::decode(atlas)?;
+
+    Ok((meta, atlas))
+}
+
+impl argosy::AssetBuild<BobBuilder<'_>> for Font {
+    fn build(builder: &mut BobBuilder, decoded: Self::Decoded) -> Result<Self, mev::OutOfMemory> {
+        let (meta, atlas) = decoded;
+        let (width, height) = (atlas.width, atlas.height);
+        let image = upload_texture(builder, "font-atlas", atlas)?;
+
+        let width = width.max(1) as f32;
+        let height = height.max(1) as f32;
+
+        let glyphs = meta
+            .glyphs
+            .iter()
+            .map(|g| {
+                let glyph = Glyph {
+                    uv_min: [g.x as f32 / width, g.y as f32 / height],
+                    uv_max: [
+                        (g.x + g.width) as f32 / width,
+                        (g.y + g.height) as f32 / height,
+                    ],
+                    offset: [g.xmin, -(g.ymin + g.height as f32)],
+                    extent: [g.width as f32, g.height as f32],
+                    advance: g.advance,
+                };
+                (g.char, glyph)
+            })
+            .collect();
+
+        Ok(Font {
+            atlas: Texture { image },
+            metrics: Arc::new(FontMetrics {
+                size: meta.size,
+                spread: meta.spread,
+                ascent: meta.ascent,
+                descent: meta.descent,
+                line_height: meta.line_height,
+                glyphs,
+            }),
+        })
+    }
+}
@@ -0,0 +1,698 @@
+//! Standalone game runtime.
+//!
+//! Shipped games link plugins statically and call [`run`] from `main`.
+//! Project data required to run the game is written by the editor
+//! into [`GAME_DATA_FILE`] next to `Arcana.toml`.
+//! Assets are loaded from the asset store in the same directory.
+//!
+//! Game may run headless, without window, GPU device and work graph execution.
+//! This is useful for dedicated servers, simulation tests and bots.
+//! Pass `--headless` argument to the game executable or call [`run_headless`].
+
+use std::{
+    collections::VecDeque,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use arcana_names::{Ident, Name};
+use blink_alloc::Blink;
+use edict::{prelude::ActionBufferSliceExt, EntityId, World};
+use gametime::{
+    Clock, ClockRate, ClockStep, FrequencyNumExt, FrequencyTicker, TimeSpan, TimeStamp,
+};
+use hashbrown::{HashMap, HashSet};
+use winit::{
+    event::{Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    window::Window,
+};
+
+use crate::{
+    assets::{build_assets, Assets},
+    code::{run_code_events, CodeGraph, CodeId, OutputCache},
+    config::ConfigId,
+    events::{dispatch_events, init_events},
+    flow::{init_flows, wake_flows, Flows},
+    input::{FilterId, Input, ViewportInput},
+    model::Value,
+    plugin::{ArcanaPlugin, PluginsHub, SystemId},
+    viewport::Viewport,
+    work::{
+        main_present, run_presents, Cycle, Edge, JobDesc, JobId, JobIdx, ParamSourceId, PinId,
+        PresentError, PresentFlags, WorkGraph,
+    },
+};
+
+/// Marker resource.
+/// When this resource is present in the world,
+/// the game will quit.
+#[derive(Debug)]
+pub struct Quit;
+
+/// Name of the file with game data.
+/// Editor writes it into project root directory.
+pub const GAME_DATA_FILE: &str = "Arcana.game.json";
+
+/// Work graph as it is configured in the project.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct WorkGraphData {
+    /// Jobs in the graph.
+    pub jobs: Vec<(JobIdx, JobId, JobDesc, HashMap<Name, Value>)>,
+
+    /// Edges between job pins.
+    pub edges: Vec<Edge>,
+
+    /// Job params bound to param sources.
+    #[serde(default)]
+    pub bindings: Vec<(JobIdx, Name, ParamSourceId)>,
+
+    /// Which pin to present to the main viewport.
+    pub present: Option<PinId>,
+
+    /// Named pins presented to viewports of entities
+    /// with matching `Present` component.
+    #[serde(default)]
+    pub presents: Vec<(Name, PinId)>,
+}
+
+impl WorkGraphData {
+    /// Builds work-graph from the data.
+    pub fn make_workgraph(&self) -> Result<WorkGraph, Cycle> {
+        let jobs = self
+            .jobs
+            .iter()
+            .map(|(idx, id, desc, params)| (*idx, (*id, desc.clone(), params.clone())))
+            .collect();
+
+        let edges = self.edges.iter().copied().collect();
+
+        let mut workgraph = WorkGraph::new(jobs, edges)?;
+
+        for &(job, name, source) in &self.bindings {
+            if !workgraph.bind_param(job, name, source) {
+                tracing::warn!("Param '{name}' is bound to missing job {job:?}");
+            }
+        }
+
+        Ok(workgraph)
+    }
+
+    /// Returns all presents of the work graph.
+    /// Main present is named [`main_present`].
+    pub fn all_presents(&self) -> Vec<(Name, PinId)> {
+        let main = self.present.map(|pin| (main_present(), pin));
+        main.into_iter()
+            .chain(self.presents.iter().copied())
+            .collect()
+    }
+}
+
+/// Project data required to run the game without the editor.
+///
+/// Editor produces it from its own project data each time it is saved.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct GameData {
+    /// Name of the game.
+    pub name: String,
+
+    /// Set of enabled plugins.
+    pub enabled_plugins: HashSet<Ident>,
+
+    /// Fixed-rate systems in execution order.
+    pub fix_schedule: Vec<SystemId>,
+
+    /// Variable-rate systems in execution order.
+    pub var_schedule: Vec<SystemId>,
+
+    /// Enabled input filters in funnel order.
+    pub filters: Vec<FilterId>,
+
+    /// Work graph.
+    pub workgraph: WorkGraphData,
+
+    /// Code graphs.
+    #[serde(default)]
+    pub codes: HashMap<CodeId, CodeGraph>,
+
+    /// Values of plugin configs.
+    #[serde(default)]
+    pub configs: HashMap<ConfigId, Value>,
+}
+
+impl GameData {
+    /// Loads game data from the project root directory.
+    pub fn load(root: &Path) -> std::io::Result<Self> {
+        let file = std::fs::File::open(root.join(GAME_DATA_FILE))?;
+        serde_json::from_reader(file).map_err(std::io::Error::from)
+    }
+
+    /// Stores game data into the project root directory.
+    pub fn store(&self, root: &Path) -> std::io::Result<()> {
+        let bytes = serde_json::to_vec(self).map_err(std::io::Error::from)?;
+        std::fs::write(root.join(GAME_DATA_FILE), bytes)
+    }
+}
+
+/// Runs systems in scheduled order.
+/// Systems that are not found in the hub are skipped.
+pub fn run_systems(schedule: &[SystemId], world: &mut World, hub: &mut PluginsHub) {
+    let mut buffers = Vec::new();
+
+    for id in schedule {
+        if let Some(system) = hub.systems.get_mut(id) {
+            system.run(world, &mut buffers);
+        }
+    }
+
+    buffers.execute_all(world);
+}
+
+/// Passes input through filters in funnel order.
+/// Returns true if input was consumed by a filter.
+pub fn filter_input(
+    filters: &[FilterId],
+    hub: &mut PluginsHub,
+    blink: &Blink,
+    world: &mut World,
+    input: &Input,
+) -> bool {
+    for id in filters {
+        if let Some(filter) = hub.filters.get_mut(id) {
+            if filter.filter(blink, world, input) {
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+/// Selects enabled plugins and sorts them placing dependencies first.
+///
+/// Plugin is activated if it is enabled and all its dependencies are active.
+///
+/// # Panics
+///
+/// Panics if there is a circular dependency between active plugins.
+pub fn active_plugins<'a>(
+    plugins: &[(Ident, &'a dyn ArcanaPlugin)],
+    enabled: &HashSet<Ident>,
+) -> Vec<(Ident, &'a dyn ArcanaPlugin)> {
+    let get = |name: Ident| plugins.iter().find(|(n, _)| *n == name).map(|(_, p)| *p);
+
+    let mut queue = plugins
+        .iter()
+        .map(|(name, _)| *name)
+        .filter(|name| enabled.contains(name))
+        .collect::<VecDeque<_>>();
+
+    let mut pending = HashSet::new();
+    let mut sorted = HashSet::new();
+    let mut inactive = HashSet::new();
+    let mut result = Vec::new();
+
+    'queue: while let Some(name) = queue.pop_front() {
+        if sorted.contains(&name) || inactive.contains(&name) {
+            continue;
+        }
+        pending.insert(name);
+
+        let plugin = get(name).unwrap();
+
+        let mut defer = false;
+        for (dep_name, _) in plugin.dependencies() {
+            if sorted.contains(&dep_name) {
+                continue;
+            }
+
+            if inactive.contains(&dep_name)
+                || !enabled.contains(&dep_name)
+                || get(dep_name).is_none()
+            {
+                tracing::warn!(
+                    "Plugin '{name}' is inactive because dependency '{dep_name}' is not active"
+                );
+                inactive.insert(name);
+                continue 'queue;
+            }
+
+            if pending.contains(&dep_name) {
+                panic!("Circular dependency between plugins: {name} <-> {dep_name}");
+            }
+
+            if !defer {
+                defer = true;
+                queue.push_front(name);
+            }
+
+            queue.push_front(dep_name);
+        }
+
+        if !defer {
+            sorted.insert(name);
+            result.push((name, plugin));
+        }
+    }
+
+    result
+}
+
+/// Instance of the game.
+///
+/// Owns the world, plugins hub and work graph
+/// and drives fixed and variable rate updates.
+///
+/// Headless game has no viewport and never executes work graph.
+pub struct Game {
+    /// Own ECS world.
+    world: World,
+
+    blink: Blink,
+
+    /// Plugins initialization hub.
+    hub: PluginsHub,
+
+    /// Active plugins in initialization order.
+    plugins: Vec<(Ident, &'static dyn ArcanaPlugin)>,
+
+    /// Project data.
+    data: GameData,
+
+    /// Specifies frequency of fixed updates.
+    fix: FrequencyTicker,
+
+    /// Time of the last fixed update.
+    last_fix: TimeStamp,
+
+    /// Limits variable updates.
+    lim: FrequencyTicker,
+
+    /// Game rate.
+    rate: ClockRate,
+
+    /// Flows to run on each tick.
+    flows: Flows,
+
+    /// Cached code outputs per entity.
+    code_cache: HashMap<EntityId, OutputCache>,
+
+    /// Work graph.
+    workgraph: WorkGraph,
+
+    /// Viewport to render into.
+    /// `None` for headless game.
+    viewport: Option<Viewport>,
+}
+
+impl Game {
+    /// Creates new game instance that renders into the viewport.
+    /// Initializes active plugins in dependency order.
+    pub fn new(
+        plugins: &[(Ident, &'static dyn ArcanaPlugin)],
+        data: GameData,
+        viewport: Viewport,
+    ) -> Self {
+        Self::with_viewport(plugins, data, Some(viewport))
+    }
+
+    /// Creates new headless game instance.
+    /// Initializes active plugins in dependency order.
+    ///
+    /// Headless game runs systems, flows and input filters,
+    /// but has no viewport and does not require GPU device.
+    pub fn new_headless(plugins: &[(Ident, &'static dyn ArcanaPlugin)], data: GameData) -> Self {
+        Self::with_viewport(plugins, data, None)
+    }
+
+    fn with_viewport(
+        plugins: &[(Ident, &'static dyn ArcanaPlugin)],
+        mut data: GameData,
+        viewport: Option<Viewport>,
+    ) -> Self {
+        let mut world = World::new();
+        let mut hub = PluginsHub::new();
+
+        let rate = ClockRate::new();
+        let fix = FrequencyTicker::new(60.hz(), rate.now());
+        let lim = FrequencyTicker::new(60.hz(), rate.now());
+
+        init_flows(&mut world);
+        init_events(&mut world);
+        world.insert_resource(PresentFlags::new());
+
+        let plugins = active_plugins(plugins, &data.enabled_plugins);
+        for (name, plugin) in &plugins {
+            tracing::debug!("Initializing plugin '{name}'");
+            plugin.init(&mut world, &mut hub);
+        }
+
+        for (&id, value) in &data.configs {
+            if !hub.apply_config(id, value, &mut world) {
+                tracing::warn!("Config {id:?} is not found");
+            }
+        }
+
+        // Invalid codes are not executed.
+        data.codes.retain(|id, code| {
+            let diagnostics = code.validate();
+            for (node, diagnostic) in diagnostics.iter() {
+                tracing::error!("Code {id:?} node {node}: {diagnostic}");
+            }
+            diagnostics.is_empty()
+        });
+
+        let workgraph = match data.workgraph.make_workgraph() {
+            Ok(workgraph) => workgraph,
+            Err(err) => {
+                tracing::error!("Failed to make workgraph: {err:?}");
+                WorkGraph::new(HashMap::new(), HashSet::new()).unwrap()
+            }
+        };
+
+        Game {
+            world,
+            blink: Blink::new(),
+            hub,
+            plugins,
+            data,
+            fix,
+            last_fix: rate.now(),
+            lim,
+            rate,
+            flows: Flows::new(),
+            code_cache: HashMap::new(),
+            workgraph,
+            viewport,
+        }
+    }
+
+    pub fn rate(&self) -> &ClockRate {
+        &self.rate
+    }
+
+    pub fn rate_mut(&mut self) -> &mut ClockRate {
+        &mut self.rate
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    /// Opens asset store of the project at `root` for this game.
+    ///
+    /// Sources are not watched for changes in shipped games.
+    pub fn set_assets_root(&mut self, root: &Path) {
+        match Assets::with_root(root) {
+            Ok(assets) => self.world.insert_resource(assets),
+            Err(err) => tracing::error!("{err}"),
+        }
+    }
+
+    /// Returns true if game runs without viewport.
+    pub fn is_headless(&self) -> bool {
+        self.viewport.is_none()
+    }
+
+    pub fn quit(&mut self) {
+        self.world.insert_resource(Quit);
+    }
+
+    pub fn should_quit(&self) -> bool {
+        self.world.get_resource::<Quit>().is_some()
+    }
+
+    pub fn tick(&mut self, span: TimeSpan) {
+        let step = self.rate.step(span);
+
+        // Fixed updates step exactly by fix period.
+        self.fix.with_ticks(step.step, |fix_now| {
+            self.world.insert_resource(ClockStep {
+                now: fix_now,
+                step: fix_now - self.last_fix,
+            });
+            self.last_fix = fix_now;
+            run_systems(&self.data.fix_schedule, &mut self.world, &mut self.hub);
+        });
+
+        self.world.insert_resource(step);
+        if self.lim.tick_count(step.step) > 0 {
+            run_systems(&self.data.var_schedule, &mut self.world, &mut self.hub);
+        }
+
+        run_code_events(
+            &mut self.world,
+            &self.data.codes,
+            &self.hub.pure_fns,
+            &self.hub.flow_fns,
+            &mut self.code_cache,
+        );
+
+        wake_flows(&mut self.world);
+        self.flows.execute(&mut self.world);
+        dispatch_events(&mut self.world);
+    }
+
+    pub fn on_input(&mut self, input: &Input) -> bool {
+        filter_input(
+            &self.data.filters,
+            &mut self.hub,
+            &self.blink,
+            &mut self.world,
+            input,
+        )
+    }
+
+    pub fn render(
+        &mut self,
+        device: &mev::Device,
+        queue: &mut mev::Queue,
+    ) -> Result<(), PresentError> {
+        let Some(viewport) = &mut self.viewport else {
+            return Ok(());
+        };
+
+        if let Err(err) = build_assets(&mut self.world, device, queue) {
+            tracing::error!("Failed to build assets: {err}");
+        }
+
+        run_presents(
+            &mut self.workgraph,
+            &self.data.workgraph.all_presents(),
+            Some(viewport),
+            device,
+            queue,
+            &mut self.world,
+            &mut self.hub,
+        )
+    }
+
+    /// Returns viewport of the game.
+    /// Returns `None` for headless game.
+    pub fn viewport(&self) -> Option<&Viewport> {
+        self.viewport.as_ref()
+    }
+}
+
+impl Drop for Game {
+    fn drop(&mut self) {
+        // De-initialize plugins in reverse order.
+        for (_, plugin) in self.plugins.iter().rev() {
+            plugin.deinit(&mut self.world);
+        }
+    }
+}
+
+/// Runs the game with statically linked plugins.
+///
+/// `path` is the game crate manifest directory, `<project-dir>/crates/game`.
+/// Game data is looked up next to the executable first
+/// and then in the project directory.
+///
+/// Runs headless if `--headless` argument is passed to the executable.
+/// Exits the process with non-zero status on error.
+pub fn run(plugins: &[(Ident, &'static dyn ArcanaPlugin)], path: &Path) {
+    if std::env::args().skip(1).any(|arg| arg == "--headless") {
+        return run_headless(plugins, path);
+    }
+
+    if let Err(err) = _run(plugins, path) {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+/// Runs the game with statically linked plugins without window and GPU.
+///
+/// Game runs until [`Quit`] resource is inserted into the world.
+/// Exits the process with non-zero status on error.
+pub fn run_headless(plugins: &[(Ident, &'static dyn ArcanaPlugin)], path: &Path) {
+    if let Err(err) = _run_headless(plugins, path) {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+fn _run(plugins: &[(Ident, &'static dyn ArcanaPlugin)], path: &Path) -> Result<(), String> {
+    install_tracing();
+
+    let (root, data) = load_game_data(path)?;
+
+    let (device, mut queue) = init_mev()?;
+
+    let events = EventLoop::new().map_err(|err| format!("Failed to create event loop: {err}"))?;
+
+    let title = if data.name.is_empty() {
+        "Arcana Game".to_owned()
+    } else {
+        data.name.clone()
+    };
+
+    #[allow(deprecated)]
+    let window = events
+        .create_window(Window::default_attributes().with_title(title))
+        .map_err(|err| format!("Failed to create game window: {err}"))?;
+
+    let mut game = Game::new(plugins, data, Viewport::new_window(window));
+    game.set_assets_root(&root);
+
+    let mut clock = Clock::new();
+    let mut limiter = clock.ticker(240.hz());
+
+    #[allow(deprecated)]
+    events
+        .run(move |event, events| match event {
+            Event::WindowEvent { event, .. } => {
+                if let WindowEvent::CloseRequested = event {
+                    events.exit();
+                    return;
+                }
+
+                if let Ok(input) = ViewportInput::try_from(&event) {
+                    game.on_input(&Input::ViewportInput { input });
+                }
+            }
+            Event::AboutToWait => {
+                let step = clock.step();
+
+                game.tick(step.step);
+
+                if game.should_quit() {
+                    events.exit();
+                    return;
+                }
+
+                limiter.ticks(step.step);
+                let until = clock.stamp_instant(limiter.next_tick().unwrap());
+
+                events.set_control_flow(ControlFlow::WaitUntil(until));
+
+                if let Err(err) = game.render(&device, &mut queue) {
+                    tracing::error!("Failed to render game: {err:?}");
+                }
+            }
+            _ => {}
+        })
+        .map_err(|err| format!("Event loop error: {err}"))
+}
+
+fn _run_headless(
+    plugins: &[(Ident, &'static dyn ArcanaPlugin)],
+    path: &Path,
+) -> Result<(), String> {
+    install_tracing();
+
+    let (root, data) = load_game_data(path)?;
+
+    let mut game = Game::new_headless(plugins, data);
+    game.set_assets_root(&root);
+
+    let mut clock = Clock::new();
+    let mut limiter = clock.ticker(240.hz());
+
+    while !game.should_quit() {
+        let step = clock.step();
+
+        game.tick(step.step);
+
+        limiter.ticks(step.step);
+        let until = clock.stamp_instant(limiter.next_tick().unwrap());
+
+        std::thread::sleep(until.saturating_duration_since(Instant::now()));
+    }
+
+    Ok(())
+}
+
+fn install_tracing() {
+    // Keep subscriber installed by the host if any.
+    let _ = tracing::subscriber::set_global_default(
+        tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .finish(),
+    );
+}
+
+/// Loads game data and returns it with the directory it was found in.
+/// That directory is also the root of the asset store.
+fn load_game_data(path: &Path) -> Result<(PathBuf, GameData), String> {
+    let mut candidates = Vec::new();
+
+    if let Ok(mut exe) = std::env::current_exe() {
+        exe.pop();
+        candidates.push(exe);
+    }
+
+    // `path` is `<project-dir>/crates/game`
+    if let Some(root) = path.parent().and_then(Path::parent) {
+        candidates.push(root.to_owned());
+    }
+
+    for root in &candidates {
+        match GameData::load(root) {
+            Ok(data) => return Ok((root.clone(), data)),
+            Err(err) if err.kind() == ErrorKind::NotFound => continue,
+            Err(err) => {
+                return Err(format!(
+                    "Failed to load game data from '{}': {err}",
+                    root.join(GAME_DATA_FILE).display()
+                ))
+            }
+        }
+    }
+
+    tracing::warn!("Game data not found, running with empty project data");
+    let root = candidates.pop().unwrap_or_default();
+    Ok((root, GameData::default()))
+}
+
+fn init_mev() -> Result<(mev::Device, mev::Queue), String> {
+    let instance =
+        mev::Instance::load().map_err(|err| format!("Failed to init graphics: {err}"))?;
+
+    let Some(caps) = instance.capabilities().devices.first() else {
+        return Err("No graphics devices found".to_owned());
+    };
+
+    // Compressed textures are used when available.
+    let optional = caps.features & mev::Features::TEXTURE_COMPRESSION_BC;
+
+    let (device, mut queues) = instance
+        .create(mev::DeviceDesc {
+            idx: 0,
+            queues: &[0],
+            features: mev::Features::SURFACE | optional,
+        })
+        .map_err(|err| format!("Failed to create graphics device: {err}"))?;
+
+    let queue = queues
+        .pop()
+        .ok_or_else(|| "Graphics device has no queues".to_owned())?;
+
+    Ok((device, queue))
+}
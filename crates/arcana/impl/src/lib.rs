@@ -88,18 +88,21 @@ pub use {
 };
 
 pub use mev;
+pub mod animation;
 pub mod arena;
 pub mod assets;
 pub mod code;
+pub mod config;
 pub mod events;
 pub mod flow;
+pub mod font;
+pub mod game;
 pub mod id;
 pub mod input;
 pub mod model;
 mod num2name;
 pub mod plugin;
 pub mod refl;
-pub mod render;
 pub mod serde_with;
 mod stable_hasher;
 mod stid;
@@ -2,13 +2,16 @@ use std::{any::Any, sync::atomic::AtomicBool};
 
 use arcana_names::{Ident, Name};
 use arcana_project::Dependency;
-use edict::{IntoSystem, System, World};
+use edict::{Component, Entities, EntityId, IntoSystem, System, World};
 use hashbrown::HashMap;
 
-use crate::code::{CodeDesc, CodeId, FlowCode, PureCode};
+use crate::code::{builtin_conversions, CodeDesc, CodeId, FlowCode, PureCode};
+use crate::config::{config_fn, Config, ConfigFn, ConfigId};
 use crate::input::{FilterId, InputFilter, IntoInputFilter};
 use crate::make_id;
-use crate::work::{Job, JobDesc, JobId};
+use crate::model::{Model, Value};
+use crate::stable_hash;
+use crate::work::{Job, JobDesc, JobId, ParamSource, ParamSourceId};
 
 make_id!(pub SystemId);
 
@@ -45,6 +48,35 @@ pub struct JobInfo {
     pub desc: JobDesc,
 }
 
+/// Job param source information declared by a plugin.
+#[derive(Clone)]
+pub struct ParamSourceInfo {
+    /// Unique identified of the param source.
+    pub id: ParamSourceId,
+
+    /// Name of the param source.
+    pub name: Name,
+
+    /// Model of values produced by the source.
+    pub model: Model,
+}
+
+/// Config information declared by a plugin.
+#[derive(Clone)]
+pub struct ConfigInfo {
+    /// Unique identified of the config.
+    pub id: ConfigId,
+
+    /// Name of the config.
+    pub name: Name,
+
+    /// Model of the config values.
+    pub model: Model,
+
+    /// Value of the default config.
+    pub default: Value,
+}
+
 /// Job information declared by a plugin.
 #[derive(Clone)]
 pub struct CodeInfo {
@@ -65,18 +97,27 @@ pub struct PluginsHub {
     pub systems: HashMap<SystemId, Box<dyn System + Send>>,
     pub filters: HashMap<FilterId, Box<dyn InputFilter>>,
     pub jobs: HashMap<JobId, Box<dyn Job>>,
+    pub param_sources: HashMap<ParamSourceId, ParamSource>,
     pub pure_fns: HashMap<CodeId, PureCode>,
     pub flow_fns: HashMap<CodeId, FlowCode>,
+    pub configs: HashMap<ConfigId, ConfigFn>,
 }
 
 impl PluginsHub {
     pub fn new() -> Self {
+        let mut pure_fns = HashMap::new();
+        for (info, code) in builtin_conversions() {
+            pure_fns.insert(info.id, code);
+        }
+
         PluginsHub {
             systems: HashMap::new(),
             filters: HashMap::new(),
             jobs: HashMap::new(),
-            pure_fns: HashMap::new(),
+            param_sources: HashMap::new(),
+            pure_fns,
             flow_fns: HashMap::new(),
+            configs: HashMap::new(),
         }
     }
 
@@ -102,6 +143,15 @@ impl PluginsHub {
         self.jobs.insert(id, Box::new(job));
     }
 
+    /// Adds a job param source from a plugin to the hub.
+    pub fn add_param_source(
+        &mut self,
+        id: ParamSourceId,
+        source: impl Fn(&World) -> Option<Value> + 'static,
+    ) {
+        self.param_sources.insert(id, Box::new(source));
+    }
+
     /// Adds a pure fn from a plugin to the hub.
     pub fn add_pure_fn(&mut self, id: CodeId, code: PureCode) {
         self.pure_fns.insert(id, code);
@@ -111,6 +161,26 @@ impl PluginsHub {
     pub fn add_flow_fn(&mut self, id: CodeId, code: FlowCode) {
         self.flow_fns.insert(id, code);
     }
+
+    /// Adds a config from a plugin to the hub.
+    pub fn add_config<T>(&mut self, id: ConfigId)
+    where
+        T: Config,
+    {
+        self.configs.insert(id, config_fn::<T>());
+    }
+
+    /// Applies config value to the world.
+    /// Returns false if config is not found.
+    pub fn apply_config(&self, id: ConfigId, value: &Value, world: &mut World) -> bool {
+        match self.configs.get(&id) {
+            Some(f) => {
+                f(world, value);
+                true
+            }
+            None => false,
+        }
+    }
 }
 
 #[macro_export]
@@ -161,11 +231,30 @@ pub trait ArcanaPlugin: Any + Sync {
         Vec::new()
     }
 
+    /// Returns list of job param sources.
+    fn param_sources(&self) -> Vec<ParamSourceInfo> {
+        Vec::new()
+    }
+
+    /// Returns list of configs.
+    fn configs(&self) -> Vec<ConfigInfo> {
+        Vec::new()
+    }
+
     /// Returns list of systems.
     fn pure_code(&self) -> Vec<CodeInfo> {
         Vec::new()
     }
 
+    /// Returns list of pure codes that convert values between types.
+    /// Editor inserts them when wiring pins of different types.
+    ///
+    /// Each must have single input and single output
+    /// and its function must be added to the hub in `init`.
+    fn conversions(&self) -> Vec<CodeInfo> {
+        Vec::new()
+    }
+
     /// Registers components and resources.
     /// Perform any other initialization of the world.
     /// Returns constructed systems and event filters.
@@ -191,22 +280,111 @@ pub trait ArcanaPlugin: Any + Sync {
         false
     }
 
+    /// Returns hash of the layout of the state written by `dump`.
+    /// Plugins with different layouts are not compatible.
+    ///
+    /// See [`component_layout`] for helper.
+    fn persistent_layout(&self) -> u64 {
+        0
+    }
+
     /// Dump state of the world known to this plugin.
     /// This method is called when the plugin is reloaded with updated code
     /// before `deinit` method.
     /// New version, if compatible, will load the state from the dump.
-    fn dump(&self, world: &World, scratch: &mut [u8]) -> usize {
-        let _ = (world, scratch);
-        0
+    ///
+    /// See [`dump_component`] for serde-based helper.
+    fn dump(&self, world: &World, buffer: &mut Vec<u8>) {
+        let _ = (world, buffer);
     }
 
     /// Load state of the world known to this plugin dumped by previous version.
-    fn load(&self, world: &mut World, scratch: &[u8]) {
-        let _ = (world, scratch);
-        unimplemented!()
+    /// This method is called after `init` method.
+    ///
+    /// See [`load_component`] for serde-based helper.
+    fn load(&self, world: &mut World, dump: &[u8]) {
+        let _ = (world, dump);
     }
 }
 
+/// Returns hash of the component layout.
+///
+/// Changes when component type is renamed or changes its size or alignment.
+/// It does not detect reordering of fields with the same types.
+pub fn component_layout<T>() -> u64
+where
+    T: 'static,
+{
+    stable_hash(&(
+        std::any::type_name::<T>(),
+        std::mem::size_of::<T>(),
+        std::mem::align_of::<T>(),
+    ))
+}
+
+/// Dumps all instances of the component into the buffer.
+///
+/// Entity ids are preserved, so components dumped by different plugins
+/// are loaded back into the same entities.
+///
+/// Dump starts with [`component_layout`] of the component,
+/// so that it is not loaded into component with different layout.
+pub fn dump_component<T>(world: &World, buffer: &mut Vec<u8>)
+where
+    T: Component + Sync + serde::Serialize,
+{
+    let components = world
+        .view::<(Entities, &T)>()
+        .into_iter()
+        .map(|(e, c)| (e.id(), c))
+        .collect::<Vec<_>>();
+
+    if let Err(err) = bincode::serialize_into(buffer, &(component_layout::<T>(), components)) {
+        tracing::error!("Failed to dump component '{}': {err}", T::name());
+    }
+}
+
+/// Loads instances of the component dumped with [`dump_component`].
+/// Advances `dump` past loaded data.
+///
+/// Returns false if dump is malformed or was made for different component layout.
+/// Components that follow should not be loaded then.
+pub fn load_component<T>(world: &mut World, dump: &mut &[u8]) -> bool
+where
+    T: Component + serde::de::DeserializeOwned,
+{
+    let layout: u64 = match bincode::deserialize_from(&mut *dump) {
+        Ok(layout) => layout,
+        Err(err) => {
+            tracing::error!("Failed to load component '{}': {err}", T::name());
+            return false;
+        }
+    };
+
+    if layout != component_layout::<T>() {
+        tracing::error!("Failed to load component '{}': layout changed", T::name());
+        return false;
+    }
+
+    let components: Vec<(EntityId, T)> = match bincode::deserialize_from(&mut *dump) {
+        Ok(components) => components,
+        Err(err) => {
+            tracing::error!("Failed to load component '{}': {err}", T::name());
+            return false;
+        }
+    };
+
+    for (id, component) in components {
+        if world.is_alive(id) {
+            let _ = world.insert(id, component);
+        } else {
+            world.spawn_at(id, (component,));
+        }
+    }
+
+    true
+}
+
 #[doc(hidden)]
 #[macro_export]
 macro_rules! name_or_expr {
@@ -328,6 +506,12 @@ macro_rules! init_resources {
 ///   // Types must implement `Component` trait.
 ///   components: [Foo, Bar],
 ///
+///   // Next declare components that are transferred to updated plugin on hot-reload.
+///   // Skip if plugin state should be reset on hot-reload.
+///   // Commas separated list of component types.
+///   // Types must implement `Component`, `serde::Serialize` and `serde::Deserialize` traits.
+///   persistent: [Foo],
+///
 ///   // Next declare systems that will be available for scheduler.
 ///   // Skip if there are no systems to add.
 ///   // Comma separated list of system names with optional system expressions after ':' token.
@@ -344,6 +528,24 @@ macro_rules! init_resources {
 ///   // Comma separated list of filter names with optional filter expressions after ':' token.
 ///   // If expression is provided, expression will be avaluated and its result transformed with `IntoFilter`.
 ///   filters: [],
+///
+///   // Next declare sources of job params.
+///   // Skip if there are no param sources to add.
+///   // Comma separated list of source names with model of produced values and source expression after '=>' token.
+///   // Source expression must evaluate to `Fn(&World) -> Option<Value>`.
+///   // See `work::resource_param` and `work::component_param` helpers.
+///   param_sources: [
+///     clear_color: Model::Color => resource_param(|s: &Settings| Value::Color(s.clear_color)),
+///   ],
+///
+///   // Next declare configs that are edited in the editor.
+///   // Skip if there are no configs.
+///   // Comma separated list of config names with config types after ':' token.
+///   // Types must implement `config::Config` trait.
+///   // Default config is inserted as a resource and replaced with values stored in the project.
+///   configs: [
+///     settings: Settings,
+///   ],
 /// });
 /// ```
 #[macro_export]
@@ -352,9 +554,12 @@ macro_rules! export_arcana_plugin {
         $(dependencies: [$($dependency:ident $dep_kind:tt),+ $(,)?] $(,)?)?
         $(resources: [$($resource:expr),+ $(,)?] $(,)?)?
         $(components: [$($component:ty),+ $(,)?] $(,)?)?
+        $(persistent: [$($persistent:ty),+ $(,)?] $(,)?)?
         $(systems: [$($system_name:ident $(: $system:expr)?),+ $(,)?] $(,)?)?
         $(filters: [$($filter_name:ident $(: $filter:expr)?),+ $(,)?] $(,)?)?
         $(jobs: [$($job_name:ident $(: $job_desc:expr)? $(=> $job:expr)?),+ $(,)?] $(,)?)?
+        $(param_sources: [$($source_name:ident: $source_model:expr => $source:expr),+ $(,)?] $(,)?)?
+        $(configs: [$($config_name:ident: $config:ty),+ $(,)?] $(,)?)?
         $(pure_fns: [$($pure_fn:ident),+ $(,)?] $(,)?)?
         $(flow_fns: [$($flow_fn:ident),+ $(,)?] $(,)?)?
         $(in $world:ident $(: $world_type:ty)? $( => { $($init:tt)* })?)?
@@ -406,6 +611,55 @@ macro_rules! export_arcana_plugin {
                     }
                 )?
 
+                $(
+                    fn param_sources(&self) -> Vec<$crate::plugin::ParamSourceInfo> {
+                        vec![$(
+                            $crate::plugin::ParamSourceInfo {
+                                id: $crate::local_name_hash_id!($source_name),
+                                name: $crate::ident!($source_name).into(),
+                                model: $source_model,
+                            },
+                        )+]
+                    }
+                )?
+
+                $(
+                    fn configs(&self) -> Vec<$crate::plugin::ConfigInfo> {
+                        vec![$(
+                            $crate::plugin::ConfigInfo {
+                                id: $crate::local_name_hash_id!($config_name),
+                                name: $crate::ident!($config_name).into(),
+                                model: <$config as $crate::model::TypeModel>::model(),
+                                default: $crate::config::Config::to_value(&<$config as ::core::default::Default>::default()),
+                            },
+                        )+]
+                    }
+                )?
+
+                $(
+                    fn compatible(&self, updated: &dyn $crate::plugin::ArcanaPlugin) -> bool {
+                        self.persistent_layout() == updated.persistent_layout()
+                    }
+
+                    fn persistent_layout(&self) -> u64 {
+                        $crate::stable_hash(&[$(
+                            $crate::plugin::component_layout::<$persistent>(),
+                        )+])
+                    }
+
+                    fn dump(&self, world: &$crate::edict::World, buffer: &mut Vec<u8>) {
+                        $(
+                            $crate::plugin::dump_component::<$persistent>(world, buffer);
+                        )+
+                    }
+
+                    fn load(&self, world: &mut $crate::edict::World, mut dump: &[u8]) {
+                        let _ = true $(
+                            && $crate::plugin::load_component::<$persistent>(world, &mut dump)
+                        )+;
+                    }
+                )?
+
                 fn init(&self, world: &mut $crate::edict::World, hub: &mut $crate::plugin::PluginsHub) {
                     $($(world.ensure_component_registered::<$component>();)*)?
 
@@ -426,6 +680,15 @@ macro_rules! export_arcana_plugin {
                         hub.add_job($crate::local_name_hash_id!($job_name), $crate::job_new_or_expr!($job_name $(=> $job)?));
                     )+)?
 
+                    $($(
+                        hub.add_param_source($crate::local_name_hash_id!($source_name), $source);
+                    )+)?
+
+                    $($(
+                        world.insert_resource(<$config as ::core::default::Default>::default());
+                        hub.add_config::<$config>($crate::local_name_hash_id!($config_name));
+                    )+)?
+
                     $crate::init_resources! {
                         world $(as $world)?
                         [$($($resource),+)?]
@@ -473,3 +736,61 @@ pub fn set_running_arcana_instance() {
 pub fn unknown_dependency() -> ! {
     panic!("Unknown dependency")
 }
+
+#[cfg(test)]
+mod tests {
+    use edict::{Component, World};
+
+    use super::{component_layout, dump_component, load_component};
+
+    #[derive(Component, serde::Serialize, serde::Deserialize)]
+    struct State {
+        value: u32,
+    }
+
+    /// Updated version of the `State`.
+    mod updated {
+        #[derive(edict::Component, serde::Serialize, serde::Deserialize)]
+        pub struct State {
+            pub value: u32,
+            pub extra: u64,
+        }
+    }
+
+    #[test]
+    fn persistent_round_trip() {
+        let mut world = World::new();
+        let entity = world.spawn((State { value: 42 },)).id();
+
+        let mut buffer = Vec::new();
+        dump_component::<State>(&world, &mut buffer);
+
+        let mut world = World::new();
+        let mut dump = &buffer[..];
+        assert!(load_component::<State>(&mut world, &mut dump));
+        assert!(dump.is_empty());
+
+        assert_eq!(world.get::<&State>(entity).unwrap().value, 42);
+    }
+
+    #[test]
+    fn persistent_incompatible_layout() {
+        assert_ne!(
+            component_layout::<State>(),
+            component_layout::<updated::State>()
+        );
+
+        let mut world = World::new();
+        let entity = world.spawn((State { value: 42 },)).id();
+
+        let mut buffer = Vec::new();
+        dump_component::<State>(&world, &mut buffer);
+
+        let mut world = World::new();
+        assert!(!load_component::<updated::State>(
+            &mut world,
+            &mut &buffer[..]
+        ));
+        assert!(world.get::<&updated::State>(entity).is_err());
+    }
+}
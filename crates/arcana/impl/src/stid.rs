@@ -47,3 +47,12 @@ impl Stid {
 }
 
 with_stid!(::edict::entity::EntityId = 0x0000_0000_0000_0001);
+with_stid!(bool = 0x0000_0000_0000_0002);
+with_stid!(i32 = 0x0000_0000_0000_0003);
+with_stid!(u32 = 0x0000_0000_0000_0004);
+with_stid!(i64 = 0x0000_0000_0000_0005);
+with_stid!(u64 = 0x0000_0000_0000_0006);
+with_stid!(f32 = 0x0000_0000_0000_0007);
+with_stid!(f64 = 0x0000_0000_0000_0008);
+with_stid!(::na::Vector2<f32> = 0x0000_0000_0000_0009);
+with_stid!(::na::Vector3<f32> = 0x0000_0000_0000_000A);
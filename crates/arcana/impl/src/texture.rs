@@ -1,5 +1,6 @@
 use std::future::{ready, Ready};
 
+use arcana_importers::texture::{TextureData, TextureDecodeError, TextureFormat};
 use edict::Component;
 
 use crate::assets::BobBuilder;
@@ -16,53 +17,92 @@ impl Component for Texture {
 }
 
 impl argosy::Asset for Texture {
-    type Decoded = (rapid_qoi::Qoi, Vec<u8>);
-    type DecodeError = rapid_qoi::DecodeError;
+    type Decoded = TextureData;
+    type DecodeError = TextureDecodeError;
     type BuildError = mev::OutOfMemory;
-    type Fut = Ready<Result<(rapid_qoi::Qoi, Vec<u8>), rapid_qoi::DecodeError>>;
+    type Fut = Ready<Result<TextureData, TextureDecodeError>>;
 
     fn name() -> &'static str {
         "texture"
     }
 
     fn decode(bytes: Box<[u8]>, _: &argosy::Loader) -> Self::Fut {
-        let result = rapid_qoi::Qoi::decode_alloc(&*bytes);
-        ready(result)
+        ready(TextureData::decode(&bytes))
     }
 }
 
 impl argosy::AssetBuild<BobBuilder<'_>> for Texture {
-    fn build(builder: &mut BobBuilder, decoded: Self::Decoded) -> Result<Self, mev::OutOfMemory> {
-        let (qoi, bytes) = decoded;
-
-        let image = builder.device.new_image(mev::ImageDesc {
-            name: "texture",
-            dimensions: mev::ImageExtent::D2(mev::Extent2::new(qoi.width, qoi.height)),
-            format: mev::PixelFormat::Rgba8Srgb,
-            usage: mev::ImageUsage::SAMPLED | mev::ImageUsage::TRANSFER_DST,
-            levels: 1,
-            layers: 1,
-        })?;
-
-        let staging = builder.device.new_buffer_init(mev::BufferInitDesc {
-            data: &bytes,
-            name: "texture-staging",
-            usage: mev::BufferUsage::TRANSFER_SRC,
-            memory: mev::Memory::Upload,
-        })?;
+    fn build(builder: &mut BobBuilder, decoded: TextureData) -> Result<Self, mev::OutOfMemory> {
+        let image = upload_texture(builder, "texture", decoded)?;
+        Ok(Texture { image })
+    }
+}
+
+/// Creates sampled image from texture prepared by importer
+/// and records upload of all its levels.
+///
+/// Compressed textures are decompressed if device doesn't support them.
+pub(crate) fn upload_texture(
+    builder: &mut BobBuilder,
+    name: &str,
+    mut texture: TextureData,
+) -> Result<mev::Image, mev::OutOfMemory> {
+    if texture.format == TextureFormat::Bc3
+        && !builder
+            .device
+            .features()
+            .contains(mev::Features::TEXTURE_COMPRESSION_BC)
+    {
+        texture.decompress();
+    }
+
+    let format = match (texture.format, texture.srgb) {
+        (TextureFormat::Rgba8, false) => mev::PixelFormat::Rgba8Unorm,
+        (TextureFormat::Rgba8, true) => mev::PixelFormat::Rgba8Srgb,
+        (TextureFormat::Bc3, false) => mev::PixelFormat::Bc3RgbaUnorm,
+        (TextureFormat::Bc3, true) => mev::PixelFormat::Bc3RgbaSrgb,
+        (TextureFormat::R8, false) => mev::PixelFormat::R8Unorm,
+        (TextureFormat::R8, true) => mev::PixelFormat::R8Srgb,
+    };
+
+    let image = builder.device.new_image(mev::ImageDesc {
+        name,
+        dimensions: mev::ImageExtent::D2(mev::Extent2::new(texture.width, texture.height)),
+        format,
+        usage: mev::ImageUsage::SAMPLED | mev::ImageUsage::TRANSFER_DST,
+        levels: texture.levels.len() as u32,
+        layers: 1,
+        cube: false,
+    })?;
+
+    // All levels are packed into single staging buffer.
+    let data = texture.levels.concat();
+
+    let staging = builder.device.new_buffer_init(mev::BufferInitDesc {
+        data: &data,
+        name: "texture-staging",
+        usage: mev::BufferUsage::TRANSFER_SRC,
+        memory: mev::Memory::Upload,
+    })?;
+
+    let mut offset = 0;
+    for (level, pixels) in texture.levels.iter().enumerate() {
+        let (width, height) = texture.level_extent(level);
 
         builder.encoder.copy_buffer_to_image(
             &staging,
-            0,
-            0,
+            offset,
+            texture.bytes_per_line(level),
             0,
             &image,
             mev::Offset3::ZERO,
-            mev::Extent3::new(qoi.width, qoi.height, 1),
+            mev::Extent3::new(width, height, 1),
             0..1,
-            0,
+            level as u32,
         );
 
-        Ok(Texture { image })
+        offset += pixels.len();
     }
+
+    Ok(image)
 }
@@ -6,10 +6,9 @@ use winit::window::Window;
 /// Viewport is where content of the game is displayed.
 /// It is semi-opaque as users usually do not need care about what is behind it.
 ///
-/// Viewport has a size and may be presented to by `RenderGraph`.
-///
-/// `RenderGraph::present` will present to main viewport which is resource in the `World`.
-/// `RenderGraph::present_to` takes `EntityId` where it will look for `Viewport` component.
+/// Viewport has a size and provides images to render into.
+/// Work graph output presented to the viewport is written into the image
+/// returned by [`Viewport::next_frame`].
 pub struct Viewport {
     kind: ViewportKind,
 }
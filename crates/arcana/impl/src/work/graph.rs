@@ -1,7 +1,9 @@
 use std::{
+    any::TypeId,
     borrow::Borrow,
     cell::{Cell, RefCell},
     hash::Hash,
+    time::Instant,
 };
 
 use arcana_names::Name;
@@ -14,13 +16,16 @@ use crate::{
 };
 
 use super::{
-    job::{JobDesc, JobId},
-    target::{Target, TargetHub, TargetId},
+    job::{Access, JobDesc, JobId},
+    param::ParamSourceId,
+    stats::{GpuTimer, JobStats, WorkGraphStats},
+    target::{Barriers, PinAccess, Target, TargetHub, TargetId, TargetMemoryReport},
 };
 
 /// Index of a job in work graph.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 #[repr(transparent)]
+#[serde(transparent)]
 pub struct JobIdx(pub usize);
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -38,20 +43,27 @@ pub struct WorkGraph {
     hub: TargetHub,
     idgen: IdGen,
     sinks: HashMap<PinId, TargetId>,
+    disabled_sinks: HashSet<PinId>,
+    gpu_timer: GpuTimer,
 
     // Temporary state
     // Cleared after each run.
     selected_jobs: HashSet<JobIdx>,
     cbufs: Arena<mev::CommandEncoder>,
+    barriers: Barriers,
+
+    // Reports of the last run.
+    memory_report: TargetMemoryReport,
+    stats: WorkGraphStats,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct PinId {
     pub job: JobIdx,
     pub pin: usize,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct Edge {
     pub from: PinId,
     pub to: PinId,
@@ -226,8 +238,13 @@ impl WorkGraph {
             hub: TargetHub::new(),
             idgen,
             sinks: HashMap::new(),
+            disabled_sinks: HashSet::new(),
+            gpu_timer: GpuTimer::new(),
             selected_jobs: HashSet::new(),
             cbufs: Arena::new(),
+            barriers: Barriers::new(),
+            memory_report: TargetMemoryReport::default(),
+            stats: WorkGraphStats::default(),
         })
     }
 
@@ -307,6 +324,38 @@ impl WorkGraph {
         }
     }
 
+    /// Binds job param to the source.
+    /// Param value is resolved from the world every frame before planning.
+    ///
+    /// Returns `false` if there is no such job in the graph.
+    pub fn bind_param(&mut self, job: JobIdx, name: Name, source: ParamSourceId) -> bool {
+        let Some(&order) = self.idx_to_order.get(&job) else {
+            return false;
+        };
+        self.plan[order].bindings.insert(name, source);
+        true
+    }
+
+    /// Removes binding of the job param.
+    /// Param keeps last resolved value.
+    /// Does nothing if there is no such job in the graph.
+    pub fn unbind_param(&mut self, job: JobIdx, name: Name) {
+        let Some(&order) = self.idx_to_order.get(&job) else {
+            return;
+        };
+        self.plan[order].bindings.remove(&name);
+    }
+
+    /// Enables or disables the sink.
+    /// Jobs that contribute only to disabled sinks are not executed.
+    pub fn set_sink_enabled(&mut self, pin: PinId, enabled: bool) {
+        if enabled {
+            self.disabled_sinks.remove(&pin);
+        } else {
+            self.disabled_sinks.insert(pin);
+        }
+    }
+
     pub fn add_hook<T>(
         &mut self,
         pin: PinId,
@@ -353,43 +402,147 @@ impl WorkGraph {
         world: &mut World,
         hub: &mut PluginsHub,
     ) -> Result<(), mev::DeviceError> {
+        let run_start = Instant::now();
+
         self.selected_jobs.clear();
+        self.hub.begin_frame();
+        self.gpu_timer.poll(device);
+
+        // Jobs shared by multiple sinks are selected once.
+        for (pin, _) in &self.sinks {
+            if !self.disabled_sinks.contains(pin) {
+                self.selected_jobs.insert(pin.job);
+            }
+        }
+
+        // Resolve bound params before planning.
+        for job in self.plan.iter_mut() {
+            for (name, source) in &job.bindings {
+                let Some(source) = hub.param_sources.get(source) else {
+                    continue;
+                };
 
-        for (&PinId { job, .. }, _) in &self.sinks {
-            self.selected_jobs.insert(job);
+                if let Some(value) = source(world) {
+                    job.params.insert(*name, value);
+                }
+            }
         }
 
         // Plan in reverse order.
         // This allows to collect all target descriptors before creating them.
         // And select dependencies for execution before planning loop considers them.
-        for job in self.plan.iter_mut().rev() {
+        // Targets with non-overlapping live ranges share allocations.
+        for (order, job) in self.plan.iter_mut().enumerate().rev() {
             if !self.selected_jobs.contains(&job.idx) {
                 continue;
             }
+            let start = Instant::now();
             job.plan(
+                order,
                 &mut self.hub,
                 &mut self.selected_jobs,
                 device.clone(),
                 world,
                 hub,
             );
+            job.stats = JobStats {
+                plan: start.elapsed(),
+                ..JobStats::default()
+            };
+        }
+
+        self.memory_report = self.hub.memory_report();
+
+        // Wrap each job into timestamps.
+        let count = self
+            .plan
+            .iter()
+            .filter(|job| self.selected_jobs.contains(&job.idx))
+            .count();
+
+        let timestamps = self.gpu_timer.begin(device, count);
+        if let Some(pool) = &timestamps {
+            let encoder = match queue.new_command_encoder() {
+                Ok(encoder) => encoder,
+                Err(err) => {
+                    self.gpu_timer.release(timestamps.unwrap());
+                    return Err(err.into());
+                }
+            };
+            let encoder = self.cbufs.put(encoder);
+            encoder.reset_queries(pool, 0..count as u32 * 2);
         }
 
+        let mut executed = Vec::with_capacity(count);
+
         for job in self.plan.iter_mut() {
             if !self.selected_jobs.contains(&job.idx) {
                 continue;
             }
+            let query = executed.len() as u32 * 2;
+            let start = Instant::now();
             job.exec(
                 &mut self.hub,
+                &mut self.barriers,
                 device.clone(),
                 queue,
                 &self.cbufs,
+                timestamps.as_ref().map(|pool| (pool, query)),
                 world,
                 hub,
             );
+            job.stats.exec_start = start - run_start;
+            job.stats.exec = start.elapsed();
+            executed.push(job.idx);
+        }
+
+        let mut result = Ok(());
+        let mut buffers = Vec::new();
+        for encoder in self.cbufs.drain() {
+            match encoder.finish() {
+                Ok(buffer) => buffers.push(buffer),
+                Err(err) => result = Err(err.into()),
+            }
+        }
+
+        if let Err(err) = queue.submit(buffers, true) {
+            result = Err(err);
+        }
+
+        self.stats.jobs.clear();
+        for &idx in &executed {
+            let mut stats = self.plan[self.idx_to_order[&idx]].stats;
+            if let Some((start, duration)) = self.gpu_timer.job(idx) {
+                stats.gpu_start = Some(start);
+                stats.gpu = Some(duration);
+            }
+            self.stats.jobs.push((idx, stats));
         }
+        self.stats.cpu = run_start.elapsed();
+        self.stats.gpu = self.gpu_timer.frame();
+
+        if let Some(pool) = timestamps {
+            if result.is_ok() {
+                self.gpu_timer.end(pool, executed);
+            } else {
+                // Timestamps may never be written, do not wait for them.
+                self.gpu_timer.release(pool);
+            }
+        }
+
+        world.insert_resource(self.stats.clone());
+
+        result
+    }
 
-        queue.submit(self.cbufs.drain().filter_map(|e| e.finish().ok()), true)
+    /// Returns memory used by targets in the last run.
+    pub fn memory_report(&self) -> TargetMemoryReport {
+        self.memory_report
+    }
+
+    /// Returns timings of the last run.
+    pub fn stats(&self) -> &WorkGraphStats {
+        &self.stats
     }
 }
 
@@ -406,6 +559,9 @@ pub struct Planner<'a> {
     /// Where all targets live.
     hub: &'a mut TargetHub,
 
+    /// Accesses to targets planned by the job.
+    accesses: &'a mut Vec<TargetAccess>,
+
     /// Set of selected jobs.
     selected_jobs: &'a mut HashSet<JobIdx>,
 
@@ -413,6 +569,9 @@ pub struct Planner<'a> {
 
     idx: JobIdx,
 
+    /// Position of the job in execution plan.
+    order: usize,
+
     params: &'a HashMap<Name, Value>,
 }
 
@@ -425,8 +584,19 @@ impl Planner<'_> {
     {
         let create = self.creates.next().expect("No more creates");
         assert_eq!(create.ty, Stid::of::<T>());
-        self.hub
-            .plan_create::<T>(create.id?, &create.name, &self.device)
+        let id = create.id?;
+        let info = self
+            .hub
+            .plan_create::<T>(id, &create.name, &self.device, self.order)?;
+
+        self.accesses.push(TargetAccess {
+            ty: TypeId::of::<T>(),
+            id,
+            kind: PinAccess::Create,
+            access: create.access.unwrap_or_else(T::write_access),
+        });
+
+        Some(info)
     }
 
     /// Fetcehs resource description for next update.
@@ -436,12 +606,20 @@ impl Planner<'_> {
     {
         let update = self.updates.next().expect("No more updates");
         assert_eq!(update.ty, Stid::of::<T>());
-        let info = self.hub.plan_update::<T>(update.id?)?;
+        let id = update.id?;
+        let info = self.hub.plan_update::<T>(id, self.order)?;
 
         if let Some(dep_idx) = update.dep_idx {
             self.selected_jobs.insert(dep_idx);
         }
 
+        self.accesses.push(TargetAccess {
+            ty: TypeId::of::<T>(),
+            id,
+            kind: PinAccess::Update,
+            access: update.access.unwrap_or_else(T::write_access),
+        });
+
         Some(info)
     }
 
@@ -456,11 +634,18 @@ impl Planner<'_> {
         let Some(id) = read.id else {
             return;
         };
-        self.hub.plan_read::<T>(id, info);
+        self.hub.plan_read::<T>(id, info, self.order);
 
         if let Some(dep_idx) = read.dep_idx {
             self.selected_jobs.insert(dep_idx);
         }
+
+        self.accesses.push(TargetAccess {
+            ty: TypeId::of::<T>(),
+            id,
+            kind: PinAccess::Read,
+            access: read.access.unwrap_or_else(T::read_access),
+        });
     }
 
     pub fn idx(&self) -> JobIdx {
@@ -596,6 +781,9 @@ struct TargetCreate {
     /// Target type.
     ty: Stid,
 
+    /// Declared access.
+    access: Option<Access>,
+
     /// Assigned target id.
     id: Option<TargetId>,
 }
@@ -605,6 +793,9 @@ struct TargetUpdate {
     /// Target type.
     ty: Stid,
 
+    /// Declared access.
+    access: Option<Access>,
+
     /// Assigned target id.
     id: Option<TargetId>,
 
@@ -617,6 +808,9 @@ struct TargetRead {
     /// Target type.
     ty: Stid,
 
+    /// Declared access.
+    access: Option<Access>,
+
     /// Assigned target id.
     id: Option<TargetId>,
 
@@ -624,15 +818,26 @@ struct TargetRead {
     dep_idx: Option<JobIdx>,
 }
 
+/// Access to a target planned by a job.
+struct TargetAccess {
+    ty: TypeId,
+    id: TargetId,
+    kind: PinAccess,
+    access: Access,
+}
+
 // #[derive(Debug)]
 struct JobNode {
     idx: JobIdx,
     id: JobId,
     params: HashMap<Name, Value>,
+    bindings: HashMap<Name, ParamSourceId>,
     updates: Vec<TargetUpdate>,
     creates: Vec<TargetCreate>,
     reads: Vec<TargetRead>,
+    accesses: Vec<TargetAccess>,
     hooks: Slab<Box<dyn FnMut(&TargetHub, &mev::Device, &CommandStream)>>,
+    stats: JobStats,
 }
 
 impl JobNode {
@@ -642,11 +847,13 @@ impl JobNode {
             idx,
             id,
             params,
+            bindings: HashMap::new(),
             updates: desc
                 .updates
                 .into_iter()
                 .map(|u| TargetUpdate {
                     ty: u.ty,
+                    access: u.access,
                     id: None,
                     dep_idx: None,
                 })
@@ -656,6 +863,7 @@ impl JobNode {
                 .into_iter()
                 .map(|c| TargetCreate {
                     ty: c.ty,
+                    access: c.access,
                     name: c.name,
                     id: None,
                 })
@@ -665,30 +873,38 @@ impl JobNode {
                 .into_iter()
                 .map(|c| TargetRead {
                     ty: c.ty,
+                    access: c.access,
                     id: None,
                     dep_idx: None,
                 })
                 .collect(),
+            accesses: Vec::new(),
             hooks: Slab::new(),
+            stats: JobStats::default(),
         }
     }
 
     fn plan(
         &mut self,
+        order: usize,
         hub: &mut TargetHub,
         selected_jobs: &mut HashSet<JobIdx>,
         device: mev::Device,
         world: &mut World,
         plugins: &mut PluginsHub,
     ) {
+        self.accesses.clear();
+
         let planner = Planner {
             updates: self.updates.iter(),
             creates: self.creates.iter(),
             reads: self.reads.iter(),
             hub,
+            accesses: &mut self.accesses,
             selected_jobs,
             device,
             idx: self.idx,
+            order,
             params: &self.params,
         };
 
@@ -700,9 +916,11 @@ impl JobNode {
     fn exec(
         &mut self,
         hub: &mut TargetHub,
+        barriers: &mut Barriers,
         device: mev::Device,
         queue: &mut mev::Queue,
         cbufs: &Arena<mev::CommandEncoder>,
+        timestamps: Option<(&mev::QueryPool, u32)>,
         world: &mut World,
         plugins: &mut PluginsHub,
     ) {
@@ -711,6 +929,25 @@ impl JobNode {
             cbufs,
         };
 
+        // Synchronize with previous accesses to the targets.
+        for access in &self.accesses {
+            hub.access(
+                access.ty,
+                access.id,
+                access.kind,
+                access.access.stages(),
+                barriers,
+            );
+        }
+
+        if timestamps.is_some() || !barriers.is_empty() {
+            let encoder = commands.new_encoder();
+            if let Some((pool, query)) = timestamps {
+                encoder.write_timestamp(pool, query);
+            }
+            barriers.record(encoder);
+        }
+
         let exec = Exec {
             updates: &self.updates,
             next_update: Cell::new(0),
@@ -737,6 +974,10 @@ impl JobNode {
         for (_, hook) in self.hooks.iter_mut() {
             hook(hub, &device, &commands);
         }
+
+        if let Some((pool, query)) = timestamps {
+            commands.new_encoder().write_timestamp(pool, query + 1);
+        }
     }
 
     // fn update_idx(&self, pin: usize) -> Option<usize> {
@@ -771,3 +1012,21 @@ impl JobNode {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU64;
+
+    use crate::name;
+
+    use super::*;
+
+    #[test]
+    fn bind_param_missing_job() {
+        let mut workgraph = WorkGraph::new(HashMap::new(), HashSet::new()).unwrap();
+        let source = ParamSourceId::new(NonZeroU64::new(1).unwrap());
+
+        assert!(!workgraph.bind_param(JobIdx(0), name!(camera), source));
+        workgraph.unbind_param(JobIdx(0), name!(camera));
+    }
+}
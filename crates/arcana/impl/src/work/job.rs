@@ -13,6 +13,47 @@ use super::graph::{Exec, Planner};
 
 make_id!(pub JobId);
 
+/// Kind of access job performs on a target through a pin.
+/// Work graph uses it to synchronize jobs accessing the same target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Access {
+    /// Target is used as render pass attachment.
+    Attachment,
+
+    /// Target is sampled or loaded in shaders.
+    Shader,
+
+    /// Target is accessed as storage in compute shaders.
+    Storage,
+
+    /// Target is accessed by copy commands.
+    Transfer,
+
+    /// Target may be accessed in any stage.
+    All,
+}
+
+impl Access {
+    /// Returns pipeline stages in which target is accessed.
+    pub fn stages(&self) -> mev::PipelineStages {
+        match self {
+            Access::Attachment => {
+                mev::PipelineStages::COLOR_OUTPUT
+                    | mev::PipelineStages::EARLY_FRAGMENT_TEST
+                    | mev::PipelineStages::LATE_FRAGMENT_TEST
+            }
+            Access::Shader => {
+                mev::PipelineStages::VERTEX_SHADER
+                    | mev::PipelineStages::FRAGMENT_SHADER
+                    | mev::PipelineStages::COMPUTE_SHADER
+            }
+            Access::Storage => mev::PipelineStages::COMPUTE_SHADER,
+            Access::Transfer => mev::PipelineStages::TRANSFER,
+            Access::All => mev::PipelineStages::all(),
+        }
+    }
+}
+
 /// Descroption of job creating a target.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct TargetCreateDesc {
@@ -21,6 +62,11 @@ pub struct TargetCreateDesc {
 
     /// Target type.
     pub ty: Stid,
+
+    /// Declared access.
+    /// If not set, target's default access is used.
+    #[serde(default)]
+    pub access: Option<Access>,
 }
 
 impl TargetCreateDesc {
@@ -28,8 +74,14 @@ impl TargetCreateDesc {
         TargetCreateDesc {
             name,
             ty: T::stid(),
+            access: None,
         }
     }
+
+    pub fn with_access(mut self, access: Access) -> Self {
+        self.access = Some(access);
+        self
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
@@ -39,6 +91,11 @@ pub struct TargetUpdateDesc {
 
     /// Target type.
     pub ty: Stid,
+
+    /// Declared access.
+    /// If not set, target's default access is used.
+    #[serde(default)]
+    pub access: Option<Access>,
 }
 
 impl TargetUpdateDesc {
@@ -46,8 +103,14 @@ impl TargetUpdateDesc {
         TargetUpdateDesc {
             name,
             ty: T::stid(),
+            access: None,
         }
     }
+
+    pub fn with_access(mut self, access: Access) -> Self {
+        self.access = Some(access);
+        self
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
@@ -57,6 +120,11 @@ pub struct TargetReadDesc {
 
     /// Target type.
     pub ty: Stid,
+
+    /// Declared access.
+    /// If not set, target's default access is used.
+    #[serde(default)]
+    pub access: Option<Access>,
 }
 
 impl TargetReadDesc {
@@ -64,8 +132,14 @@ impl TargetReadDesc {
         TargetReadDesc {
             name,
             ty: T::stid(),
+            access: None,
         }
     }
+
+    pub fn with_access(mut self, access: Access) -> Self {
+        self.access = Some(access);
+        self
+    }
 }
 
 /// Job description.
@@ -173,6 +247,18 @@ impl JobDesc {
 #[macro_export]
 macro_rules! add_job_desc {
     (($params:ident, $reads:ident, $updates:ident, $creates:ident)) => {};
+    (($params:ident, $reads:ident, $updates:ident, $creates:ident) $name:ident: $ty:ty as $access:ident , $($rest:tt)*) => {
+        $reads.push($crate::work::TargetReadDesc::new::< $ty >($crate::ident!($name).into()).with_access($crate::work::Access::$access));
+        $crate::add_job_desc!(($params, $reads, $updates, $creates) $($rest)*);
+    };
+    (($params:ident, $reads:ident, $updates:ident, $creates:ident) $name:ident: mut $ty:ty as $access:ident , $($rest:tt)*) => {
+        $updates.push($crate::work::TargetUpdateDesc::new::< $ty >($crate::ident!($name).into()).with_access($crate::work::Access::$access));
+        $crate::add_job_desc!(($params, $reads, $updates, $creates) $($rest)*);
+    };
+    (($params:ident, $reads:ident, $updates:ident, $creates:ident) $name:ident: +$ty:ty as $access:ident , $($rest:tt)*) => {
+        $creates.push($crate::work::TargetCreateDesc::new::< $ty >($crate::ident!($name).into()).with_access($crate::work::Access::$access));
+        $crate::add_job_desc!(($params, $reads, $updates, $creates) $($rest)*);
+    };
     (($params:ident, $reads:ident, $updates:ident, $creates:ident) $name:ident: $ty:ty , $($rest:tt)*) => {
         $reads.push($crate::work::TargetReadDesc::new::< $ty >($crate::ident!($name).into()));
         $crate::add_job_desc!(($params, $reads, $updates, $creates) $($rest)*);
@@ -4,6 +4,9 @@
 
 mod graph;
 mod job;
+mod param;
+mod present;
+mod stats;
 mod target;
 
 use std::ops::Deref;
@@ -12,10 +15,30 @@ use arcana_proc::WithStid;
 
 pub use self::{
     graph::{CommandStream, Cycle, Edge, Exec, HookId, JobIdx, PinId, Planner, WorkGraph},
-    job::{Job, JobDesc, JobId, TargetCreateDesc, TargetReadDesc, TargetUpdateDesc},
-    target::{Target, TargetHub, TargetId},
+    job::{Access, Job, JobDesc, JobId, TargetCreateDesc, TargetReadDesc, TargetUpdateDesc},
+    param::{component_param, resource_param, ParamSource, ParamSourceId},
+    present::{main_present, run_presents, run_viewport, Present, PresentError, PresentFlags},
+    stats::{JobStats, WorkGraphStats},
+    target::{Target, TargetHub, TargetId, TargetMemoryReport},
 };
 
+/// Returns size of single-level image in bytes.
+/// Depth of the extent is either depth of 3d image or number of layers.
+fn image_memory_size(extent: mev::Extent3, format: mev::PixelFormat) -> u64 {
+    let (width, height) = if format.is_compressed() {
+        (extent.width().div_ceil(4), extent.height().div_ceil(4))
+    } else {
+        (extent.width(), extent.height())
+    };
+
+    u64::from(width) * u64::from(height) * u64::from(extent.depth()) * format.size() as u64
+}
+
+/// Returns extent that covers both extents.
+fn max_extent_2d(a: mev::Extent2, b: mev::Extent2) -> mev::Extent2 {
+    mev::Extent2::new(a.width().max(b.width()), a.height().max(b.height()))
+}
+
 /// Generic 2d image target.
 /// It does not hold particular meaning behind pixel values.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, WithStid)]
@@ -57,6 +80,7 @@ impl target::Target for Image2D {
                 format: info.format,
                 usage: info.usage,
                 layers: 1,
+                cube: false,
                 levels: 1,
                 name,
             })
@@ -64,6 +88,22 @@ impl target::Target for Image2D {
 
         Image2D(image)
     }
+
+    fn write_access() -> Access {
+        Access::Attachment
+    }
+
+    fn read_access() -> Access {
+        Access::Shader
+    }
+
+    fn image(&self) -> Option<&mev::Image> {
+        Some(&self.0)
+    }
+
+    fn memory_size(info: &Image2DInfo) -> u64 {
+        image_memory_size(info.extent.to_3d(), info.format)
+    }
 }
 
 /// Generic 2d image target.
@@ -101,6 +141,7 @@ impl target::Target for SampledImage2D {
                 format: info.format,
                 usage: info.usage,
                 layers: 1,
+                cube: false,
                 levels: 1,
                 name,
             })
@@ -109,18 +150,522 @@ impl target::Target for SampledImage2D {
         SampledImage2D(image)
     }
 
+    fn write_access() -> Access {
+        Access::Attachment
+    }
+
+    fn read_access() -> Access {
+        Access::Shader
+    }
+
+    fn image(&self) -> Option<&mev::Image> {
+        Some(&self.0)
+    }
+
+    fn memory_size(info: &SampledImage2DInfo) -> u64 {
+        image_memory_size(info.extent.to_3d(), info.format)
+    }
+
     fn merge_info(info: &mut SampledImage2DInfo, other: &SampledImage2DInfo) -> bool {
         if info.format != other.format {
             return false;
         }
 
-        info.extent = mev::Extent2::new(
+        info.extent = max_extent_2d(info.extent, other.extent);
+
+        info.usage |= other.usage;
+
+        true
+    }
+}
+
+/// Depth image target.
+/// Used as depth attachment and sampled by shadow-mapping jobs.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, WithStid)]
+pub struct DepthImage2D(pub mev::Image);
+
+impl Deref for DepthImage2D {
+    type Target = mev::Image;
+
+    fn deref(&self) -> &mev::Image {
+        &self.0
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DepthImage2DInfo {
+    pub extent: mev::Extent2,
+    pub format: mev::PixelFormat,
+    pub usage: mev::ImageUsage,
+}
+
+impl DepthImage2DInfo {
+    /// Returns info with 32-bit depth-only format.
+    pub fn new(extent: mev::Extent2, usage: mev::ImageUsage) -> Self {
+        DepthImage2DInfo {
+            extent,
+            format: mev::PixelFormat::D32Float,
+            usage,
+        }
+    }
+
+    /// Returns info with combined depth-stencil format.
+    pub fn with_stencil(extent: mev::Extent2, usage: mev::ImageUsage) -> Self {
+        DepthImage2DInfo {
+            extent,
+            format: mev::PixelFormat::D32FloatS8Uint,
+            usage,
+        }
+    }
+}
+
+/// Returns depth bits and presence of stencil in depth format.
+fn depth_format_bits(format: mev::PixelFormat) -> Option<(u32, bool)> {
+    match format {
+        mev::PixelFormat::D16Unorm => Some((16, false)),
+        mev::PixelFormat::D32Float => Some((32, false)),
+        mev::PixelFormat::S8Uint => Some((0, true)),
+        mev::PixelFormat::D16UnormS8Uint => Some((16, true)),
+        mev::PixelFormat::D24UnormS8Uint => Some((24, true)),
+        mev::PixelFormat::D32FloatS8Uint => Some((32, true)),
+        _ => None,
+    }
+}
+
+/// Selects format that satisfies both depth formats.
+/// Format with more depth precision is selected
+/// and stencil is added if any of the formats has it.
+fn merge_depth_format(a: mev::PixelFormat, b: mev::PixelFormat) -> Option<mev::PixelFormat> {
+    let (a_bits, a_stencil) = depth_format_bits(a)?;
+    let (b_bits, b_stencil) = depth_format_bits(b)?;
+
+    let format = match (a_bits.max(b_bits), a_stencil || b_stencil) {
+        (0, _) => mev::PixelFormat::S8Uint,
+        (16, false) => mev::PixelFormat::D16Unorm,
+        (16, true) => mev::PixelFormat::D16UnormS8Uint,
+        (24, _) => mev::PixelFormat::D24UnormS8Uint,
+        (_, false) => mev::PixelFormat::D32Float,
+        (_, true) => mev::PixelFormat::D32FloatS8Uint,
+    };
+
+    Some(format)
+}
+
+impl target::Target for DepthImage2D {
+    type Info = DepthImage2DInfo;
+
+    fn allocate(device: &mev::Device, name: &str, info: &DepthImage2DInfo) -> Self {
+        let image = device
+            .new_image(mev::ImageDesc {
+                dimensions: info.extent.into(),
+                format: info.format,
+                usage: info.usage,
+                layers: 1,
+                cube: false,
+                levels: 1,
+                name,
+            })
+            .unwrap();
+
+        DepthImage2D(image)
+    }
+
+    /// Depth images must match extent of attachments they are used with,
+    /// so readers must agree on extent.
+    fn merge_info(info: &mut DepthImage2DInfo, other: &DepthImage2DInfo) -> bool {
+        if info.extent != other.extent {
+            return false;
+        }
+
+        let Some(format) = merge_depth_format(info.format, other.format) else {
+            return false;
+        };
+
+        info.format = format;
+        info.usage |= other.usage;
+
+        true
+    }
+
+    fn write_access() -> Access {
+        Access::Attachment
+    }
+
+    fn read_access() -> Access {
+        Access::Shader
+    }
+
+    fn image(&self) -> Option<&mev::Image> {
+        Some(&self.0)
+    }
+
+    fn memory_size(info: &DepthImage2DInfo) -> u64 {
+        image_memory_size(info.extent.to_3d(), info.format)
+    }
+}
+
+/// Array of 2d images target.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, WithStid)]
+pub struct Image2DArray(pub mev::Image);
+
+impl Deref for Image2DArray {
+    type Target = mev::Image;
+
+    fn deref(&self) -> &mev::Image {
+        &self.0
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Image2DArrayInfo {
+    pub extent: mev::Extent2,
+    pub layers: u32,
+    pub format: mev::PixelFormat,
+    pub usage: mev::ImageUsage,
+}
+
+impl target::Target for Image2DArray {
+    type Info = Image2DArrayInfo;
+
+    fn allocate(device: &mev::Device, name: &str, info: &Image2DArrayInfo) -> Self {
+        let image = device
+            .new_image(mev::ImageDesc {
+                dimensions: info.extent.into(),
+                format: info.format,
+                usage: info.usage,
+                layers: info.layers,
+                cube: false,
+                levels: 1,
+                name,
+            })
+            .unwrap();
+
+        Image2DArray(image)
+    }
+
+    fn merge_info(info: &mut Image2DArrayInfo, other: &Image2DArrayInfo) -> bool {
+        if info.format != other.format {
+            return false;
+        }
+
+        info.extent = max_extent_2d(info.extent, other.extent);
+        info.layers = info.layers.max(other.layers);
+        info.usage |= other.usage;
+
+        true
+    }
+
+    fn write_access() -> Access {
+        Access::Attachment
+    }
+
+    fn read_access() -> Access {
+        Access::Shader
+    }
+
+    fn image(&self) -> Option<&mev::Image> {
+        Some(&self.0)
+    }
+
+    fn memory_size(info: &Image2DArrayInfo) -> u64 {
+        let extent = mev::Extent3::new(info.extent.width(), info.extent.height(), info.layers);
+        image_memory_size(extent, info.format)
+    }
+}
+
+/// Cube image target.
+/// Allocated as cube-compatible 2d image with six square layers
+/// in `+X`, `-X`, `+Y`, `-Y`, `+Z`, `-Z` order.
+/// Default view samples as cube.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, WithStid)]
+pub struct ImageCube(pub mev::Image);
+
+impl Deref for ImageCube {
+    type Target = mev::Image;
+
+    fn deref(&self) -> &mev::Image {
+        &self.0
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ImageCubeInfo {
+    /// Size of each face.
+    pub size: u32,
+    pub format: mev::PixelFormat,
+    pub usage: mev::ImageUsage,
+}
+
+impl target::Target for ImageCube {
+    type Info = ImageCubeInfo;
+
+    fn allocate(device: &mev::Device, name: &str, info: &ImageCubeInfo) -> Self {
+        let image = device
+            .new_image(mev::ImageDesc {
+                dimensions: mev::Extent2::new(info.size, info.size).into(),
+                format: info.format,
+                usage: info.usage,
+                layers: 6,
+                cube: true,
+                levels: 1,
+                name,
+            })
+            .unwrap();
+
+        ImageCube(image)
+    }
+
+    fn merge_info(info: &mut ImageCubeInfo, other: &ImageCubeInfo) -> bool {
+        if info.format != other.format {
+            return false;
+        }
+
+        info.size = info.size.max(other.size);
+        info.usage |= other.usage;
+
+        true
+    }
+
+    fn write_access() -> Access {
+        Access::Attachment
+    }
+
+    fn read_access() -> Access {
+        Access::Shader
+    }
+
+    fn image(&self) -> Option<&mev::Image> {
+        Some(&self.0)
+    }
+
+    fn memory_size(info: &ImageCubeInfo) -> u64 {
+        image_memory_size(mev::Extent3::new(info.size, info.size, 6), info.format)
+    }
+}
+
+/// 3d image target.
+/// Typically written and read by compute jobs.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, WithStid)]
+pub struct Image3D(pub mev::Image);
+
+impl Deref for Image3D {
+    type Target = mev::Image;
+
+    fn deref(&self) -> &mev::Image {
+        &self.0
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Image3DInfo {
+    pub extent: mev::Extent3,
+    pub format: mev::PixelFormat,
+    pub usage: mev::ImageUsage,
+}
+
+impl target::Target for Image3D {
+    type Info = Image3DInfo;
+
+    fn allocate(device: &mev::Device, name: &str, info: &Image3DInfo) -> Self {
+        let image = device
+            .new_image(mev::ImageDesc {
+                dimensions: info.extent.into(),
+                format: info.format,
+                usage: info.usage,
+                layers: 1,
+                cube: false,
+                levels: 1,
+                name,
+            })
+            .unwrap();
+
+        Image3D(image)
+    }
+
+    fn merge_info(info: &mut Image3DInfo, other: &Image3DInfo) -> bool {
+        if info.format != other.format {
+            return false;
+        }
+
+        info.extent = mev::Extent3::new(
             info.extent.width().max(other.extent.width()),
             info.extent.height().max(other.extent.height()),
+            info.extent.depth().max(other.extent.depth()),
         );
+        info.usage |= other.usage;
+
+        true
+    }
+
+    fn write_access() -> Access {
+        Access::Storage
+    }
+
+    fn read_access() -> Access {
+        Access::Shader
+    }
+
+    fn image(&self) -> Option<&mev::Image> {
+        Some(&self.0)
+    }
+
+    fn memory_size(info: &Image3DInfo) -> u64 {
+        image_memory_size(info.extent, info.format)
+    }
+}
+
+/// Buffer target.
+/// Used for data produced on GPU, like particles or indirect draw arguments.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, WithStid)]
+pub struct Buffer(pub mev::Buffer);
+
+impl Deref for Buffer {
+    type Target = mev::Buffer;
+
+    fn deref(&self) -> &mev::Buffer {
+        &self.0
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BufferInfo {
+    pub size: usize,
+    pub usage: mev::BufferUsage,
+    pub memory: mev::Memory,
+}
+
+impl target::Target for Buffer {
+    type Info = BufferInfo;
+
+    fn allocate(device: &mev::Device, name: &str, info: &BufferInfo) -> Self {
+        let buffer = device
+            .new_buffer(mev::BufferDesc {
+                size: info.size,
+                usage: info.usage,
+                memory: info.memory,
+                name,
+            })
+            .unwrap();
+
+        Buffer(buffer)
+    }
 
+    fn merge_info(info: &mut BufferInfo, other: &BufferInfo) -> bool {
+        if info.memory != other.memory {
+            return false;
+        }
+
+        info.size = info.size.max(other.size);
         info.usage |= other.usage;
 
         true
     }
+
+    fn memory_size(info: &BufferInfo) -> u64 {
+        info.size as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_cube_info() {
+        let mut info = ImageCubeInfo {
+            size: 256,
+            format: mev::PixelFormat::Rgba8Srgb,
+            usage: mev::ImageUsage::TARGET,
+        };
+
+        let other = ImageCubeInfo {
+            size: 512,
+            format: mev::PixelFormat::Rgba8Srgb,
+            usage: mev::ImageUsage::SAMPLED,
+        };
+
+        assert!(<ImageCube as Target>::merge_info(&mut info, &other));
+        assert_eq!(info.size, 512);
+        assert_eq!(
+            info.usage,
+            mev::ImageUsage::TARGET | mev::ImageUsage::SAMPLED
+        );
+        assert_eq!(ImageCube::memory_size(&info), 512 * 512 * 6 * 4);
+    }
+
+    #[test]
+    fn merge_cube_info_format_mismatch() {
+        let mut info = ImageCubeInfo {
+            size: 256,
+            format: mev::PixelFormat::Rgba8Srgb,
+            usage: mev::ImageUsage::TARGET,
+        };
+
+        let other = ImageCubeInfo {
+            size: 256,
+            format: mev::PixelFormat::Rgba8Unorm,
+            usage: mev::ImageUsage::SAMPLED,
+        };
+
+        assert!(!<ImageCube as Target>::merge_info(&mut info, &other));
+    }
+
+    #[test]
+    fn merge_array_info() {
+        let mut info = Image2DArrayInfo {
+            extent: mev::Extent2::new(128, 64),
+            layers: 2,
+            format: mev::PixelFormat::Rgba8Unorm,
+            usage: mev::ImageUsage::TARGET,
+        };
+
+        let other = Image2DArrayInfo {
+            extent: mev::Extent2::new(64, 128),
+            layers: 4,
+            format: mev::PixelFormat::Rgba8Unorm,
+            usage: mev::ImageUsage::SAMPLED,
+        };
+
+        assert!(<Image2DArray as Target>::merge_info(&mut info, &other));
+        assert_eq!(info.extent, mev::Extent2::new(128, 128));
+        assert_eq!(info.layers, 4);
+    }
+
+    #[test]
+    fn merge_depth_info() {
+        let mut info = DepthImage2DInfo {
+            extent: mev::Extent2::new(64, 64),
+            format: mev::PixelFormat::D16Unorm,
+            usage: mev::ImageUsage::TARGET,
+        };
+
+        let other = DepthImage2DInfo {
+            extent: mev::Extent2::new(64, 64),
+            format: mev::PixelFormat::D32Float,
+            usage: mev::ImageUsage::SAMPLED,
+        };
+
+        assert!(<DepthImage2D as Target>::merge_info(&mut info, &other));
+        assert_eq!(info.format, mev::PixelFormat::D32Float);
+        assert_eq!(
+            info.usage,
+            mev::ImageUsage::TARGET | mev::ImageUsage::SAMPLED
+        );
+
+        let smaller = DepthImage2DInfo {
+            extent: mev::Extent2::new(32, 32),
+            format: mev::PixelFormat::D32Float,
+            usage: mev::ImageUsage::SAMPLED,
+        };
+
+        assert!(!<DepthImage2D as Target>::merge_info(&mut info, &smaller));
+
+        let color = DepthImage2DInfo {
+            extent: mev::Extent2::new(64, 64),
+            format: mev::PixelFormat::Rgba8Unorm,
+            usage: mev::ImageUsage::SAMPLED,
+        };
+
+        assert!(!<DepthImage2D as Target>::merge_info(&mut info, &color));
+    }
 }
@@ -0,0 +1,41 @@
+use edict::{Component, Entities, EntityId, World};
+
+use crate::{make_id, model::Value};
+
+make_id!(pub ParamSourceId);
+
+/// Source of job parameter value.
+///
+/// Param bound to a source is resolved from the world every frame
+/// before jobs are planned.
+/// If source returns `None`, last value of the param is kept.
+pub type ParamSource = Box<dyn Fn(&World) -> Option<Value>>;
+
+/// Makes param source that reads value from the resource.
+pub fn resource_param<R, F>(f: F) -> impl Fn(&World) -> Option<Value> + 'static
+where
+    R: Sync + 'static,
+    F: Fn(&R) -> Value + 'static,
+{
+    move |world: &World| {
+        let resource = world.get_resource::<R>()?;
+        Some(f(&resource))
+    }
+}
+
+/// Makes param source that reads value from the component
+/// of the first entity that has it.
+///
+/// Useful with marker components to fetch
+/// entity like main camera.
+pub fn component_param<C, F>(f: F) -> impl Fn(&World) -> Option<Value> + 'static
+where
+    C: Component + Sync,
+    F: Fn(EntityId, &C) -> Value + 'static,
+{
+    move |world: &World| {
+        let mut view = world.view::<(Entities, &C)>();
+        let (e, c) = view.iter().next()?;
+        Some(f(e.id(), c))
+    }
+}
@@ -0,0 +1,217 @@
+use arcana_names::Name;
+use edict::{Component, EntityId, World};
+use hashbrown::HashSet;
+
+use crate::{plugin::PluginsHub, viewport::Viewport};
+
+use super::{graph::PinId, Image2D, Image2DInfo, WorkGraph};
+
+/// Error that may occur when running work graph for presents.
+#[derive(Debug, thiserror::Error)]
+pub enum PresentError {
+    #[error("Failed to acquire viewport frame: {0}")]
+    Surface(#[from] mev::SurfaceError),
+
+    #[error("Failed to run work graph: {0}")]
+    Device(#[from] mev::DeviceError),
+}
+
+impl From<mev::OutOfMemory> for PresentError {
+    fn from(_: mev::OutOfMemory) -> Self {
+        PresentError::Device(mev::DeviceError::OutOfMemory)
+    }
+}
+
+/// Returns name of the present rendered into the main viewport.
+pub fn main_present() -> Name {
+    crate::ident!(main).into()
+}
+
+/// Component that makes work graph present with given name
+/// rendered into the [`Viewport`] of the same entity.
+///
+/// Use it for split-screen, picture-in-picture and additional views.
+#[derive(Clone, Copy, Debug)]
+pub struct Present {
+    pub name: Name,
+}
+
+impl Component for Present {
+    fn name() -> &'static str {
+        "Present"
+    }
+}
+
+/// Resource with enable flags of work graph presents.
+///
+/// Presents are enabled unless disabled explicitly.
+/// Jobs that contribute only to disabled presents are not executed.
+#[derive(Clone, Debug, Default)]
+pub struct PresentFlags {
+    disabled: HashSet<Name>,
+}
+
+impl PresentFlags {
+    pub fn new() -> Self {
+        PresentFlags::default()
+    }
+
+    pub fn is_enabled(&self, name: Name) -> bool {
+        !self.disabled.contains(&name)
+    }
+
+    pub fn set_enabled(&mut self, name: Name, enabled: bool) {
+        if enabled {
+            self.disabled.remove(&name);
+        } else {
+            self.disabled.insert(name);
+        }
+    }
+
+    pub fn enable(&mut self, name: Name) {
+        self.set_enabled(name, true);
+    }
+
+    pub fn disable(&mut self, name: Name) {
+        self.set_enabled(name, false);
+    }
+}
+
+/// Runs work graph once for all enabled presents.
+///
+/// Present named [`main_present`] is rendered into `viewport`.
+/// Other presents are rendered into viewports of entities
+/// with matching [`Present`] component.
+/// Jobs shared by multiple presents are executed once.
+/// Presents that reuse pin of a preceding present are skipped.
+pub fn run_presents(
+    workgraph: &mut WorkGraph,
+    presents: &[(Name, PinId)],
+    viewport: Option<&mut Viewport>,
+    device: &mev::Device,
+    queue: &mut mev::Queue,
+    world: &mut World,
+    hub: &mut PluginsHub,
+) -> Result<(), PresentError> {
+    let main = main_present();
+    let flags = world
+        .get_resource::<PresentFlags>()
+        .map(|f| PresentFlags::clone(&f));
+    let enabled = |name: Name| flags.as_ref().map_or(true, |f| f.is_enabled(name));
+
+    let mut viewport = viewport;
+    let mut frames = Vec::new();
+    let mut pins = HashSet::new();
+    let mut any = false;
+
+    for &(name, pin) in presents {
+        if !pins.insert(pin) {
+            tracing::warn!("Present '{name}' uses the same pin as another present, skipping");
+            continue;
+        }
+
+        let image = if !enabled(name) {
+            None
+        } else if name == main {
+            match viewport.as_deref_mut() {
+                None => None,
+                Some(viewport) => viewport.next_frame(device, queue, mev::PipelineStages::all())?,
+            }
+        } else {
+            let mut image = None;
+            for (present, viewport) in world.view_mut::<(&Present, &mut Viewport)>() {
+                if present.name == name {
+                    image = viewport.next_frame(device, queue, mev::PipelineStages::all())?;
+                    break;
+                }
+            }
+            image
+        };
+
+        match image {
+            None => workgraph.set_sink_enabled(pin, false),
+            Some((image, frame)) => {
+                let info = Image2DInfo::from_image(&image);
+                workgraph.set_sink(pin, Image2D(image), info);
+                workgraph.set_sink_enabled(pin, true);
+                frames.extend(frame);
+                any = true;
+            }
+        }
+    }
+
+    if !any {
+        return Ok(());
+    }
+
+    run_and_present(workgraph, frames, device, queue, world, hub)
+}
+
+/// Runs work graph to render into single viewport and presents the frame.
+///
+/// Viewport is taken from the `entity` if specified,
+/// otherwise from [`Viewport`] resource.
+/// Image of the viewport is set to the `pin` sink.
+///
+/// Does nothing if there is no viewport or it has no image to render into.
+pub fn run_viewport(
+    workgraph: &mut WorkGraph,
+    pin: PinId,
+    entity: Option<EntityId>,
+    device: &mev::Device,
+    queue: &mut mev::Queue,
+    world: &mut World,
+    hub: &mut PluginsHub,
+) -> Result<(), PresentError> {
+    let next_frame = match entity {
+        None => match world.get_resource_mut::<Viewport>() {
+            None => return Ok(()),
+            Some(mut viewport) => viewport.next_frame(device, queue, mev::PipelineStages::all())?,
+        },
+        Some(entity) => match world.get::<&mut Viewport>(entity) {
+            Err(_) => return Ok(()),
+            Ok(viewport) => viewport.next_frame(device, queue, mev::PipelineStages::all())?,
+        },
+    };
+
+    let Some((image, frame)) = next_frame else {
+        return Ok(());
+    };
+
+    let info = Image2DInfo::from_image(&image);
+    workgraph.set_sink(pin, Image2D(image), info);
+    workgraph.set_sink_enabled(pin, true);
+
+    run_and_present(
+        workgraph,
+        frame.into_iter().collect(),
+        device,
+        queue,
+        world,
+        hub,
+    )
+}
+
+/// Runs work graph and presents frames rendered by it.
+fn run_and_present(
+    workgraph: &mut WorkGraph,
+    frames: Vec<mev::Frame>,
+    device: &mev::Device,
+    queue: &mut mev::Queue,
+    world: &mut World,
+    hub: &mut PluginsHub,
+) -> Result<(), PresentError> {
+    workgraph.run(device, queue, world, hub)?;
+
+    if !frames.is_empty() {
+        let mut encoder = queue.new_command_encoder()?;
+        for frame in frames {
+            encoder.present(frame, mev::PipelineStages::all());
+        }
+        let buffer = encoder.finish()?;
+
+        queue.submit(std::iter::once(buffer), true)?;
+    }
+
+    Ok(())
+}
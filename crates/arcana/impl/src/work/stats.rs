@@ -0,0 +1,160 @@
+use std::{collections::VecDeque, time::Duration};
+
+use hashbrown::HashMap;
+
+use super::graph::JobIdx;
+
+/// Maximum number of runs with timestamps not yet read back.
+/// Runs above this limit are not timed on GPU.
+const MAX_PENDING: usize = 4;
+
+/// Timings of a single job.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct JobStats {
+    /// CPU time spent planning the job.
+    pub plan: Duration,
+
+    /// Offset of the job execution from the start of the run.
+    pub exec_start: Duration,
+
+    /// CPU time spent executing the job and its hooks.
+    pub exec: Duration,
+
+    /// Offset of the job GPU work from the start of the frame GPU work.
+    pub gpu_start: Option<Duration>,
+
+    /// GPU time of the job commands.
+    pub gpu: Option<Duration>,
+}
+
+/// Timings of the work graph run.
+///
+/// Work graph inserts it into the world as a resource after each run.
+/// GPU timings are read back when available
+/// and thus lag few frames behind CPU timings.
+/// They are absent if device does not support timestamps.
+#[derive(Clone, Debug, Default)]
+pub struct WorkGraphStats {
+    /// Timings of executed jobs in execution order.
+    pub jobs: Vec<(JobIdx, JobStats)>,
+
+    /// CPU time of the whole run.
+    pub cpu: Duration,
+
+    /// GPU time of the whole frame.
+    pub gpu: Option<Duration>,
+}
+
+impl WorkGraphStats {
+    /// Returns timings of the job if it was executed.
+    pub fn job(&self, idx: JobIdx) -> Option<&JobStats> {
+        self.jobs.iter().find(|(i, _)| *i == idx).map(|(_, s)| s)
+    }
+}
+
+/// Manages timestamp queries written around jobs
+/// and reads them back when GPU is done.
+pub(super) struct GpuTimer {
+    free: Vec<mev::QueryPool>,
+    pending: VecDeque<(mev::QueryPool, Vec<JobIdx>)>,
+
+    /// Start offset and duration of the jobs from last resolved frame.
+    jobs: HashMap<JobIdx, (Duration, Duration)>,
+
+    /// Duration of last resolved frame.
+    frame: Option<Duration>,
+}
+
+impl GpuTimer {
+    pub fn new() -> Self {
+        GpuTimer {
+            free: Vec::new(),
+            pending: VecDeque::new(),
+            jobs: HashMap::new(),
+            frame: None,
+        }
+    }
+
+    /// Reads back timestamps of finished frames.
+    pub fn poll(&mut self, device: &mev::Device) {
+        let Some(period) = device.timestamp_period() else {
+            return;
+        };
+
+        let to_duration = |ticks: u64| Duration::from_nanos((ticks as f64 * period as f64) as u64);
+
+        while let Some((pool, jobs)) = self.pending.front() {
+            let Some(timestamps) = pool.timestamps(0..jobs.len() as u32 * 2) else {
+                break;
+            };
+
+            let base = timestamps.first().copied().unwrap_or(0);
+
+            self.jobs.clear();
+            for (k, &idx) in jobs.iter().enumerate() {
+                let begin = timestamps[k * 2];
+                let end = timestamps[k * 2 + 1];
+
+                self.jobs.insert(
+                    idx,
+                    (
+                        to_duration(begin.wrapping_sub(base)),
+                        to_duration(end.wrapping_sub(begin)),
+                    ),
+                );
+            }
+
+            self.frame = timestamps
+                .last()
+                .map(|&end| to_duration(end.wrapping_sub(base)));
+
+            let (pool, _) = self.pending.pop_front().unwrap();
+            self.free.push(pool);
+        }
+    }
+
+    /// Returns query pool to time `count` jobs.
+    /// Two queries are used per job.
+    ///
+    /// Returns `None` if timestamps are not supported
+    /// or too many frames are pending.
+    pub fn begin(&mut self, device: &mev::Device, count: usize) -> Option<mev::QueryPool> {
+        device.timestamp_period()?;
+
+        if count == 0 || self.pending.len() >= MAX_PENDING {
+            return None;
+        }
+
+        let needed = count as u32 * 2;
+
+        // Pools too small for current graph are not needed anymore.
+        self.free.retain(|pool| pool.count() >= needed);
+
+        match self.free.pop() {
+            Some(pool) => Some(pool),
+            None => device.new_query_pool(needed).ok(),
+        }
+    }
+
+    /// Enqueues pool with timestamps of the jobs for read back.
+    pub fn end(&mut self, pool: mev::QueryPool, jobs: Vec<JobIdx>) {
+        self.pending.push_back((pool, jobs));
+    }
+
+    /// Returns pool that won't be read back for reuse.
+    /// Used when commands that write timestamps fail to be submitted.
+    pub fn release(&mut self, pool: mev::QueryPool) {
+        self.free.push(pool);
+    }
+
+    /// Returns start offset and duration of the job
+    /// from the last resolved frame.
+    pub fn job(&self, idx: JobIdx) -> Option<(Duration, Duration)> {
+        self.jobs.get(&idx).copied()
+    }
+
+    /// Returns duration of the last resolved frame.
+    pub fn frame(&self) -> Option<Duration> {
+        self.frame
+    }
+}
@@ -1,13 +1,11 @@
-use std::{
-    any::TypeId,
-    hash::Hash,
-    mem::{transmute, ManuallyDrop},
-};
+use std::any::{Any, TypeId};
 
 use hashbrown::HashMap;
 
 use crate::{make_id, stid::WithStid};
 
+use super::job::Access;
+
 make_id!(pub TargetId);
 
 pub trait Target: WithStid + 'static {
@@ -26,50 +24,192 @@ pub trait Target: WithStid + 'static {
     {
         false
     }
+
+    /// Checks if target allocated with `info` can be used
+    /// where target with `other` info is requested.
+    ///
+    /// Targets with compatible infos share allocation
+    /// when they are not in use at the same time.
+    fn compatible(info: &Self::Info, other: &Self::Info) -> bool
+    where
+        Self: Sized,
+    {
+        *info == *other
+    }
+
+    /// Access of jobs creating or updating the target
+    /// through pins without declared access.
+    fn write_access() -> Access
+    where
+        Self: Sized,
+    {
+        Access::All
+    }
+
+    /// Access of jobs reading the target
+    /// through pins without declared access.
+    fn read_access() -> Access
+    where
+        Self: Sized,
+    {
+        Access::All
+    }
+
+    /// Returns image of the target.
+    /// Image content is discarded when job creates the target.
+    fn image(&self) -> Option<&mev::Image> {
+        None
+    }
+
+    /// Returns size of the target memory in bytes.
+    /// Used only for memory reports.
+    fn memory_size(_info: &Self::Info) -> u64
+    where
+        Self: Sized,
+    {
+        0
+    }
 }
 
-struct AnyHashMap<K> {
-    map: ManuallyDrop<HashMap<K, u8>>,
-    drop: fn(HashMap<K, u8>),
+/// Kind of access to a target through job pin.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum PinAccess {
+    Create,
+    Update,
+    Read,
 }
 
-impl<K> Drop for AnyHashMap<K> {
-    fn drop(&mut self) {
-        unsafe {
-            (self.drop)(ManuallyDrop::take(&mut self.map));
+/// Barriers to record before job accesses its targets.
+pub(super) struct Barriers {
+    after: mev::PipelineStages,
+    before: mev::PipelineStages,
+    images: Vec<(mev::PipelineStages, mev::PipelineStages, mev::Image)>,
+}
+
+impl Barriers {
+    pub fn new() -> Self {
+        Barriers {
+            after: mev::PipelineStages::empty(),
+            before: mev::PipelineStages::empty(),
+            images: Vec::new(),
         }
     }
-}
 
-impl<K> AnyHashMap<K> {
-    pub fn new<V: 'static>() -> Self {
-        unsafe {
-            AnyHashMap {
-                map: transmute(HashMap::<K, V>::new()),
-                drop: |map: HashMap<K, u8>| {
-                    transmute::<HashMap<K, u8>, HashMap<K, V>>(map);
-                },
-            }
+    pub fn is_empty(&self) -> bool {
+        self.before.is_empty() && self.images.is_empty()
+    }
+
+    /// Records barriers into the encoder and clears them.
+    pub fn record(&mut self, encoder: &mut mev::CommandEncoder) {
+        if !self.before.is_empty() {
+            encoder.barrier(self.after, self.before);
         }
+
+        for (after, before, image) in self.images.drain(..) {
+            encoder.init_image(after, before, &image);
+        }
+
+        self.after = mev::PipelineStages::empty();
+        self.before = mev::PipelineStages::empty();
     }
+}
+
+/// Last accesses to the target memory.
+#[derive(Clone, Copy)]
+struct AccessState {
+    /// Stages of the last write.
+    written: mev::PipelineStages,
+
+    /// Stages that read since the last write.
+    read: mev::PipelineStages,
 
-    pub unsafe fn downcast_ref<V: 'static>(&self) -> &HashMap<K, V> {
-        unsafe { transmute(&self.map) }
+    /// Stages already synchronized with the last write.
+    synced: mev::PipelineStages,
+}
+
+impl AccessState {
+    fn new() -> Self {
+        AccessState {
+            written: mev::PipelineStages::empty(),
+            read: mev::PipelineStages::empty(),
+            synced: mev::PipelineStages::empty(),
+        }
     }
 
-    pub unsafe fn downcast_mut<V: 'static>(&mut self) -> &mut HashMap<K, V> {
-        unsafe { transmute(&mut self.map) }
+    /// Adds barriers required for the access and updates the state.
+    fn access(
+        &mut self,
+        kind: PinAccess,
+        stages: mev::PipelineStages,
+        image: Option<&mev::Image>,
+        barriers: &mut Barriers,
+    ) {
+        match kind {
+            PinAccess::Read => {
+                // Reads after reads need no synchronization.
+                let unsynced = stages - self.synced;
+                if !self.written.is_empty() && !unsynced.is_empty() {
+                    barriers.after |= self.written;
+                    barriers.before |= unsynced;
+                    self.synced |= unsynced;
+                }
+                self.read |= stages;
+                return;
+            }
+            PinAccess::Create if image.is_some() => {
+                // Content is discarded, so previous writes need not be visible.
+                // Image is transitioned into usable layout.
+                let image = image.unwrap();
+                barriers
+                    .images
+                    .push((self.written | self.read, stages, image.clone()));
+            }
+            PinAccess::Create | PinAccess::Update => {
+                let after = self.written | self.read;
+                if !after.is_empty() {
+                    barriers.after |= after;
+                    barriers.before |= stages;
+                }
+            }
+        }
+
+        self.written = stages;
+        self.read = mev::PipelineStages::empty();
+        self.synced = mev::PipelineStages::empty();
     }
 }
 
+/// Allocated target instance that may back multiple targets
+/// which are not in use at the same time.
+struct Allocation<T: Target> {
+    instance: T,
+    info: T::Info,
+
+    /// Last accesses to the allocation.
+    /// Preserved between frames.
+    state: AccessState,
+
+    /// Earliest position in the plan at which any target
+    /// backed by this allocation is in use in current frame.
+    ///
+    /// `None` if allocation is not used in current frame.
+    busy_from: Option<usize>,
+}
+
 pub struct TargetData<T: Target> {
     external: Option<(T, T::Info)>,
 
-    /// Allocated target instance.
-    target: Option<(T, T::Info)>,
+    /// Last accesses to the external target.
+    external_state: AccessState,
+
+    /// Index of allocation backing this target in current frame.
+    allocation: Option<usize>,
 
     /// Target info.
     new_info: Option<T::Info>,
+
+    /// Range of plan positions in which target is in use in current frame.
+    live: Option<(usize, usize)>,
 }
 
 impl<T> TargetData<T>
@@ -79,14 +219,17 @@ where
     fn new() -> Self {
         TargetData {
             external: None,
-            target: None,
+            external_state: AccessState::new(),
+            allocation: None,
             new_info: None,
+            live: None,
         }
     }
 
     pub fn external(&mut self, instance: T, info: T::Info) {
         self.external = Some((instance, info));
-        self.target = None;
+        self.external_state = AccessState::new();
+        self.allocation = None;
         self.new_info = None;
     }
 
@@ -94,64 +237,223 @@ where
         self.external = None;
     }
 
-    pub fn plan_create<'a>(&'a mut self, name: &str, device: &mev::Device) -> Option<&'a T::Info> {
+    /// Marks target as used at given plan position.
+    fn use_at(&mut self, order: usize) {
+        self.live = match self.live {
+            None => Some((order, order)),
+            Some((start, end)) => Some((start.min(order), end.max(order))),
+        };
+    }
+
+    pub fn plan_update(&mut self, order: usize) -> Option<&T::Info> {
+        self.use_at(order);
+
         if let Some((_, info)) = &self.external {
             return Some(info);
         }
 
-        let new_info = self.new_info.take()?;
+        self.new_info.as_ref()
+    }
+
+    pub fn plan_read(&mut self, info: T::Info, order: usize) {
+        self.use_at(order);
 
-        if let Some((_, info)) = &self.target {
-            if *info != new_info {
-                self.target = None;
+        match self.new_info {
+            None => {
+                self.new_info = Some(info);
+            }
+            Some(ref mut new_info) => {
+                T::merge_info(new_info, &info);
             }
         }
+    }
+}
 
-        if self.target.is_none() {
-            let instance = T::allocate(device, name, &new_info);
-            self.target = Some((instance, new_info));
-        }
+/// All targets of one type and allocations backing them.
+struct TypedTargets<T: Target> {
+    data: HashMap<TargetId, TargetData<T>>,
+    allocations: Vec<Allocation<T>>,
+}
 
-        match &self.target {
-            Some((_, info)) => Some(info),
-            None => unreachable!(),
+impl<T> TypedTargets<T>
+where
+    T: Target,
+{
+    fn new() -> Self {
+        TypedTargets {
+            data: HashMap::new(),
+            allocations: Vec::new(),
         }
     }
 
-    pub fn plan_update(&mut self) -> Option<&T::Info> {
-        if let Some((_, info)) = &self.external {
-            return Some(info);
+    fn plan_create(
+        &mut self,
+        id: TargetId,
+        name: &str,
+        device: &mev::Device,
+        order: usize,
+    ) -> Option<&T::Info> {
+        let data = self.data.get_mut(&id)?;
+
+        if data.external.is_some() {
+            return data.external.as_ref().map(|(_, info)| info);
         }
 
-        self.new_info.as_ref()
-    }
+        let new_info = data.new_info.take()?;
 
-    pub fn plan_read(&mut self, info: T::Info) {
-        match self.new_info {
+        // Jobs are planned in reverse order.
+        // All users of the target were already planned and
+        // all targets planned before start later than this one.
+        data.use_at(order);
+        let (start, end) = data.live.unwrap();
+
+        // Find allocation that is free for the whole live range of the target.
+        let idx = self.allocations.iter().position(|a| {
+            a.busy_from.map_or(true, |busy_from| end < busy_from)
+                && T::compatible(&a.info, &new_info)
+        });
+
+        let idx = match idx {
+            Some(idx) => idx,
             None => {
-                self.new_info = Some(info);
+                let instance = T::allocate(device, name, &new_info);
+                self.allocations.push(Allocation {
+                    instance,
+                    info: new_info,
+                    state: AccessState::new(),
+                    busy_from: None,
+                });
+                self.allocations.len() - 1
             }
-            Some(ref mut new_info) => {
-                T::merge_info(new_info, &info);
+        };
+
+        let allocation = &mut self.allocations[idx];
+        allocation.busy_from = Some(start);
+        data.allocation = Some(idx);
+
+        Some(&allocation.info)
+    }
+
+    fn instance(&self, id: TargetId) -> Option<&T> {
+        let data = self.data.get(&id)?;
+
+        if let Some((instance, _)) = &data.external {
+            return Some(instance);
+        }
+
+        let idx = data.allocation?;
+        Some(&self.allocations[idx].instance)
+    }
+
+    fn access(
+        &mut self,
+        id: TargetId,
+        kind: PinAccess,
+        stages: mev::PipelineStages,
+        barriers: &mut Barriers,
+    ) {
+        let Some(data) = self.data.get_mut(&id) else {
+            return;
+        };
+
+        let (state, instance) = match (&data.external, data.allocation) {
+            (Some((instance, _)), _) => (&mut data.external_state, instance),
+            (None, Some(idx)) => {
+                let allocation = &mut self.allocations[idx];
+                (&mut allocation.state, &allocation.instance)
             }
+            (None, None) => return,
+        };
+
+        state.access(kind, stages, instance.image(), barriers);
+    }
+}
+
+/// Type-erased [`TypedTargets`].
+trait AnyTargets {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    /// Resets per-frame state.
+    /// Frees allocations that were not used in previous frame.
+    fn begin_frame(&mut self);
+
+    /// Reports allocated memory and collects
+    /// live ranges and sizes of allocated targets.
+    fn report(&self, allocated: &mut u64, live: &mut Vec<(usize, usize, u64)>);
+
+    /// Adds barriers required for the access to the target.
+    fn access(
+        &mut self,
+        id: TargetId,
+        kind: PinAccess,
+        stages: mev::PipelineStages,
+        barriers: &mut Barriers,
+    );
+}
+
+impl<T> AnyTargets for TypedTargets<T>
+where
+    T: Target,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn begin_frame(&mut self) {
+        self.allocations.retain(|a| a.busy_from.is_some());
+
+        for allocation in &mut self.allocations {
+            allocation.busy_from = None;
+        }
+
+        for data in self.data.values_mut() {
+            data.allocation = None;
+            data.live = None;
         }
     }
 
-    pub fn instance(&self) -> Option<&T> {
-        if let Some((instance, _)) = &self.external {
-            return Some(instance);
+    fn report(&self, allocated: &mut u64, live: &mut Vec<(usize, usize, u64)>) {
+        for allocation in &self.allocations {
+            *allocated += T::memory_size(&allocation.info);
         }
 
-        if let Some((instance, _)) = &self.target {
-            return Some(instance);
+        for data in self.data.values() {
+            if let (Some(idx), Some((start, end))) = (data.allocation, data.live) {
+                live.push((start, end, T::memory_size(&self.allocations[idx].info)));
+            }
         }
+    }
 
-        None
+    fn access(
+        &mut self,
+        id: TargetId,
+        kind: PinAccess,
+        stages: mev::PipelineStages,
+        barriers: &mut Barriers,
+    ) {
+        TypedTargets::access(self, id, kind, stages, barriers);
     }
 }
 
+/// Memory used by targets allocated by work graph in last frame.
+/// External targets are not included.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TargetMemoryReport {
+    /// Total size of target allocations in bytes.
+    pub allocated: u64,
+
+    /// Peak size of targets that are in use at the same time in bytes.
+    /// This is how much memory would be required with perfect aliasing.
+    pub peak: u64,
+}
+
 pub struct TargetHub {
-    types: HashMap<TypeId, AnyHashMap<TargetId>>,
+    types: HashMap<TypeId, Box<dyn AnyTargets>>,
 }
 
 impl TargetHub {
@@ -161,49 +463,72 @@ impl TargetHub {
         }
     }
 
+    fn typed<T: Target>(&self) -> Option<&TypedTargets<T>> {
+        let any = self.types.get(&TypeId::of::<T>())?;
+        any.as_any().downcast_ref()
+    }
+
+    fn typed_mut<T: Target>(&mut self) -> Option<&mut TypedTargets<T>> {
+        let any = self.types.get_mut(&TypeId::of::<T>())?;
+        any.as_any_mut().downcast_mut()
+    }
+
+    fn make_typed_mut<T: Target>(&mut self) -> &mut TypedTargets<T> {
+        let any = self
+            .types
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(TypedTargets::<T>::new()));
+        any.as_any_mut().downcast_mut().unwrap()
+    }
+
     pub fn data<T: Target>(&self, id: TargetId) -> Option<&TargetData<T>> {
-        let any_hub = self.types.get(&TypeId::of::<T>())?;
-        let typed_hub = unsafe { any_hub.downcast_ref::<TargetData<T>>() };
-        typed_hub.get(&id)
+        self.typed::<T>()?.data.get(&id)
     }
 
     pub fn data_mut<T: Target>(&mut self, id: TargetId) -> Option<&mut TargetData<T>> {
-        let any_hub = self.types.get_mut(&TypeId::of::<T>())?;
-        let typed_hub = unsafe { any_hub.downcast_mut::<TargetData<T>>() };
-        typed_hub.get_mut(&id)
+        self.typed_mut::<T>()?.data.get_mut(&id)
     }
 
     pub fn make_data_mut<T: Target>(&mut self, id: TargetId) -> &mut TargetData<T> {
-        let any_hub = self
-            .types
-            .entry(TypeId::of::<T>())
-            .or_insert_with(|| AnyHashMap::<TargetId>::new::<TargetData<T>>());
-        let typed_hub = unsafe { any_hub.downcast_mut::<TargetData<T>>() };
-        typed_hub.entry(id).or_insert_with(|| TargetData::new())
+        self.make_typed_mut::<T>()
+            .data
+            .entry(id)
+            .or_insert_with(|| TargetData::new())
     }
 
+    /// Resets per-frame state before planning.
+    pub fn begin_frame(&mut self) {
+        for typed in self.types.values_mut() {
+            typed.begin_frame();
+        }
+    }
+
+    /// Plans creation of the target by job at given plan position.
+    ///
+    /// Targets that are not in use at the same time
+    /// share allocations if their infos are compatible.
     pub fn plan_create<T: Target>(
         &mut self,
         id: TargetId,
         name: &str,
         device: &mev::Device,
+        order: usize,
     ) -> Option<&T::Info> {
-        let data = self.data_mut::<T>(id)?;
-        data.plan_create(name, device)
+        self.typed_mut::<T>()?.plan_create(id, name, device, order)
     }
 
-    pub fn plan_update<T: Target>(&mut self, id: TargetId) -> Option<&T::Info> {
+    pub fn plan_update<T: Target>(&mut self, id: TargetId, order: usize) -> Option<&T::Info> {
         let data = self.data_mut::<T>(id)?;
-        data.plan_update()
+        data.plan_update(order)
     }
 
-    pub fn plan_read<T: Target>(&mut self, id: TargetId, info: T::Info) {
+    pub fn plan_read<T: Target>(&mut self, id: TargetId, info: T::Info, order: usize) {
         let data = self.make_data_mut::<T>(id);
-        data.plan_read(info);
+        data.plan_read(info, order);
     }
 
     pub fn get<T: Target>(&self, id: TargetId) -> Option<&T> {
-        self.data::<T>(id)?.instance()
+        self.typed::<T>()?.instance(id)
     }
 
     pub fn external<T: Target>(&mut self, id: TargetId, instance: T, info: T::Info) {
@@ -221,4 +546,52 @@ impl TargetHub {
     pub fn clear(&mut self) {
         self.types.clear();
     }
+
+    /// Adds barriers required for the access to the target of given type.
+    pub(super) fn access(
+        &mut self,
+        ty: TypeId,
+        id: TargetId,
+        kind: PinAccess,
+        stages: mev::PipelineStages,
+        barriers: &mut Barriers,
+    ) {
+        if let Some(typed) = self.types.get_mut(&ty) {
+            typed.access(id, kind, stages, barriers);
+        }
+    }
+
+    /// Reports memory used by targets planned in current frame.
+    pub fn memory_report(&self) -> TargetMemoryReport {
+        let mut allocated = 0;
+        let mut live = Vec::new();
+
+        for typed in self.types.values() {
+            typed.report(&mut allocated, &mut live);
+        }
+
+        // Sweep over range boundaries to find peak usage.
+        let mut events = Vec::with_capacity(live.len() * 2);
+        for (start, end, size) in live {
+            events.push((start, false, size));
+            events.push((end, true, size));
+        }
+
+        // Targets starting at the position where another ends
+        // are in use at the same time, so starts go first.
+        events.sort_unstable_by_key(|&(order, end, _)| (order, end));
+
+        let mut current = 0;
+        let mut peak = 0;
+        for (_, end, size) in events {
+            if end {
+                current -= size;
+            } else {
+                current += size;
+                peak = peak.max(current);
+            }
+        }
+
+        TargetMemoryReport { allocated, peak }
+    }
 }
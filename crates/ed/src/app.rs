@@ -1,16 +1,10 @@
 use std::{path::PathBuf, sync::Arc};
 
 use arcana::{
-    blink_alloc::BlinkAlloc,
-    edict::world::WorldLocal,
-    input::ViewportInput,
-    mev,
-    project::Project,
-    render::{render, RenderGraph, RenderResources},
-    viewport::Viewport,
-    ClockStep, Entities, IdGen, With, World,
+    edict::world::WorldLocal, input::ViewportInput, mev, project::Project, viewport::Viewport,
+    ClockStep, Entities, EntityId, IdGen, With, World,
 };
-use arcana_egui::{Egui, EguiRender, TopBottomPanel, Ui, WidgetText};
+use arcana_egui::{Egui, EguiGraph, TopBottomPanel, Ui, WidgetText};
 use egui::{vec2, Id};
 use egui_dock::{DockState, NodeIndex, TabIndex, TabViewer, Tree};
 use egui_tracing::EventCollector;
@@ -61,10 +55,8 @@ pub struct App {
     // App state is stored in World.
     world: World,
 
-    graph: RenderGraph,
-    resources: RenderResources,
-
-    blink: BlinkAlloc,
+    /// Graphs that draw egui of each window.
+    graphs: HashMap<EntityId, EguiGraph>,
 
     device: mev::Device,
     queue: Arc<Mutex<mev::Queue>>,
@@ -113,6 +105,7 @@ impl App {
         event_collector: EventCollector,
         project: Project,
         data: ProjectData,
+        headless: bool,
     ) -> Self {
         let (device, queue) = init_mev();
         let queue = Arc::new(Mutex::new(queue));
@@ -120,19 +113,20 @@ impl App {
         let builder = World::builder();
 
         let mut world = builder.build();
+        world.insert_resource(Main::new(project.root_path(), headless));
         world.insert_resource(project);
         world.insert_resource(Plugins::new());
         world.insert_resource(Console::new(event_collector));
         world.insert_resource(Systems::new());
         world.insert_resource(Filters::new());
         world.insert_resource(Rendering::new());
-        world.insert_resource(Main::new());
+        world.insert_resource(Codes::new());
         world.insert_resource(device.clone());
         world.insert_resource(queue.clone());
         world.insert_resource(data);
         world.insert_resource(ImageSample::new(&device).unwrap());
 
-        let mut graph = RenderGraph::new();
+        let mut graphs = HashMap::new();
 
         let state = load_app_state().unwrap_or_default();
 
@@ -158,8 +152,7 @@ impl App {
             );
             let id = world.spawn((Viewport::new_window(window), egui)).id();
 
-            let target = EguiRender::build(Some(id), &mut graph);
-            graph.present_to(target, id);
+            graphs.insert(id, EguiGraph::new(Some(id)));
         }
 
         for w in state.windows {
@@ -189,16 +182,13 @@ impl App {
             );
             let id = world.spawn((Viewport::new_window(window), egui)).id();
 
-            let target = EguiRender::build(Some(id), &mut graph);
-            graph.present_to(target, id);
+            graphs.insert(id, EguiGraph::new(Some(id)));
         }
 
         App {
             dock_states,
             world,
-            graph,
-            resources: RenderResources::default(),
-            blink: BlinkAlloc::new(),
+            graphs,
             device,
             queue,
 
@@ -321,15 +311,18 @@ impl App {
             return;
         }
 
-        render(
-            &mut self.graph,
-            &self.device,
-            &mut self.queue.lock(),
-            &self.blink,
-            None,
-            &mut self.world,
-            &mut self.resources,
-        );
+        let mut queue = self.queue.lock();
+
+        // Drop graphs of closed windows.
+        let world = &self.world;
+        self.graphs
+            .retain(|&id, _| world.get::<&Viewport>(id).is_ok());
+
+        for graph in self.graphs.values_mut() {
+            if let Err(err) = graph.render(&self.device, &mut queue, &mut self.world) {
+                tracing::error!(?err);
+            }
+        }
     }
 
     pub fn should_quit(&self) -> bool {
@@ -1,16 +1,13 @@
 //! This module UI to generate flows.
-
-use std::ops::Range;
+//!
+//! Code graphs are executed by the [`arcana::code`] runtime.
 
 use arcana::{
-    code::{CodeDesc, CodeId, Continuation, FlowCode, InputId, OutputCache, OutputId, PureCode},
-    edict::{
-        self, spawn_block,
-        world::{World, WorldLocal},
-        Component, EntityId,
+    code::{
+        CodeDesc, CodeDiagnostics, CodeGraph as CodeGraphData, CodeId, CodeNode, CodePin,
+        Conversions, InputId, OutputId, Wire,
     },
-    events::EventId,
-    flow::FlowEntity,
+    edict::world::WorldLocal,
     Name,
 };
 use egui::{epaint::PathShape, Color32, Painter, PointerButton, Rect, Shape, Stroke, Ui};
@@ -18,440 +15,63 @@ use egui_snarl::{
     ui::{CustomPinShape, PinInfo, PinShape, SnarlStyle, SnarlViewer},
     InPin, InPinId, NodeId, OutPin, OutPinId, Snarl,
 };
-use hashbrown::{HashMap, HashSet};
-use smallvec::SmallVec;
-
-use crate::{data::ProjectData, hue_hash};
 
-#[derive(Clone, Copy, Component)]
-#[edict(name = "Code")]
-pub struct Code {
-    id: CodeId,
-}
+use crate::{container::Container, data::ProjectData, hue_hash};
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
-pub struct CodeNode {
-    id: CodeId,
+/// Code graph edited in the editor.
+///
+/// Runtime uses editor-independent [`arcana::code::CodeGraph`]
+/// produced by [`CodeGraph::data`].
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct CodeGraph {
     name: Name,
-    desc: CodeDesc,
-}
-
-fn schedule_pure_inputs(
-    node: NodeId,
-    inputs: Range<usize>,
-    snarl: &Snarl<CodeNode>,
-) -> Vec<NodeId> {
-    let mut scheduled = HashSet::new();
-    let mut queue = Vec::new();
-    let mut schedule = Vec::new();
-
-    for input in inputs {
-        let inpin = snarl.in_pin(InPinId { node, input });
-        assert!(inpin.remotes.len() <= 1);
-
-        if inpin.remotes.is_empty() {
-            continue;
-        }
-
-        let producer = inpin.remotes[0];
-        queue.push(producer.node);
-    }
-
-    while let Some(node) = queue.pop() {
-        if scheduled.contains(&node) {
-            continue;
-        }
-
-        let mut delay = false;
-
-        match snarl.get_node(node) {
-            Some(CodeNode {
-                desc: CodeDesc::Pure { inputs, .. },
-                ..
-            }) => {
-                for input in 0..inputs.len() {
-                    let inpin = snarl.in_pin(InPinId { node, input });
-                    assert!(inpin.remotes.len() <= 1);
-
-                    if !inpin.remotes.is_empty() {
-                        let producer = inpin.remotes[0];
-                        if !scheduled.contains(&producer.node) {
-                            delay = true;
-                            queue.push(producer.node);
-                        }
-                    }
-                }
-            }
-            _ => continue,
-        }
-
-        if delay {
-            queue.push(node);
-        } else {
-            scheduled.insert(node);
-            schedule.push(node);
-        }
-    }
-
-    schedule
-}
-
-/// Execute specific pure code.
-fn execute_pure(
-    entity: FlowEntity,
-    node: NodeId,
-    snarl: &Snarl<CodeNode>,
-    cache: &mut OutputCache,
-    get_pure_code: &HashMap<CodeId, PureCode>,
-) {
-    let Some(code_node) = snarl.get_node(node) else {
-        tracing::error!("Pure node {node:?} was not found");
-        return;
-    };
-
-    match code_node.desc {
-        CodeDesc::Pure {
-            ref inputs,
-            ref outputs,
-        } => {
-            let pure_code = get_pure_code[&code_node.id];
-
-            let mut outputs = (0..outputs.len())
-                .map(|output| {
-                    cache.take_output(OutputId {
-                        node: node.0,
-                        output,
-                    })
-                })
-                .collect::<SmallVec<[_; 8]>>();
-
-            let Some(inputs) = (0..inputs.len())
-                .map(|input| {
-                    let in_pin = snarl.in_pin(InPinId { node, input });
-                    assert!(in_pin.remotes.len() <= 1);
-                    let out_pin_id = in_pin.remotes[0];
-
-                    cache.input(OutputId {
-                        node: out_pin_id.node.0,
-                        output: out_pin_id.output,
-                    })
-                })
-                .collect::<Option<SmallVec<[_; 8]>>>()
-            else {
-                tracing::error!("Code node {node:?} has missing inputs");
-                return;
-            };
-
-            pure_code(entity, &inputs, &mut outputs);
-        }
-        _ => {
-            tracing::error!("Node {node:?} is not pure");
-            return;
-        }
-    }
+    snarl: Snarl<CodeNode>,
 }
 
-/// Execute specific flow code.
-fn execute_flow(
-    mut entity: FlowEntity,
-    pin: InPinId,
-    snarl: &Snarl<CodeNode>,
-    cache: &mut OutputCache,
-    get_pure_code: &HashMap<CodeId, PureCode>,
-    get_flow_code: &HashMap<CodeId, FlowCode>,
-) -> Option<usize> {
-    let Some(code_node) = snarl.get_node(pin.node) else {
-        tracing::error!("Pure node {:?} was not found", pin.node);
-        return None;
-    };
-
-    match code_node.desc {
-        CodeDesc::Flow {
-            inflows,
-            ref inputs,
-            ref outputs,
-            ..
-        } => {
-            // Check flow connection.
-            if pin.input >= inflows {
-                tracing::error!("Flow {:?} doesn't have inflow {}", pin.node, pin.input);
-                return None;
-            }
-
-            // Grab code function.
-            let flow_code = get_flow_code[&code_node.id];
-
-            // Schedule pure deps.
-            let schedule = schedule_pure_inputs(pin.node, inflows..inflows + inputs.len(), snarl);
-
-            // Execute pure deps.
-            for node in schedule {
-                execute_pure(entity.reborrow(), node, snarl, cache, get_pure_code);
-            }
-
-            // Collect outputs.
-            let mut outputs = (0..outputs.len())
-                .map(|output| {
-                    cache.take_output(OutputId {
-                        node: pin.node.0,
-                        output,
-                    })
-                })
-                .collect::<SmallVec<[_; 8]>>();
-
-            let continuation = {
-                // Collect inputs.
-                let Some(inputs) = (inflows..inflows + inputs.len())
-                    .map(|input| {
-                        let in_pin = snarl.in_pin(InPinId {
-                            node: pin.node,
-                            input,
-                        });
-                        assert!(in_pin.remotes.len() <= 1);
-                        let out_pin_id = in_pin.remotes[0];
-
-                        cache.input(OutputId {
-                            node: out_pin_id.node.0,
-                            output: out_pin_id.output,
-                        })
-                    })
-                    .collect::<Option<SmallVec<[_; 8]>>>()
-                else {
-                    tracing::error!("Code node {:?} has missing inputs", pin.node);
-                    return None;
-                };
-
-                flow_code(
-                    InputId {
-                        node: pin.node.0,
-                        input: pin.input,
+impl CodeGraph {
+    /// Returns code graph to run without the editor.
+    pub fn data(&self) -> CodeGraphData {
+        CodeGraphData {
+            name: self.name,
+            nodes: self
+                .snarl
+                .node_ids()
+                .map(|(id, node)| (id.0, node.clone()))
+                .collect(),
+            wires: self
+                .snarl
+                .wires()
+                .map(|(from, to)| Wire {
+                    from: OutputId {
+                        node: from.node.0,
+                        output: from.output,
                     },
-                    entity.reborrow(),
-                    &inputs,
-                    &mut outputs,
-                )
-            };
-
-            for (output, slot) in outputs.into_iter().enumerate() {
-                cache.put_output(
-                    OutputId {
-                        node: pin.node.0,
-                        output,
+                    to: InputId {
+                        node: to.node.0,
+                        input: to.input,
                     },
-                    slot,
-                )
-            }
-
-            match continuation {
-                Continuation::Continue(output) => Some(output),
-                Continuation::Await(future) => {
-                    let node = pin.node.0;
-
-                    spawn_block!(for entity -> {
-                        let output = future.await;
-                    });
-
-                    None
-                }
-            }
-        }
-        _ => {
-            tracing::error!("Node {:?} is not flow", pin.node);
-            return None;
-        }
-    }
-}
-
-pub struct CodeAfter {
-    entity: EntityId,
-    code: CodeId,
-    outflow: OutPinId,
-}
-
-struct Schedule {
-    queue: Vec<CodeAfter>,
-}
-
-pub fn enque_code_after(entity: EntityId, world: &World, code: CodeId, outflow: OutPinId) {
-    let mut schedule = world.expect_resource_mut::<Schedule>();
-
-    schedule.queue.push(CodeAfter {
-        entity,
-        code,
-        outflow,
-    });
-}
-
-/// Execute code after specific outflow.
-fn execute_code_after(
-    mut entity: FlowEntity,
-    mut outflow: OutPinId,
-    snarl: &Snarl<CodeNode>,
-    cache: &mut OutputCache,
-    get_pure_code: &HashMap<CodeId, PureCode>,
-    get_flow_code: &HashMap<CodeId, FlowCode>,
-) {
-    loop {
-        let Some(code_node) = snarl.get_node(outflow.node) else {
-            tracing::error!("Code node {:?} was not found", outflow.node);
-            return;
-        };
-
-        match code_node.desc {
-            CodeDesc::Pure { .. } => {
-                tracing::error!("Node {:?} is not event or flow", outflow.node);
-                return;
-            }
-            CodeDesc::Event { .. } => {
-                if outflow.output > 0 {
-                    tracing::error!("Events dont have outflow {:?}", outflow.output);
-                    return;
-                }
-            }
-            CodeDesc::Flow { outflows, .. } => {
-                if outflow.output >= outflows {
-                    tracing::error!(
-                        "Flow {:?} doesn't have outflow {:?}",
-                        outflow.node,
-                        outflow.output
-                    );
-                    return;
-                }
-            }
-        }
-
-        let outpin = snarl.out_pin(outflow);
-        assert!(outpin.remotes.len() <= 1);
-
-        if outpin.remotes.is_empty() {
-            // Leaf pin.
-            break;
-        }
-
-        let inflow = outpin.remotes[0];
-
-        let next = execute_flow(
-            entity.reborrow(),
-            inflow,
-            snarl,
-            cache,
-            get_pure_code,
-            get_flow_code,
-        );
-
-        match next {
-            None => break,
-            Some(output) => {
-                outflow = OutPinId {
-                    node: inflow.node,
-                    output,
-                };
-            }
+                })
+                .collect(),
         }
     }
 }
 
-pub fn on_code_event(
-    entity: EntityId,
-    event: EventId,
-    world: &mut World,
-    codes: &HashMap<CodeId, Snarl<CodeNode>>,
-    pure: &HashMap<CodeId, PureCode>,
-    flow: &HashMap<CodeId, FlowCode>,
-    cache: &mut HashMap<EntityId, OutputCache>,
-) {
-    let Ok(Some(Code { id, .. })) = world.try_get_cloned::<Code>(entity) else {
-        tracing::error!("Entity {:?} was despawned", entity);
-        return;
-    };
-
-    let Some(snarl) = codes.get(&id) else {
-        tracing::error!("Code {id:?} is not found");
-        return;
-    };
-
-    let node = snarl
-        .node_ids()
-        .find_map(|(node_id, node)| match node.desc {
-            CodeDesc::Event { id, .. } if id == event => Some(node_id),
-            _ => None,
-        });
-
-    let Some(node) = node else {
-        tracing::error!("Event {event:?} is not found");
-        return;
-    };
-
-    let cache = cache.entry(entity).or_insert_with(OutputCache::new);
-
-    let guard = edict::tls::Guard::new(world);
-
-    let Ok(entity) = guard.entity(entity) else {
-        return;
-    };
-
-    execute_code_after(
-        entity,
-        OutPinId { node, output: 0 },
-        snarl,
-        cache,
-        &pure,
-        &flow,
-    );
-}
-
-struct RunCodeAfterQueue {
-    queue: Vec<(EntityId, OutPinId)>,
+struct CodeViewer<'a> {
+    conversions: &'a Conversions,
+    diagnostics: &'a CodeDiagnostics,
 }
 
-pub(crate) fn run_code_after(entity: EntityId, outflow: OutputId, world: &World) {
-    let outflow = OutPinId {
-        node: NodeId(outflow.node),
-        output: outflow.output,
-    };
-
-    world
-        .expect_resource_mut::<RunCodeAfterQueue>()
-        .queue
-        .push((entity, outflow));
-}
-
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
-pub struct CodeGraph {
-    name: Name,
-    snarl: Snarl<CodeNode>,
-    events: HashMap<(), OutPinId>,
-}
-
-struct CodeViewer;
-
-impl SnarlViewer<CodeNode> for CodeViewer {
+impl SnarlViewer<CodeNode> for CodeViewer<'_> {
     fn title(&mut self, node: &CodeNode) -> String {
         node.name.to_string()
     }
 
     fn inputs(&mut self, node: &CodeNode) -> usize {
-        match node.desc {
-            CodeDesc::Event { .. } => 0,
-            CodeDesc::Pure { ref inputs, .. } => inputs.len(),
-            CodeDesc::Flow {
-                inflows,
-                ref inputs,
-                ..
-            } => inflows + inputs.len(),
-        }
+        node.inputs()
     }
 
     fn outputs(&mut self, node: &CodeNode) -> usize {
-        match node.desc {
-            CodeDesc::Event { ref outputs, .. } => 1 + outputs.len(),
-            CodeDesc::Pure { ref outputs, .. } => outputs.len(),
-            CodeDesc::Flow {
-                outflows,
-                ref outputs,
-                ..
-            } => outflows + outputs.len(),
-        }
+        node.outputs()
     }
 
     fn show_header(
@@ -463,18 +83,69 @@ impl SnarlViewer<CodeNode> for CodeViewer {
         _scale: f32,
         snarl: &mut Snarl<CodeNode>,
     ) {
+        let diagnostics = self.diagnostics.node(node.0);
         let node = &snarl[node];
 
-        match node.desc {
-            CodeDesc::Event { .. } => {
-                ui.label(node.name.to_string());
+        ui.horizontal(|ui| {
+            ui.label(node.name.to_string());
+
+            if !diagnostics.is_empty() {
+                let text = diagnostics
+                    .iter()
+                    .map(|d| d.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                ui.colored_label(Color32::RED, "⚠").on_hover_text(text);
             }
-            CodeDesc::Pure { .. } => {
-                ui.label(node.name.to_string());
+        });
+    }
+
+    fn connect(&mut self, from: &OutPin, to: &InPin, snarl: &mut Snarl<CodeNode>) {
+        let from_pin = snarl[from.id.node].output_pin(from.id.output);
+        let to_pin = snarl[to.id.node].input_pin(to.id.input);
+
+        match (from_pin, to_pin) {
+            (Some(CodePin::Flow), Some(CodePin::Flow)) => {
+                // Outflow leads to single inflow.
+                for &r in &from.remotes {
+                    snarl.disconnect(from.id, r);
+                }
+                snarl.connect(from.id, to.id);
             }
-            CodeDesc::Flow { .. } => {
-                ui.label(node.name.to_string());
+            (Some(CodePin::Value(found)), Some(CodePin::Value(expected))) => {
+                if found == expected {
+                    for &r in &to.remotes {
+                        snarl.disconnect(r, to.id);
+                    }
+                    snarl.connect(from.id, to.id);
+                } else if let Some(info) = self.conversions.get(found, expected) {
+                    for &r in &to.remotes {
+                        snarl.disconnect(r, to.id);
+                    }
+
+                    // Place conversion node between connected nodes.
+                    let mut pos = egui::Pos2::ZERO;
+                    for (id, node_pos, _) in snarl.nodes_pos_ids() {
+                        if id == from.id.node || id == to.id.node {
+                            pos += node_pos.to_vec2() * 0.5;
+                        }
+                    }
+
+                    let node = snarl.insert_node(
+                        pos,
+                        CodeNode {
+                            id: info.id,
+                            name: info.name,
+                            desc: info.desc.clone(),
+                        },
+                    );
+
+                    snarl.connect(from.id, InPinId { node, input: 0 });
+                    snarl.connect(OutPinId { node, output: 0 }, to.id);
+                }
             }
+            _ => {}
         }
     }
 
@@ -563,9 +234,36 @@ fn flow_pin() -> PinInfo {
 
 pub struct Codes {
     selected: Option<CodeId>,
+    conversions: Conversions,
+    modification: u64,
 }
 
 impl Codes {
+    pub fn new() -> Self {
+        Codes {
+            selected: None,
+            conversions: Conversions::builtin(),
+            modification: 1,
+        }
+    }
+
+    /// Returns counter that is incremented each time code graph is edited.
+    pub fn modification(&self) -> u64 {
+        self.modification
+    }
+
+    pub fn update_plugins(&mut self, container: &Container) {
+        self.conversions = Conversions::builtin();
+
+        for (name, plugin) in container.plugins() {
+            for info in plugin.conversions() {
+                if !self.conversions.add(info) {
+                    tracing::error!("Plugin '{name}' provides invalid conversion code");
+                }
+            }
+        }
+    }
+
     pub fn show(world: &WorldLocal, ui: &mut Ui) {
         let mut codes = world.expect_resource_mut::<Codes>();
         let mut data = world.expect_resource_mut::<ProjectData>();
@@ -599,8 +297,20 @@ impl Codes {
                 return;
             };
 
+            let before = code.data();
+            let diagnostics = before.validate();
+
+            let mut viewer = CodeViewer {
+                conversions: &codes.conversions,
+                diagnostics: &diagnostics,
+            };
+
             code.snarl
-                .show(&mut CodeViewer, &SnarlStyle::default(), "code-viwer", ui);
+                .show(&mut viewer, &SnarlStyle::default(), "code-viwer", ui);
+
+            if code.data() != before {
+                codes.modification += 1;
+            }
         });
     }
 }
@@ -3,10 +3,22 @@
 
 use std::io::Write;
 
-use arcana::{code::CodeId, project::Project, Ident};
+use arcana::{
+    code::{CodeGraph as CodeGraphData, CodeId},
+    config::ConfigId,
+    game::GameData,
+    model::Value,
+    project::Project,
+    Ident,
+};
 use hashbrown::{HashMap, HashSet};
 
-use crate::{code::CodeGraph, filters::Funnel, render::WorkGraph, systems::SystemGraph};
+use crate::{
+    code::CodeGraph,
+    filters::Funnel,
+    render::WorkGraph,
+    systems::{Category, SystemGraph},
+};
 
 /// In combination with `ProjectManifest` this defines the project completely.
 /// This includes enabled plugins, filter chain, system graph, asset collections, etc
@@ -28,10 +40,44 @@ pub struct ProjectData {
 
     /// Code graphs.
     pub codes: HashMap<CodeId, CodeGraph>,
+
+    /// Values of plugin configs.
+    #[serde(default)]
+    pub configs: HashMap<ConfigId, Value>,
 }
 
 impl ProjectData {
+    /// Returns data required to run the game without the editor.
+    pub fn game_data(&self, project: &Project) -> GameData {
+        GameData {
+            name: project.name().as_str().to_owned(),
+            enabled_plugins: self.enabled_plugins.clone(),
+            fix_schedule: self.systems.schedule(Category::Fix),
+            var_schedule: self.systems.schedule(Category::Var),
+            filters: self.funnel.enabled(),
+            workgraph: self.workgraph.data(),
+            codes: self.codes_data(),
+            configs: self.configs.clone(),
+        }
+    }
+
+    /// Returns code graphs to run.
+    ///
+    /// Code graphs that fail validation are skipped.
+    /// Their diagnostics are shown in the code editor.
+    pub fn codes_data(&self) -> HashMap<CodeId, CodeGraphData> {
+        self.codes
+            .iter()
+            .map(|(&id, code)| (id, code.data()))
+            .filter(|(_, code)| code.validate().is_empty())
+            .collect()
+    }
+
     pub fn sync(&mut self, project: &Project) -> miette::Result<()> {
+        if let Err(err) = self.game_data(project).store(project.root_path()) {
+            tracing::error!("Failed to store game data: {}", err);
+        }
+
         let path = project.root_path().join("Arcana.bin");
         let bak = path.with_extension("bin.bak");
 
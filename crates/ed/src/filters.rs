@@ -50,6 +50,15 @@ impl Funnel {
 
         false
     }
+
+    /// Returns enabled filters in funnel order.
+    pub fn enabled(&self) -> Vec<FilterId> {
+        self.filters
+            .iter()
+            .filter(|f| f.enabled)
+            .map(|f| f.id)
+            .collect()
+    }
 }
 
 impl Filters {
@@ -1,18 +1,29 @@
 //! Running instance of the project.
 
-use std::sync::Arc;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use arcana::{
+    assets::{build_assets, Assets},
+    code::{run_code_events, CodeGraph, CodeId, OutputCache},
+    config::ConfigId,
     edict::world::WorldLocal,
-    flow::{init_flows, Flows},
-    gametime::{ClockRate, FrequencyNumExt, TimeSpan},
+    events::{dispatch_events, init_events},
+    flow::{init_flows, wake_flows, Flows},
+    gametime::{ClockRate, FrequencyNumExt, TimeSpan, TimeStamp},
     input::{DeviceId, Input, KeyCode, PhysicalKey, ViewportInput},
     mev,
+    model::Value,
     plugin::PluginsHub,
     texture::Texture,
     viewport::Viewport,
-    work::{CommandStream, HookId, Image2D, Image2DInfo, PinId, Target, WorkGraph},
-    Blink, ClockStep, EntityId, FrequencyTicker, World,
+    work::{
+        run_presents, CommandStream, HookId, PinId, PresentError, PresentFlags, Target,
+        TargetMemoryReport, WorkGraph, WorkGraphStats,
+    },
+    Blink, ClockStep, EntityId, FrequencyTicker, Name, World,
 };
 use egui::Ui;
 use hashbrown::{HashMap, HashSet};
@@ -20,6 +31,7 @@ use parking_lot::Mutex;
 use winit::{event::WindowEvent, window::WindowId};
 
 use crate::{
+    code::Codes,
     container::Container,
     data::ProjectData,
     filters::Funnel,
@@ -40,6 +52,9 @@ pub struct Instance {
     /// Specifies frequency of fixed updates.
     fix: FrequencyTicker,
 
+    /// Time of the last fixed update.
+    last_fix: TimeStamp,
+
     /// Limits variable updates.
     lim: FrequencyTicker,
 
@@ -49,20 +64,86 @@ pub struct Instance {
     /// Flows to run on each tick.
     flows: Flows,
 
+    /// Output values of code nodes per entity.
+    code_cache: HashMap<EntityId, OutputCache>,
+
+    /// Config values applied to the world.
+    configs: HashMap<ConfigId, Value>,
+
     /// Work graph.
     workgraph: WorkGraph,
 
-    /// Which pin to present to viewport.
-    present: Option<PinId>,
+    /// Which pins to present to viewports.
+    presents: Vec<(Name, PinId)>,
 
     /// Viewport to render into.
-    viewport: Viewport,
+    /// `None` for headless instance.
+    viewport: Option<Viewport>,
 
     /// Container in which plugins reside.
     container: Option<Container>,
+
+    /// Project root for asset store.
+    assets_root: Option<PathBuf>,
 }
 
 impl Instance {
+    /// Creates new instance.
+    ///
+    /// Headless instance runs systems, flows and input filters,
+    /// but never renders and does not require GPU device.
+    pub fn new(headless: bool) -> Self {
+        let mut world = World::new();
+        let hub = PluginsHub::new();
+        let blink = Blink::new();
+
+        let rate = ClockRate::new();
+        let fix = FrequencyTicker::new(60.hz(), rate.now());
+        let lim = FrequencyTicker::new(60.hz(), rate.now());
+
+        let flows = Flows::new();
+        let workgraph = WorkGraph::new(HashMap::new(), HashSet::new()).unwrap();
+
+        let presents = Vec::new();
+        let viewport = if headless {
+            None
+        } else {
+            Some(Viewport::new_image())
+        };
+
+        init_flows(&mut world);
+        init_events(&mut world);
+        world.insert_resource(PresentFlags::new());
+
+        Instance {
+            world,
+            blink,
+            hub,
+            fix,
+            last_fix: rate.now(),
+            lim,
+            rate,
+            flows,
+            code_cache: HashMap::new(),
+            configs: HashMap::new(),
+            workgraph,
+            presents,
+            viewport,
+            container: None,
+            assets_root: None,
+        }
+    }
+
+    /// Returns true if instance runs without viewport.
+    pub fn is_headless(&self) -> bool {
+        self.viewport.is_none()
+    }
+
+    /// Replaces plugins with ones from the new container.
+    ///
+    /// State of compatible plugins is transferred to updated versions
+    /// using `ArcanaPlugin::dump` and `ArcanaPlugin::load`.
+    /// Incompatible plugins start from scratch.
     pub fn update_plugins(&mut self, c: &Container) {
         match self.container.take() {
             None => {
@@ -71,18 +152,63 @@ impl Instance {
                 }
                 self.container = Some(c.clone());
             }
-            Some(_old) => {
+            Some(old) => {
+                let mut dumps = HashMap::new();
+
+                for (name, old_plugin) in old.plugins() {
+                    let Some((_, new_plugin)) = c.plugins().find(|(n, _)| *n == name) else {
+                        continue;
+                    };
+
+                    if old_plugin.compatible(new_plugin) {
+                        let mut buffer = Vec::new();
+                        old_plugin.dump(&self.world, &mut buffer);
+                        dumps.insert(name, buffer);
+                    } else {
+                        tracing::info!(
+                            "Plugin '{name}' is incompatible with updated version, state is reset"
+                        );
+                    }
+                }
+
+                let old_plugins = old.plugins().collect::<Vec<_>>();
+                for (_, p) in old_plugins.into_iter().rev() {
+                    p.deinit(&mut self.world);
+                }
+
+                // Old world may contain values with code from old plugins.
+                // It must be dropped before old container.
                 self.world = World::new();
                 self.hub = PluginsHub::new();
-                self.container = Some(c.clone());
-
-                for (_, p) in c.plugins() {
+                self.flows = Flows::new();
+                self.code_cache.clear();
+                self.configs.clear();
+                init_flows(&mut self.world);
+                init_events(&mut self.world);
+                self.world.insert_resource(PresentFlags::new());
+                init_assets(&mut self.world, self.assets_root.as_deref());
+
+                for (name, p) in c.plugins() {
                     p.init(&mut self.world, &mut self.hub);
+
+                    if let Some(dump) = dumps.get(&name) {
+                        p.load(&mut self.world, dump);
+                    }
                 }
+
+                self.container = Some(c.clone());
+                drop(old);
             }
         }
     }
 
+    /// Opens project asset store for this instance.
+    /// Asset sources are watched and reloaded on change.
+    pub fn set_assets_root(&mut self, root: &Path) {
+        self.assets_root = Some(root.to_owned());
+        init_assets(&mut self.world, Some(root));
+    }
+
     pub fn rate(&self) -> &ClockRate {
         &self.rate
     }
@@ -91,15 +217,21 @@ impl Instance {
         &mut self.rate
     }
 
-    pub fn tick(&mut self, span: TimeSpan, schedule: &Schedule) {
-        let last_now = self.rate.now();
+    pub fn tick(
+        &mut self,
+        span: TimeSpan,
+        schedule: &Schedule,
+        codes: &HashMap<CodeId, CodeGraph>,
+    ) {
         let step = self.rate.step(span);
 
+        // Fixed updates step exactly by fix period.
         self.fix.with_ticks(step.step, |fix_now| {
             self.world.insert_resource(ClockStep {
                 now: fix_now,
-                step: fix_now - last_now,
+                step: fix_now - self.last_fix,
             });
+            self.last_fix = fix_now;
             schedule.run(systems::Category::Fix, &mut self.world, &mut self.hub);
         });
 
@@ -108,7 +240,31 @@ impl Instance {
             schedule.run(systems::Category::Var, &mut self.world, &mut self.hub);
         }
 
+        run_code_events(
+            &mut self.world,
+            codes,
+            &self.hub.pure_fns,
+            &self.hub.flow_fns,
+            &mut self.code_cache,
+        );
+
+        wake_flows(&mut self.world);
         self.flows.execute(&mut self.world);
+        dispatch_events(&mut self.world);
+    }
+
+    /// Applies config values that changed since last call.
+    /// All values are applied again after plugins are updated.
+    pub fn apply_configs(&mut self, configs: &HashMap<ConfigId, Value>) {
+        for (&id, value) in configs {
+            if self.configs.get(&id) == Some(value) {
+                continue;
+            }
+
+            if self.hub.apply_config(id, value, &mut self.world) {
+                self.configs.insert(id, value.clone());
+            }
+        }
     }
 
     pub fn on_input(&mut self, funnel: &Funnel, event: &Input) -> bool {
@@ -119,48 +275,39 @@ impl Instance {
         &mut self,
         device: &mev::Device,
         queue: &mut mev::Queue,
-    ) -> Result<(), mev::SurfaceError> {
-        let Some(pin) = self.present else {
+    ) -> Result<(), PresentError> {
+        let Some(viewport) = &mut self.viewport else {
             return Ok(());
         };
 
-        let (image, frame) =
-            match self
-                .viewport
-                .next_frame(device, queue, mev::PipelineStages::all())?
-            {
-                Some((image, frame)) => (image, frame),
-                None => return Ok(()),
-            };
-
-        let info = Image2DInfo::from_image(&image);
-        let target = Image2D(image);
-
-        self.workgraph.set_sink(pin, target, info);
-
-        self.workgraph
-            .run(device, queue, &mut self.world, &mut self.hub)
-            .unwrap();
-
-        if let Some(frame) = frame {
-            let mut encoder = queue.new_command_encoder().unwrap();
-            encoder.present(frame, mev::PipelineStages::all());
-            let buffer = encoder.finish().unwrap();
+        if self.presents.is_empty() {
+            return Ok(());
+        }
 
-            queue.submit(std::iter::once(buffer), true).unwrap();
+        if let Err(err) = build_assets(&mut self.world, device, queue) {
+            tracing::error!("Failed to build assets: {err}");
         }
 
-        Ok(())
+        run_presents(
+            &mut self.workgraph,
+            &self.presents,
+            Some(viewport),
+            device,
+            queue,
+            &mut self.world,
+            &mut self.hub,
+        )
     }
 
     /// Makes this instance render into a texture.
     ///
     /// Returns image to which main presentation happens.
+    /// Returns `None` for headless instance as it never renders.
     pub fn set_texture(
         &mut self,
         world: &World,
         extent: mev::Extent2,
-    ) -> Result<mev::Image, mev::OutOfMemory> {
+    ) -> Result<Option<mev::Image>, mev::OutOfMemory> {
         #[cold]
         fn new_image(extent: mev::Extent2, world: &World) -> Result<mev::Image, mev::OutOfMemory> {
             let device = world.expect_resource::<mev::Device>();
@@ -171,22 +318,26 @@ impl Instance {
                     | mev::ImageUsage::SAMPLED
                     | mev::ImageUsage::STORAGE,
                 layers: 1,
+                cube: false,
                 levels: 1,
                 name: "Game Viewport",
             })?;
             Ok(image)
         }
 
-        if self
-            .viewport
+        let Some(viewport) = &mut self.viewport else {
+            return Ok(None);
+        };
+
+        if viewport
             .get_image()
             .map_or(true, |i| i.dimensions() != extent)
         {
             tracing::debug!("Creating new image for viewport");
-            self.viewport.set_image(new_image(extent, world)?);
+            viewport.set_image(new_image(extent, world)?);
         }
 
-        Ok(self.viewport.get_image().unwrap().clone())
+        Ok(Some(viewport.get_image().unwrap().clone()))
     }
 }
 
@@ -194,6 +345,10 @@ pub struct Main {
     instance: Instance,
     rendering_modifications: u64,
 
+    // Code graphs to run, rebuilt when edited.
+    codes: HashMap<CodeId, CodeGraph>,
+    codes_modifications: u64,
+
     focused: bool,
     view_id: Option<EntityId>,
 
@@ -211,40 +366,15 @@ pub struct Main {
 }
 
 impl Main {
-    pub fn new() -> Self {
-        let mut world = World::new();
-        let hub = PluginsHub::new();
-        let blink = Blink::new();
-
-        let rate = ClockRate::new();
-        let fix = FrequencyTicker::new(60.hz(), rate.now());
-        let lim = FrequencyTicker::new(60.hz(), rate.now());
-
-        let flows = Flows::new();
-        let workgraph = WorkGraph::new(HashMap::new(), HashSet::new()).unwrap();
-
-        let present = None;
-        let viewport = Viewport::new_image();
-
-        init_flows(&mut world);
-
-        let instance = Instance {
-            world,
-            blink,
-            hub,
-            fix,
-            lim,
-            rate,
-            flows,
-            workgraph,
-            present,
-            viewport,
-            container: None,
-        };
+    pub fn new(root: &Path, headless: bool) -> Self {
+        let mut instance = Instance::new(headless);
+        instance.set_assets_root(root);
 
         Main {
             instance,
             rendering_modifications: 0,
+            codes: HashMap::new(),
+            codes_modifications: 0,
             focused: false,
             view_id: None,
             rect: egui::Rect::NOTHING,
@@ -295,11 +425,20 @@ impl Main {
         game_frame.show(ui, |ui| {
             let size = ui.available_size();
             let extent = mev::Extent2::new(size.x as u32, size.y as u32);
-            let Ok(image) = main.instance.set_texture(world, extent) else {
-                ui.centered_and_justified(|ui| {
-                    ui.label("GPU OOM");
-                });
-                return;
+            let image = match main.instance.set_texture(world, extent) {
+                Ok(Some(image)) => image,
+                Ok(None) => {
+                    ui.centered_and_justified(|ui| {
+                        ui.label("Headless");
+                    });
+                    return;
+                }
+                Err(_) => {
+                    ui.centered_and_justified(|ui| {
+                        ui.label("GPU OOM");
+                    });
+                    return;
+                }
             };
 
             let view_id = *main.view_id.get_or_insert_with(|| world.allocate().id());
@@ -436,7 +575,18 @@ impl Main {
         let world = world.local();
         let mut main = world.expect_resource_mut::<Main>();
         let systems = world.expect_resource::<Systems>();
-        main.instance.tick(step.step, systems.schedule());
+        let data = world.expect_resource::<ProjectData>();
+        let codes = world.expect_resource::<Codes>();
+
+        let main = &mut *main;
+        if codes.modification() > main.codes_modifications {
+            main.codes = data.codes_data();
+            main.codes_modifications = codes.modification();
+        }
+
+        main.instance.apply_configs(&data.configs);
+        main.instance
+            .tick(step.step, systems.schedule(), &main.codes);
     }
 
     pub fn render(world: &mut World) {
@@ -448,17 +598,20 @@ impl Main {
         let data = world.expect_resource::<ProjectData>();
 
         if rendering.modification() > main.rendering_modifications {
-            match data.workgraph.make_workgraph() {
+            let workgraph = data.workgraph.data();
+            match workgraph.make_workgraph() {
                 Ok(workgraph) => main.instance.workgraph = workgraph,
                 Err(err) => {
                     tracing::error!("Failed to make workgraph: {err:?}");
                 }
             }
-            main.instance.present = data.workgraph.get_present();
+            main.instance.presents = workgraph.all_presents();
             main.rendering_modifications = rendering.modification();
         }
 
-        main.instance.render(&device, &mut queue.lock()).unwrap();
+        if let Err(err) = main.instance.render(&device, &mut queue.lock()) {
+            tracing::error!("Failed to render instance: {err}");
+        }
     }
 
     pub fn update_plugins(&mut self, c: &Container) {
@@ -483,4 +636,36 @@ impl Main {
     pub fn remove_workgraph_hook(&mut self, hook: HookId) {
         self.instance.workgraph.remove_hook(hook)
     }
+
+    /// Returns memory used by work graph targets in the last frame.
+    pub fn workgraph_memory_report(&self) -> TargetMemoryReport {
+        self.instance.workgraph.memory_report()
+    }
+
+    /// Returns timings of work graph jobs in the last frame.
+    pub fn workgraph_stats(&self) -> &WorkGraphStats {
+        self.instance.workgraph.stats()
+    }
+}
+
+/// Inserts `Assets` resource with project asset store into the world.
+/// Assets loaded by old plugins are not carried over.
+fn init_assets(world: &mut World, root: Option<&Path>) {
+    let Some(root) = root else {
+        return;
+    };
+
+    let mut assets = match Assets::with_root(root) {
+        Ok(assets) => assets,
+        Err(err) => {
+            tracing::error!("{err}");
+            return;
+        }
+    };
+
+    if let Err(err) = assets.watch() {
+        tracing::error!("{err}");
+    }
+
+    world.insert_resource(assets);
 }
@@ -68,6 +68,8 @@ mod systems;
 mod tools;
 
 /// Runs the editor application
+///
+/// Main game instance runs headless if `--headless` argument is passed.
 pub fn run(path: &Path) {
     if let Err(err) = _run(path) {
         eprintln!("Error: {}", err);
@@ -113,7 +115,9 @@ fn _run(path: &Path) -> miette::Result<()> {
     builder.with_any_thread(true);
 
     let events = builder.build().expect("Failed to create event loop");
-    let mut app = app::App::new(&events, event_collector, project, data);
+    let headless = std::env::args().skip(1).any(|arg| arg == "--headless");
+
+    let mut app = app::App::new(&events, event_collector, project, data, headless);
 
     #[allow(deprecated)]
     events
@@ -216,11 +220,15 @@ fn get_profile() -> Profile {
 fn init_mev() -> (mev::Device, mev::Queue) {
     let instance = mev::Instance::load().expect("Failed to init graphics");
 
+    // Compressed textures are used when available.
+    let optional =
+        instance.capabilities().devices[0].features & mev::Features::TEXTURE_COMPRESSION_BC;
+
     let (device, mut queues) = instance
         .create(mev::DeviceDesc {
             idx: 0,
             queues: &[0],
-            features: mev::Features::SURFACE,
+            features: mev::Features::SURFACE | optional,
         })
         .unwrap();
     let queue = queues.pop().unwrap();
@@ -11,11 +11,13 @@ use egui::{Color32, RichText, Ui};
 use egui_file::FileDialog;
 
 use crate::{
+    code::Codes,
     container::{Container, Loader, PluginsError},
     data::ProjectData,
     filters::Filters,
     get_profile,
     instance::Main,
+    model::ValueProbe,
     render::Rendering,
     systems::Systems,
 };
@@ -109,6 +111,7 @@ impl Plugins {
         let mut systems = world.expect_resource_mut::<Systems>();
         let mut filters = world.expect_resource_mut::<Filters>();
         let mut rendering = world.expect_resource_mut::<Rendering>();
+        let mut codes = world.expect_resource_mut::<Codes>();
         let mut main = world.expect_resource_mut::<Main>();
 
         if let Some(mut build) = plugins.build.take() {
@@ -206,6 +209,7 @@ impl Plugins {
                 systems.update_plugins(&mut data, &c);
                 filters.update_plugins(&mut data, &c);
                 rendering.update_plugins(&mut data, &c);
+                codes.update_plugins(&c);
                 plugins.linked = Some(c);
             }
         }
@@ -342,6 +346,27 @@ impl Plugins {
             if let Some(idx) = remove_plugin {
                 project.manifest_mut().remove_plugin_idx(idx);
             }
+
+            // Configs of linked plugins.
+            if let Some(linked) = &plugins.linked {
+                for (name, plugin) in linked.plugins() {
+                    for info in plugin.configs() {
+                        egui::CollapsingHeader::new(format!("{name}: {}", info.name))
+                            .id_source(info.id)
+                            .show(ui, |ui| {
+                                let value = data
+                                    .configs
+                                    .entry(info.id)
+                                    .or_insert_with(|| info.default.clone());
+
+                                let mut probe = ValueProbe::new(Some(&info.model), value, info.id);
+                                sync |= egui_probe::Probe::new(info.name.as_str(), &mut probe)
+                                    .show(ui)
+                                    .changed();
+                            });
+                    }
+                }
+            }
         });
 
         match &mut plugins.dialog {
@@ -2,12 +2,16 @@ use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
 
 use arcana::{
     edict::world::WorldLocal,
+    game::WorkGraphData,
     mev,
     model::Value,
-    plugin::JobInfo,
+    plugin::{JobInfo, ParamSourceInfo},
     project::Project,
     texture::Texture,
-    work::{Edge, HookId, Image2D, JobDesc, JobId, JobIdx, PinId},
+    work::{
+        Buffer, DepthImage2D, Edge, HookId, Image2D, Image2DArray, Image3D, ImageCube, JobDesc,
+        JobId, JobIdx, JobStats, ParamSourceId, PinId, SampledImage2D, WorkGraphStats,
+    },
     EntityId, Ident, Name, Stid, WithStid,
 };
 use egui::Ui;
@@ -29,14 +33,15 @@ pub struct WorkGraph {
 }
 
 impl WorkGraph {
-    pub fn make_workgraph(&self) -> Result<arcana::work::WorkGraph, arcana::work::Cycle> {
+    /// Returns work graph data to run it outside the editor.
+    pub fn data(&self) -> WorkGraphData {
         let jobs = self
             .snarl
             .node_ids()
             .filter_map(|(id, node)| match node {
                 WorkGraphNode::Job {
                     job, desc, params, ..
-                } => Some((JobIdx(id.0), (*job, desc.clone(), params.clone()))),
+                } => Some((JobIdx(id.0), *job, desc.clone(), params.clone())),
                 _ => None,
             })
             .collect();
@@ -63,7 +68,29 @@ impl WorkGraph {
             })
             .collect();
 
-        arcana::work::WorkGraph::new(jobs, edges)
+        let bindings = self
+            .snarl
+            .node_ids()
+            .flat_map(|(id, node)| match node {
+                WorkGraphNode::Job { bindings, .. } => bindings
+                    .iter()
+                    .map(|(&name, &source)| (JobIdx(id.0), name, source))
+                    .collect::<Vec<_>>(),
+                _ => Vec::new(),
+            })
+            .collect();
+
+        WorkGraphData {
+            jobs,
+            edges,
+            bindings,
+            present: self.get_present(),
+            presents: self.get_presents(),
+        }
+    }
+
+    pub fn make_workgraph(&self) -> Result<arcana::work::WorkGraph, arcana::work::Cycle> {
+        self.data().make_workgraph()
     }
 
     pub fn get_present(&self) -> Option<PinId> {
@@ -79,6 +106,25 @@ impl WorkGraph {
         }
         None
     }
+
+    /// Returns named presents.
+    pub fn get_presents(&self) -> Vec<(Name, PinId)> {
+        let mut presents = Vec::new();
+        for (from, to) in self.snarl.wires() {
+            if let Some(&WorkGraphNode::Present { name }) = self.snarl.get_node(to.node) {
+                if let Some(&WorkGraphNode::Job { .. }) = self.snarl.get_node(from.node) {
+                    presents.push((
+                        name,
+                        PinId {
+                            job: JobIdx(from.node.0),
+                            pin: from.output,
+                        },
+                    ));
+                }
+            }
+        }
+        presents
+    }
 }
 
 struct Preview {
@@ -90,6 +136,7 @@ struct Preview {
 
 pub struct Rendering {
     available: BTreeMap<Ident, Vec<JobInfo>>,
+    sources: Vec<ParamSourceInfo>,
     modification: u64,
     preview: Option<Rc<RefCell<Preview>>>,
 }
@@ -98,6 +145,7 @@ impl Rendering {
     pub fn new() -> Self {
         Rendering {
             available: BTreeMap::new(),
+            sources: Vec::new(),
             modification: 1,
             preview: None,
         }
@@ -110,8 +158,11 @@ impl Rendering {
     pub fn update_plugins(&mut self, data: &mut ProjectData, container: &Container) {
         let mut all_jobs = HashMap::new();
         self.available.clear();
+        self.sources.clear();
 
         for (name, plugin) in container.plugins() {
+            self.sources.extend(plugin.param_sources());
+
             let jobs = self.available.entry(name).or_default();
 
             for job in plugin.jobs() {
@@ -139,6 +190,7 @@ impl Rendering {
                 WorkGraphNode::MainPresent => {
                     add_present_node = false;
                 }
+                WorkGraphNode::Present { .. } => {}
             }
         }
 
@@ -187,6 +239,7 @@ impl Rendering {
                             format: mev::PixelFormat::Rgba8Srgb,
                             usage: mev::ImageUsage::TARGET | mev::ImageUsage::SAMPLED,
                             layers: 1,
+                            cube: false,
                             levels: 1,
                             name: "preview",
                         })
@@ -204,11 +257,28 @@ impl Rendering {
             }
         }
 
+        let report = main.workgraph_memory_report();
+        ui.label(format!(
+            "Targets memory: {:.1} MiB allocated, {:.1} MiB peak",
+            report.allocated as f64 / MIB,
+            report.peak as f64 / MIB,
+        ));
+
+        let stats = main.workgraph_stats().clone();
+
+        egui::CollapsingHeader::new("Timeline")
+            .id_source("work-graph-timeline")
+            .show(ui, |ui| {
+                show_timeline(&stats, &data.workgraph.snarl, ui);
+            });
+
         const STYLE: SnarlStyle = SnarlStyle::new();
 
         let mut viewer = WorkGraphViewer {
             modified: false,
             available: &mut rendering.available,
+            sources: &rendering.sources,
+            stats: &stats,
             main: &mut main,
             sample: &sample,
             preview,
@@ -235,15 +305,27 @@ pub enum WorkGraphNode {
         desc: JobDesc,
         params: HashMap<Name, Value>,
 
+        /// Params bound to param sources.
+        #[serde(default)]
+        bindings: HashMap<Name, ParamSourceId>,
+
         #[serde(skip)]
         active: bool,
     },
     MainPresent,
+
+    /// Present rendered into viewports of entities
+    /// with matching `Present` component.
+    Present {
+        name: Name,
+    },
 }
 
 pub struct WorkGraphViewer<'a> {
     modified: bool,
     available: &'a mut BTreeMap<Ident, Vec<JobInfo>>,
+    sources: &'a [ParamSourceInfo],
+    stats: &'a WorkGraphStats,
     main: &'a mut Main,
     sample: &'a ImageSample,
     preview: &'a Rc<RefCell<Preview>>,
@@ -254,6 +336,7 @@ impl SnarlViewer<WorkGraphNode> for WorkGraphViewer<'_> {
         match *node {
             WorkGraphNode::Job { ref name, .. } => name.as_str().to_owned(),
             WorkGraphNode::MainPresent => "Present".to_owned(),
+            WorkGraphNode::Present { name } => format!("Present {name}"),
         }
     }
 
@@ -288,11 +371,36 @@ impl SnarlViewer<WorkGraphNode> for WorkGraphViewer<'_> {
                             ui.label("Remove job from graph");
                         });
                     });
+
+                    match self.stats.job(JobIdx(id.0)) {
+                        None => {
+                            ui.weak("Not executed");
+                        }
+                        Some(stats) => {
+                            ui.weak(format_job_stats(stats));
+                        }
+                    }
                 });
             }
             WorkGraphNode::MainPresent => {
                 ui.label("Present");
             }
+            WorkGraphNode::Present { ref mut name } => {
+                ui.horizontal(|ui| {
+                    ui.label("Present");
+
+                    let mut text = name.to_string();
+                    if ui.text_edit_singleline(&mut text).changed() {
+                        if let Ok(new_name) = Name::from_str(&text) {
+                            *name = new_name;
+                            self.modified = true;
+                        }
+                    }
+
+                    let r = ui.small_button(egui_phosphor::regular::TRASH_SIMPLE);
+                    remove = r.clicked();
+                });
+            }
         }
 
         if remove {
@@ -304,14 +412,14 @@ impl SnarlViewer<WorkGraphNode> for WorkGraphViewer<'_> {
     fn inputs(&mut self, node: &WorkGraphNode) -> usize {
         match *node {
             WorkGraphNode::Job { ref desc, .. } => desc.input_count(),
-            WorkGraphNode::MainPresent => 1,
+            WorkGraphNode::MainPresent | WorkGraphNode::Present { .. } => 1,
         }
     }
 
     fn outputs(&mut self, node: &WorkGraphNode) -> usize {
         match *node {
             WorkGraphNode::Job { ref desc, .. } => desc.output_count(),
-            WorkGraphNode::MainPresent => 0,
+            WorkGraphNode::MainPresent | WorkGraphNode::Present { .. } => 0,
         }
     }
 
@@ -326,6 +434,7 @@ impl SnarlViewer<WorkGraphNode> for WorkGraphViewer<'_> {
             WorkGraphNode::Job {
                 ref desc,
                 ref mut params,
+                ref mut bindings,
                 ..
             } => {
                 match (
@@ -336,30 +445,65 @@ impl SnarlViewer<WorkGraphNode> for WorkGraphViewer<'_> {
                     (Some(update), _, _) => {
                         let update = &desc.updates[update];
                         ui.label("updates");
-                        PinInfo::square().with_fill(hue_hash(&update.ty))
+                        PinInfo::square().with_fill(target_pin_color(update.ty))
                     }
                     (_, Some(read), _) => {
                         let read = &desc.reads[read];
                         ui.label("reads");
-                        PinInfo::circle().with_fill(hue_hash(&read.ty))
+                        PinInfo::circle().with_fill(target_pin_color(read.ty))
                     }
                     (_, _, Some(param)) => {
                         let (name, ref model) = desc.params[param];
+                        let sources = self.sources;
 
                         ui.horizontal(|ui| {
-                            let value = params.entry(name).or_insert_with(|| model.default_value());
-
-                            let mut probe = ValueProbe::new(Some(model), value, name);
-                            self.modified |= egui_probe::Probe::new(name.as_str(), &mut probe)
-                                .show(ui)
-                                .changed();
+                            let bound = bindings.get(&name).copied();
+
+                            let selected = match bound {
+                                None => "value".to_owned(),
+                                Some(id) => match sources.iter().find(|s| s.id == id) {
+                                    Some(source) => source.name.to_string(),
+                                    None => "missing".to_owned(),
+                                },
+                            };
+
+                            egui::ComboBox::from_id_source((pin.id.node, name))
+                                .selected_text(selected)
+                                .show_ui(ui, |ui| {
+                                    if ui.selectable_label(bound.is_none(), "value").clicked() {
+                                        bindings.remove(&name);
+                                        self.modified = true;
+                                    }
+
+                                    for source in sources.iter().filter(|s| s.model == *model) {
+                                        let r = ui.selectable_label(
+                                            bound == Some(source.id),
+                                            source.name.as_str(),
+                                        );
+
+                                        if r.clicked() {
+                                            bindings.insert(name, source.id);
+                                            self.modified = true;
+                                        }
+                                    }
+                                });
+
+                            if bound.is_none() {
+                                let value =
+                                    params.entry(name).or_insert_with(|| model.default_value());
+
+                                let mut probe = ValueProbe::new(Some(model), value, name);
+                                self.modified |= egui_probe::Probe::new(name.as_str(), &mut probe)
+                                    .show(ui)
+                                    .changed();
+                            }
                         });
                         PinInfo::square().with_size(0.0)
                     }
                     a => unreachable!("{a:?}"),
                 }
             }
-            WorkGraphNode::MainPresent => {
+            WorkGraphNode::MainPresent | WorkGraphNode::Present { .. } => {
                 ui.label("presents");
                 PinInfo::circle().with_fill(present_pin_color())
             }
@@ -399,7 +543,7 @@ impl SnarlViewer<WorkGraphNode> for WorkGraphViewer<'_> {
                             &mut self.preview,
                         );
 
-                        PinInfo::square().with_fill(hue_hash(&update.ty))
+                        PinInfo::square().with_fill(target_pin_color(update.ty))
                     }
                     (_, Some(create)) => {
                         let create = &desc.creates[create];
@@ -416,12 +560,12 @@ impl SnarlViewer<WorkGraphNode> for WorkGraphViewer<'_> {
                             create.ty,
                             &mut self.preview,
                         );
-                        PinInfo::triangle().with_fill(hue_hash(&create.ty))
+                        PinInfo::triangle().with_fill(target_pin_color(create.ty))
                     }
                     _ => unreachable!(),
                 }
             }
-            WorkGraphNode::MainPresent => {
+            WorkGraphNode::MainPresent | WorkGraphNode::Present { .. } => {
                 unreachable!()
             }
         }
@@ -444,7 +588,10 @@ impl SnarlViewer<WorkGraphNode> for WorkGraphViewer<'_> {
                     self.modified = true;
                 }
             }
-            (WorkGraphNode::Job { desc: from_job, .. }, WorkGraphNode::MainPresent) => {
+            (
+                WorkGraphNode::Job { desc: from_job, .. },
+                WorkGraphNode::MainPresent | WorkGraphNode::Present { .. },
+            ) => {
                 if from_job.output_type(from.id.output) == present_kind() {
                     debug_assert!(to.remotes.len() <= 1);
                     for &r in &to.remotes {
@@ -507,6 +654,7 @@ impl SnarlViewer<WorkGraphNode> for WorkGraphViewer<'_> {
                             plugin,
                             desc: job.desc.clone(),
                             params: job.desc.default_params(),
+                            bindings: HashMap::new(),
                             active: true,
                         },
                     );
@@ -557,6 +705,27 @@ impl SnarlViewer<WorkGraphNode> for WorkGraphViewer<'_> {
         _scale: f32,
         snarl: &mut Snarl<WorkGraphNode>,
     ) {
+        if ui.button("Add present").clicked() {
+            let mut idx = 1;
+            let name = loop {
+                let name = Name::from_str(&format!("present{idx}")).unwrap();
+                let taken = snarl
+                    .nodes()
+                    .any(|node| matches!(node, WorkGraphNode::Present { name: n } if *n == name));
+                if !taken {
+                    break name;
+                }
+                idx += 1;
+            };
+
+            snarl.insert_node(pos, WorkGraphNode::Present { name });
+            self.modified = true;
+
+            ui.close_menu();
+            return;
+        }
+
+        ui.separator();
         ui.label("Add job");
 
         if self.available.is_empty() {
@@ -582,6 +751,7 @@ impl SnarlViewer<WorkGraphNode> for WorkGraphViewer<'_> {
                             plugin,
                             desc: job.desc.clone(),
                             params: job.desc.default_params(),
+                            bindings: HashMap::new(),
                             active: true,
                         },
                     );
@@ -594,6 +764,98 @@ impl SnarlViewer<WorkGraphNode> for WorkGraphViewer<'_> {
     }
 }
 
+const MIB: f64 = 1024.0 * 1024.0;
+
+fn millis(duration: std::time::Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn format_job_stats(stats: &JobStats) -> String {
+    let cpu = millis(stats.plan + stats.exec);
+    match stats.gpu {
+        None => format!("CPU {cpu:.2} ms"),
+        Some(gpu) => format!("CPU {cpu:.2} ms, GPU {:.2} ms", millis(gpu)),
+    }
+}
+
+/// Shows CPU and GPU timeline of the last work graph run.
+/// Each executed job gets a row with CPU execution and GPU work bars.
+fn show_timeline(stats: &WorkGraphStats, snarl: &Snarl<WorkGraphNode>, ui: &mut Ui) {
+    const CPU_COLOR: egui::Color32 = egui::Color32::from_rgb(0x42, 0xa5, 0xf5);
+    const GPU_COLOR: egui::Color32 = egui::Color32::from_rgb(0xff, 0x70, 0x43);
+
+    match stats.gpu {
+        None => ui.label(format!("CPU {:.2} ms", millis(stats.cpu))),
+        Some(gpu) => ui.label(format!(
+            "CPU {:.2} ms, GPU {:.2} ms",
+            millis(stats.cpu),
+            millis(gpu)
+        )),
+    };
+
+    if stats.jobs.is_empty() {
+        ui.weak("No jobs executed");
+        return;
+    }
+
+    let cpu_total = stats.cpu.as_secs_f32().max(f32::EPSILON);
+    let gpu_total = stats
+        .gpu
+        .map_or(f32::EPSILON, |gpu| gpu.as_secs_f32().max(f32::EPSILON));
+
+    egui::Grid::new("work-graph-timeline-grid")
+        .num_columns(2)
+        .show(ui, |ui| {
+            for (idx, job) in &stats.jobs {
+                match snarl.get_node(NodeId(idx.0)) {
+                    Some(WorkGraphNode::Job { name, .. }) => ui.label(name.as_str()),
+                    _ => ui.weak(format!("#{}", idx.0)),
+                };
+
+                let width = ui.available_width().max(100.0);
+                let (rect, response) =
+                    ui.allocate_exact_size(egui::vec2(width, 12.0), egui::Sense::hover());
+
+                let painter = ui.painter_at(rect);
+                painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+
+                let bar = |start: f32, len: f32, top: f32| {
+                    let min = rect.left() + rect.width() * start.clamp(0.0, 1.0);
+                    let max = min + (rect.width() * len).max(1.0);
+                    egui::Rect::from_min_max(
+                        egui::pos2(min, top),
+                        egui::pos2(max.min(rect.right()), top + rect.height() * 0.5),
+                    )
+                };
+
+                painter.rect_filled(
+                    bar(
+                        job.exec_start.as_secs_f32() / cpu_total,
+                        job.exec.as_secs_f32() / cpu_total,
+                        rect.top(),
+                    ),
+                    0.0,
+                    CPU_COLOR,
+                );
+
+                if let (Some(start), Some(gpu)) = (job.gpu_start, job.gpu) {
+                    painter.rect_filled(
+                        bar(
+                            start.as_secs_f32() / gpu_total,
+                            gpu.as_secs_f32() / gpu_total,
+                            rect.center().y,
+                        ),
+                        0.0,
+                        GPU_COLOR,
+                    );
+                }
+
+                response.on_hover_text(format_job_stats(job));
+                ui.end_row();
+            }
+        });
+}
+
 #[inline(always)]
 fn present_kind() -> Stid {
     Stid::of::<Image2D>()
@@ -601,7 +863,30 @@ fn present_kind() -> Stid {
 
 #[inline(always)]
 fn present_pin_color() -> egui::Color32 {
-    hue_hash(&present_kind())
+    target_pin_color(present_kind())
+}
+
+/// Returns color of the pin for target type.
+/// Built-in targets have fixed distinct colors,
+/// other targets are colored by type hash.
+fn target_pin_color(ty: Stid) -> egui::Color32 {
+    if ty == Image2D::stid() {
+        egui::Color32::from_rgb(0x4c, 0xaf, 0x50)
+    } else if ty == SampledImage2D::stid() {
+        egui::Color32::from_rgb(0x8b, 0xc3, 0x4a)
+    } else if ty == DepthImage2D::stid() {
+        egui::Color32::from_rgb(0x9e, 0x9e, 0x9e)
+    } else if ty == Image2DArray::stid() {
+        egui::Color32::from_rgb(0x00, 0x96, 0x88)
+    } else if ty == ImageCube::stid() {
+        egui::Color32::from_rgb(0x21, 0x96, 0xf3)
+    } else if ty == Image3D::stid() {
+        egui::Color32::from_rgb(0x67, 0x3a, 0xb7)
+    } else if ty == Buffer::stid() {
+        egui::Color32::from_rgb(0xff, 0x98, 0x00)
+    } else {
+        hue_hash(&ty)
+    }
 }
 
 fn show_preview(
@@ -56,7 +56,11 @@ impl Schedule {
     }
 }
 
-fn order_systems(snarl: &Snarl<SystemNode>, category: Category) -> Vec<SystemId> {
+fn order_systems(
+    snarl: &Snarl<SystemNode>,
+    category: Category,
+    filter: impl Fn(&SystemNode) -> bool,
+) -> Vec<SystemId> {
     let mut order = Vec::new();
 
     let mut queue = VecDeque::new();
@@ -84,7 +88,7 @@ fn order_systems(snarl: &Snarl<SystemNode>, category: Category) -> Vec<SystemId>
 
         let node = &snarl[idx];
 
-        if node.active && node.enabled {
+        if filter(node) {
             order.push(node.system);
         }
         scheduled.insert(idx);
@@ -124,8 +128,12 @@ impl Systems {
         data.systems.snarl.show(&mut viewer, &STYLE, "systems", ui);
 
         if viewer.modified {
-            me.schedule.fix_schedule = order_systems(&data.systems.snarl, Category::Fix);
-            me.schedule.var_schedule = order_systems(&data.systems.snarl, Category::Var);
+            me.schedule.fix_schedule = order_systems(&data.systems.snarl, Category::Fix, |n| {
+                n.active && n.enabled
+            });
+            me.schedule.var_schedule = order_systems(&data.systems.snarl, Category::Var, |n| {
+                n.active && n.enabled
+            });
 
             try_log_err!(data.sync(&project));
         }
@@ -177,6 +185,12 @@ impl SystemGraph {
             snarl: Snarl::new(),
         }
     }
+
+    /// Returns enabled systems of the category in execution order.
+    /// Unlike editor schedule, it includes systems from inactive plugins.
+    pub fn schedule(&self, category: Category) -> Vec<SystemId> {
+        order_systems(&self.snarl, category, |n| n.enabled)
+    }
 }
 
 impl Default for SystemGraph {
@@ -1,7 +1,11 @@
 use std::{fmt::Display, fs::File, io::Write, path::Path};
 
 use argosy_import::{Dependencies, ImportError, Importer, Sources};
-use asefile::AsepriteFile;
+use asefile::{AnimationDirection, AsepriteFile};
+
+use self::texture::{TextureData, TextureImportOptions};
+
+pub mod texture;
 
 /// Imports sprites from Aseprite files.
 struct AsepriteSpriteImporter;
@@ -20,7 +24,7 @@ impl Importer for AsepriteSpriteImporter {
     }
 
     fn target(&self) -> &str {
-        "qoi"
+        "texture"
     }
 
     fn import(
@@ -30,24 +34,403 @@ impl Importer for AsepriteSpriteImporter {
         _sources: &mut dyn Sources,
         _dependencies: &mut dyn Dependencies,
     ) -> Result<(), ImportError> {
+        let options = TextureImportOptions::read(source).map_err(error_to_reason)?;
         let ase = AsepriteFile::read_file(source).map_err(error_to_reason)?;
         let frame = ase.frame(0);
         let image = frame.image();
 
-        let qoi = rapid_qoi::Qoi {
-            width: image.width(),
-            height: image.height(),
-            colors: rapid_qoi::Colors::SrgbLinA,
+        let encoded = TextureData::from_rgba(
+            image.width(),
+            image.height(),
+            image.as_raw(),
+            options.color_space,
+        )
+        .encode();
+
+        let mut outfile = File::create(output).map_err(error_to_reason)?;
+        outfile.write_all(&encoded).map_err(error_to_reason)?;
+        Ok(())
+    }
+}
+
+/// Imports images in common raster formats.
+struct ImageImporter;
+
+impl Importer for ImageImporter {
+    fn name(&self) -> &str {
+        "image"
+    }
+
+    fn formats(&self) -> &[&str] {
+        &["png", "jpeg", "tga"]
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["png", "jpg", "jpeg", "tga"]
+    }
+
+    fn target(&self) -> &str {
+        "texture"
+    }
+
+    fn import(
+        &self,
+        source: &Path,
+        output: &Path,
+        _sources: &mut dyn Sources,
+        _dependencies: &mut dyn Dependencies,
+    ) -> Result<(), ImportError> {
+        let options = TextureImportOptions::read(source).map_err(error_to_reason)?;
+        let image = image::open(source).map_err(error_to_reason)?.to_rgba8();
+        let encoded = TextureData::from_rgba(
+            image.width(),
+            image.height(),
+            image.as_raw(),
+            options.color_space,
+        )
+        .encode();
+
+        let mut outfile = File::create(output).map_err(error_to_reason)?;
+        outfile.write_all(&encoded).map_err(error_to_reason)?;
+        Ok(())
+    }
+}
+
+/// Frame of the sprite animation.
+#[derive(serde::Serialize)]
+struct AnimationFrame {
+    /// Position of the frame in the sheet.
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+
+    /// Duration of the frame in milliseconds.
+    duration: u32,
+}
+
+/// Tagged range of frames.
+#[derive(serde::Serialize)]
+struct AnimationTag {
+    name: String,
+    from: u32,
+    to: u32,
+    direction: &'static str,
+}
+
+#[derive(serde::Serialize)]
+struct AnimationMeta {
+    frames: Vec<AnimationFrame>,
+    tags: Vec<AnimationTag>,
+}
+
+/// Imports animations from Aseprite files.
+///
+/// All frames are placed into a single row of the sprite sheet.
+/// Output contains little-endian `u32` length of JSON metadata,
+/// the metadata itself and sheet as texture artifact.
+struct AsepriteAnimationImporter;
+
+impl Importer for AsepriteAnimationImporter {
+    fn name(&self) -> &str {
+        "aseprite-animation"
+    }
+
+    fn formats(&self) -> &[&str] {
+        &["aseprite"]
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ase", "aseprite"]
+    }
+
+    fn target(&self) -> &str {
+        "sprite-animation"
+    }
+
+    fn import(
+        &self,
+        source: &Path,
+        output: &Path,
+        _sources: &mut dyn Sources,
+        _dependencies: &mut dyn Dependencies,
+    ) -> Result<(), ImportError> {
+        let options = TextureImportOptions::read(source).map_err(error_to_reason)?;
+        let ase = AsepriteFile::read_file(source).map_err(error_to_reason)?;
+
+        let width = ase.width() as u32;
+        let height = ase.height() as u32;
+        let count = ase.num_frames();
+
+        let sheet_width = width * count;
+        let mut sheet = vec![0u8; sheet_width as usize * height as usize * 4];
+
+        let mut frames = Vec::with_capacity(count as usize);
+        for idx in 0..count {
+            let frame = ase.frame(idx);
+            let image = frame.image();
+
+            let row_bytes = width as usize * 4;
+            for (y, row) in image.as_raw().chunks_exact(row_bytes).enumerate() {
+                let start = (y * sheet_width as usize + (idx * width) as usize) * 4;
+                sheet[start..start + row_bytes].copy_from_slice(row);
+            }
+
+            frames.push(AnimationFrame {
+                x: idx * width,
+                y: 0,
+                width,
+                height,
+                duration: frame.duration(),
+            });
+        }
+
+        let tags = (0..ase.num_tags())
+            .map(|idx| {
+                let tag = ase.tag(idx);
+                AnimationTag {
+                    name: tag.name().to_owned(),
+                    from: tag.from_frame(),
+                    to: tag.to_frame(),
+                    direction: match tag.animation_direction() {
+                        AnimationDirection::Forward => "forward",
+                        AnimationDirection::Reverse => "reverse",
+                        AnimationDirection::PingPong => "ping-pong",
+                    },
+                }
+            })
+            .collect();
+
+        let meta = serde_json::to_vec(&AnimationMeta { frames, tags }).map_err(error_to_reason)?;
+        let encoded =
+            TextureData::from_rgba(sheet_width, height, &sheet, options.color_space).encode();
+
+        let mut outfile = File::create(output).map_err(error_to_reason)?;
+        outfile
+            .write_all(&(meta.len() as u32).to_le_bytes())
+            .map_err(error_to_reason)?;
+        outfile.write_all(&meta).map_err(error_to_reason)?;
+        outfile.write_all(&encoded).map_err(error_to_reason)?;
+        Ok(())
+    }
+}
+
+/// Size in pixels at which glyphs are rasterized.
+const FONT_SIZE: f32 = 48.0;
+
+/// Maximum distance in pixels encoded in the distance field.
+const FONT_SPREAD: usize = 6;
+
+/// Width of the glyph atlas in pixels.
+const FONT_ATLAS_WIDTH: usize = 512;
+
+/// Glyph placement in the atlas.
+#[derive(serde::Serialize)]
+struct FontGlyph {
+    char: char,
+
+    /// Position and size of the glyph in the atlas in pixels.
+    /// Includes distance field padding.
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+
+    /// Offset of the bottom-left corner of the glyph from the pen position.
+    /// Y axis points up.
+    xmin: f32,
+    ymin: f32,
+
+    /// Horizontal advance after the glyph.
+    advance: f32,
+}
+
+#[derive(serde::Serialize)]
+struct FontMeta {
+    size: f32,
+    spread: f32,
+    ascent: f32,
+    descent: f32,
+    line_height: f32,
+    glyphs: Vec<FontGlyph>,
+}
+
+/// Imports TrueType and OpenType fonts.
+///
+/// Printable ASCII and Latin-1 glyphs are rasterized into
+/// a single-channel signed distance field atlas.
+/// Output contains little-endian `u32` length of JSON metadata,
+/// the metadata itself and atlas as texture artifact.
+struct FontImporter;
+
+impl Importer for FontImporter {
+    fn name(&self) -> &str {
+        "font"
+    }
+
+    fn formats(&self) -> &[&str] {
+        &["ttf", "otf"]
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ttf", "otf"]
+    }
+
+    fn target(&self) -> &str {
+        "font"
+    }
+
+    fn import(
+        &self,
+        source: &Path,
+        output: &Path,
+        _sources: &mut dyn Sources,
+        _dependencies: &mut dyn Dependencies,
+    ) -> Result<(), ImportError> {
+        let data = std::fs::read(source).map_err(error_to_reason)?;
+        let font = fontdue::Font::from_bytes(data, fontdue::FontSettings::default())
+            .map_err(error_to_reason)?;
+
+        let line = font
+            .horizontal_line_metrics(FONT_SIZE)
+            .ok_or_else(|| error_to_reason("Font has no horizontal metrics"))?;
+
+        let chars = (' '..='~')
+            .chain('\u{a0}'..='\u{ff}')
+            .filter(|&c| c == ' ' || font.has_glyph(c));
+
+        // Pack glyphs into rows.
+        let mut glyphs = Vec::new();
+        let mut fields = Vec::new();
+        let (mut x, mut y, mut row_height) = (0, 0, 0);
+
+        for c in chars {
+            let (metrics, coverage) = font.rasterize(c, FONT_SIZE);
+            let (width, height, field) =
+                distance_field(&coverage, metrics.width, metrics.height, FONT_SPREAD);
+
+            if x + width > FONT_ATLAS_WIDTH {
+                x = 0;
+                y += row_height + 1;
+                row_height = 0;
+            }
+
+            glyphs.push(FontGlyph {
+                char: c,
+                x: x as u32,
+                y: y as u32,
+                width: width as u32,
+                height: height as u32,
+                xmin: (metrics.xmin - FONT_SPREAD as i32) as f32,
+                ymin: (metrics.ymin - FONT_SPREAD as i32) as f32,
+                advance: metrics.advance_width,
+            });
+            fields.push(field);
+
+            x += width + 1;
+            row_height = row_height.max(height);
+        }
+
+        let atlas_height = y + row_height;
+        let mut atlas = vec![0u8; FONT_ATLAS_WIDTH * atlas_height];
+
+        for (glyph, field) in glyphs.iter().zip(&fields) {
+            for (row, values) in field.chunks_exact(glyph.width.max(1) as usize).enumerate() {
+                for (col, &value) in values.iter().enumerate() {
+                    let idx = (glyph.y as usize + row) * FONT_ATLAS_WIDTH + glyph.x as usize + col;
+                    atlas[idx] = value;
+                }
+            }
+        }
+
+        let meta = FontMeta {
+            size: FONT_SIZE,
+            spread: FONT_SPREAD as f32,
+            ascent: line.ascent,
+            descent: line.descent,
+            line_height: line.new_line_size,
+            glyphs,
         };
 
-        let encoded = qoi.encode_alloc(image.as_raw()).map_err(error_to_reason)?;
+        let meta = serde_json::to_vec(&meta).map_err(error_to_reason)?;
+        // Mips and block compression would distort the distance field.
+        let encoded =
+            TextureData::from_r8(FONT_ATLAS_WIDTH as u32, atlas_height as u32, atlas).encode();
 
         let mut outfile = File::create(output).map_err(error_to_reason)?;
+        outfile
+            .write_all(&(meta.len() as u32).to_le_bytes())
+            .map_err(error_to_reason)?;
+        outfile.write_all(&meta).map_err(error_to_reason)?;
         outfile.write_all(&encoded).map_err(error_to_reason)?;
         Ok(())
     }
 }
 
+/// Converts glyph coverage into signed distance field
+/// padded with `spread` pixels on each side.
+///
+/// Value of 128 lies on the glyph edge, larger values are inside.
+fn distance_field(
+    coverage: &[u8],
+    width: usize,
+    height: usize,
+    spread: usize,
+) -> (usize, usize, Vec<u8>) {
+    if width == 0 || height == 0 {
+        return (0, 0, Vec::new());
+    }
+
+    let padded_width = width + spread * 2;
+    let padded_height = height + spread * 2;
+
+    let inside = |x: isize, y: isize| {
+        let x = x - spread as isize;
+        let y = y - spread as isize;
+        if x < 0 || y < 0 || x >= width as isize || y >= height as isize {
+            return false;
+        }
+        coverage[y as usize * width + x as usize] >= 128
+    };
+
+    let radius = spread as isize;
+    let mut field = Vec::with_capacity(padded_width * padded_height);
+
+    for y in 0..padded_height as isize {
+        for x in 0..padded_width as isize {
+            let state = inside(x, y);
+
+            // Distance to the nearest pixel in opposite state.
+            let mut nearest = spread as f32;
+            for dy in -radius..=radius {
+                for dx in -radius..=radius {
+                    if inside(x + dx, y + dy) != state {
+                        let d = ((dx * dx + dy * dy) as f32).sqrt();
+                        nearest = nearest.min(d);
+                    }
+                }
+            }
+
+            let signed = if state { nearest } else { -nearest };
+            let value = 0.5 + signed / (2.0 * spread as f32);
+            field.push((value.clamp(0.0, 1.0) * 255.0).round() as u8);
+        }
+    }
+
+    (padded_width, padded_height, field)
+}
+
+/// Returns all importers provided by this crate.
+/// Used to register importers without loading them as dynamic library.
+pub fn importers() -> Vec<&'static dyn Importer> {
+    vec![
+        &AsepriteSpriteImporter,
+        &AsepriteAnimationImporter,
+        &ImageImporter,
+        &FontImporter,
+    ]
+}
+
 fn error_to_reason<E: Display>(error: E) -> ImportError {
     ImportError::Other {
         reason: error.to_string(),
@@ -56,4 +439,7 @@ fn error_to_reason<E: Display>(error: E) -> ImportError {
 
 argosy_import::make_argosy_importers_library! {
     &AsepriteSpriteImporter;
+    &AsepriteAnimationImporter;
+    &ImageImporter;
+    &FontImporter;
 }
@@ -0,0 +1,582 @@
+//! Texture artifact produced by image importers.
+//!
+//! Mip chain is generated and large textures are block-compressed
+//! at import time, so loading a texture only uploads prepared levels.
+//!
+//! Artifact is little-endian: magic `ATEX`, `u32` width and height,
+//! `u8` format, `u8` sRGB flag, `u8` number of levels
+//! followed by `u32` length and bytes of each level.
+
+use std::{fmt, io::ErrorKind, path::Path};
+
+const MAGIC: [u8; 4] = *b"ATEX";
+
+/// Textures with both dimensions at least this large are block-compressed.
+/// Smaller textures are mostly pixel art that doesn't survive compression.
+pub const COMPRESS_MIN_SIZE: u32 = 512;
+
+/// Format of texture levels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextureFormat {
+    /// Four 8-bit channels.
+    Rgba8,
+
+    /// BC3 blocks of 4x4 pixels.
+    Bc3,
+
+    /// Single 8-bit channel.
+    R8,
+}
+
+/// Color space of texture color channels.
+/// Alpha channel is always linear.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorSpace {
+    #[default]
+    Srgb,
+    Linear,
+}
+
+/// Options of texture import.
+///
+/// Read from JSON file next to the source with `.import.json` appended to its name.
+/// For example `normal.png.import.json` with `{ "color-space": "linear" }`.
+#[derive(Clone, Copy, Debug, Default, serde::Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct TextureImportOptions {
+    pub color_space: ColorSpace,
+}
+
+impl TextureImportOptions {
+    /// Reads options for the source.
+    /// Returns default options if there is no options file.
+    pub fn read(source: &Path) -> std::io::Result<Self> {
+        let mut path = source.as_os_str().to_owned();
+        path.push(".import.json");
+
+        match std::fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(std::io::Error::from),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Error that may occur when decoding texture artifact.
+#[derive(Debug)]
+pub enum TextureDecodeError {
+    /// Artifact is produced by older importer.
+    Outdated,
+
+    /// Artifact is truncated.
+    Truncated,
+
+    /// Unknown level format.
+    Format(u8),
+}
+
+impl fmt::Display for TextureDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextureDecodeError::Outdated => {
+                f.write_str("Texture artifact is outdated, source must be imported again")
+            }
+            TextureDecodeError::Truncated => f.write_str("Texture artifact is truncated"),
+            TextureDecodeError::Format(format) => {
+                write!(f, "Unknown texture artifact format {format}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TextureDecodeError {}
+
+/// Texture prepared for upload.
+#[derive(Clone, Debug)]
+pub struct TextureData {
+    pub width: u32,
+    pub height: u32,
+    pub format: TextureFormat,
+
+    /// Color channels are in sRGB color space.
+    pub srgb: bool,
+
+    /// Mip levels starting from the full-size image.
+    pub levels: Vec<Vec<u8>>,
+}
+
+impl TextureData {
+    /// Prepares texture from RGBA pixels.
+    ///
+    /// Generates full mip chain and compresses large textures to BC3.
+    pub fn from_rgba(width: u32, height: u32, pixels: &[u8], color_space: ColorSpace) -> Self {
+        let srgb = color_space == ColorSpace::Srgb;
+        let mips = mip_chain(width, height, pixels, srgb);
+
+        let compress = width >= COMPRESS_MIN_SIZE
+            && height >= COMPRESS_MIN_SIZE
+            && width % 4 == 0
+            && height % 4 == 0;
+
+        let (format, levels) = if compress {
+            let levels = mips
+                .iter()
+                .map(|(width, height, pixels)| compress_bc3(*width, *height, pixels))
+                .collect();
+            (TextureFormat::Bc3, levels)
+        } else {
+            let levels = mips.into_iter().map(|(_, _, pixels)| pixels).collect();
+            (TextureFormat::Rgba8, levels)
+        };
+
+        TextureData {
+            width,
+            height,
+            format,
+            srgb,
+            levels,
+        }
+    }
+
+    /// Prepares single-level texture from single-channel pixels.
+    pub fn from_r8(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        TextureData {
+            width,
+            height,
+            format: TextureFormat::R8,
+            srgb: false,
+            levels: vec![pixels],
+        }
+    }
+
+    /// Returns size of the level in pixels.
+    pub fn level_extent(&self, level: usize) -> (u32, u32) {
+        ((self.width >> level).max(1), (self.height >> level).max(1))
+    }
+
+    /// Returns number of bytes in one row of the level.
+    /// For compressed formats it is a row of blocks.
+    pub fn bytes_per_line(&self, level: usize) -> usize {
+        let (width, _) = self.level_extent(level);
+        match self.format {
+            TextureFormat::Rgba8 => width as usize * 4,
+            TextureFormat::Bc3 => width.div_ceil(4) as usize * 16,
+            TextureFormat::R8 => width as usize,
+        }
+    }
+
+    /// Decompresses BC3 levels into RGBA.
+    /// Used when device doesn't support block compression.
+    pub fn decompress(&mut self) {
+        if self.format != TextureFormat::Bc3 {
+            return;
+        }
+
+        for level in 0..self.levels.len() {
+            let (width, height) = self.level_extent(level);
+            self.levels[level] = decompress_bc3(width, height, &self.levels[level]);
+        }
+        self.format = TextureFormat::Rgba8;
+    }
+
+    /// Encodes texture into artifact bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let size = self
+            .levels
+            .iter()
+            .map(|level| level.len() + 4)
+            .sum::<usize>();
+        let mut out = Vec::with_capacity(15 + size);
+
+        out.extend_from_slice(&MAGIC);
+        out.extend_from_slice(&self.width.to_le_bytes());
+        out.extend_from_slice(&self.height.to_le_bytes());
+        out.push(match self.format {
+            TextureFormat::Rgba8 => 0,
+            TextureFormat::Bc3 => 1,
+            TextureFormat::R8 => 2,
+        });
+        out.push(self.srgb as u8);
+        out.push(self.levels.len() as u8);
+
+        for level in &self.levels {
+            out.extend_from_slice(&(level.len() as u32).to_le_bytes());
+            out.extend_from_slice(level);
+        }
+
+        out
+    }
+
+    /// Decodes texture from artifact bytes.
+    pub fn decode(bytes: &[u8]) -> Result<Self, TextureDecodeError> {
+        let Some((magic, mut bytes)) = bytes.split_first_chunk::<4>() else {
+            return Err(TextureDecodeError::Outdated);
+        };
+
+        if *magic != MAGIC {
+            return Err(TextureDecodeError::Outdated);
+        }
+
+        let width = read_u32(&mut bytes)?;
+        let height = read_u32(&mut bytes)?;
+
+        let [format, srgb, count, ref rest @ ..] = *bytes else {
+            return Err(TextureDecodeError::Truncated);
+        };
+        bytes = rest;
+
+        let format = match format {
+            0 => TextureFormat::Rgba8,
+            1 => TextureFormat::Bc3,
+            2 => TextureFormat::R8,
+            _ => return Err(TextureDecodeError::Format(format)),
+        };
+
+        let mut levels = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let len = read_u32(&mut bytes)? as usize;
+            if bytes.len() < len {
+                return Err(TextureDecodeError::Truncated);
+            }
+            let (level, rest) = bytes.split_at(len);
+            levels.push(level.to_vec());
+            bytes = rest;
+        }
+
+        Ok(TextureData {
+            width,
+            height,
+            format,
+            srgb: srgb != 0,
+            levels,
+        })
+    }
+}
+
+fn read_u32(bytes: &mut &[u8]) -> Result<u32, TextureDecodeError> {
+    let (value, rest) = bytes
+        .split_first_chunk::<4>()
+        .ok_or(TextureDecodeError::Truncated)?;
+    *bytes = rest;
+    Ok(u32::from_le_bytes(*value))
+}
+
+/// Generates mip chain down to 1x1 level.
+/// First level is the original image.
+///
+/// Levels are downsampled with box filter.
+/// sRGB colors are averaged in linear space.
+fn mip_chain(width: u32, height: u32, pixels: &[u8], srgb: bool) -> Vec<(u32, u32, Vec<u8>)> {
+    let mut levels = vec![(width, height, pixels.to_vec())];
+
+    let (mut width, mut height) = (width, height);
+    while width > 1 || height > 1 {
+        let (_, _, prev) = levels.last().unwrap();
+
+        let next_width = (width / 2).max(1);
+        let next_height = (height / 2).max(1);
+        let mut next = vec![0u8; next_width as usize * next_height as usize * 4];
+
+        for y in 0..next_height {
+            for x in 0..next_width {
+                let mut sum = [0.0f32; 4];
+
+                for (sx, sy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let px = (x * 2 + sx).min(width - 1);
+                    let py = (y * 2 + sy).min(height - 1);
+                    let idx = (py * width + px) as usize * 4;
+
+                    for c in 0..4 {
+                        let v = prev[idx + c] as f32 / 255.0;
+                        sum[c] += if srgb && c < 3 { srgb_to_linear(v) } else { v };
+                    }
+                }
+
+                let idx = (y * next_width + x) as usize * 4;
+                for c in 0..4 {
+                    let v = sum[c] / 4.0;
+                    let v = if srgb && c < 3 { linear_to_srgb(v) } else { v };
+                    next[idx + c] = (v * 255.0 + 0.5) as u8;
+                }
+            }
+        }
+
+        levels.push((next_width, next_height, next));
+        width = next_width;
+        height = next_height;
+    }
+
+    levels
+}
+
+fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(v: f32) -> f32 {
+    if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Compresses RGBA pixels to BC3 blocks.
+/// Partial blocks at the edges are padded with edge pixels.
+fn compress_bc3(width: u32, height: u32, pixels: &[u8]) -> Vec<u8> {
+    let blocks_x = width.div_ceil(4);
+    let blocks_y = height.div_ceil(4);
+    let mut out = Vec::with_capacity(blocks_x as usize * blocks_y as usize * 16);
+
+    for by in 0..blocks_y {
+        for bx in 0..blocks_x {
+            let mut block = [[0u8; 4]; 16];
+            for (i, texel) in block.iter_mut().enumerate() {
+                let x = (bx * 4 + i as u32 % 4).min(width - 1);
+                let y = (by * 4 + i as u32 / 4).min(height - 1);
+                let idx = (y * width + x) as usize * 4;
+                texel.copy_from_slice(&pixels[idx..idx + 4]);
+            }
+
+            out.extend_from_slice(&bc3_alpha_block(&block));
+            out.extend_from_slice(&bc1_color_block(&block));
+        }
+    }
+
+    out
+}
+
+/// Decompresses BC3 blocks to RGBA pixels.
+fn decompress_bc3(width: u32, height: u32, blocks: &[u8]) -> Vec<u8> {
+    let blocks_x = width.div_ceil(4);
+    let mut out = vec![0u8; width as usize * height as usize * 4];
+
+    for (idx, block) in blocks.chunks_exact(16).enumerate() {
+        let bx = idx as u32 % blocks_x;
+        let by = idx as u32 / blocks_x;
+
+        let alpha = bc3_alpha_palette(block[0], block[1]);
+        let mut alpha_bits = [0u8; 8];
+        alpha_bits[..6].copy_from_slice(&block[2..8]);
+        let alpha_bits = u64::from_le_bytes(alpha_bits);
+
+        let e0 = unpack565(u16::from_le_bytes([block[8], block[9]]));
+        let e1 = unpack565(u16::from_le_bytes([block[10], block[11]]));
+        let color = bc1_palette(e0, e1);
+        let color_bits = u32::from_le_bytes([block[12], block[13], block[14], block[15]]);
+
+        for i in 0..16 {
+            let x = bx * 4 + i % 4;
+            let y = by * 4 + i / 4;
+            if x >= width || y >= height {
+                continue;
+            }
+
+            let rgb = color[(color_bits >> (2 * i)) as usize & 3];
+            let a = alpha[(alpha_bits >> (3 * i)) as usize & 7];
+
+            let idx = (y * width + x) as usize * 4;
+            out[idx..idx + 4].copy_from_slice(&[rgb[0] as u8, rgb[1] as u8, rgb[2] as u8, a as u8]);
+        }
+    }
+
+    out
+}
+
+/// Encodes alpha of the block using 8 interpolated values.
+fn bc3_alpha_block(block: &[[u8; 4]; 16]) -> [u8; 8] {
+    let a0 = block.iter().map(|t| t[3]).max().unwrap();
+    let a1 = block.iter().map(|t| t[3]).min().unwrap();
+
+    let mut out = [0u8; 8];
+    out[0] = a0;
+    out[1] = a1;
+
+    if a0 == a1 {
+        return out;
+    }
+
+    let palette = bc3_alpha_palette(a0, a1);
+
+    let mut bits = 0u64;
+    for (i, texel) in block.iter().enumerate() {
+        let index = nearest(&palette, |p| p.abs_diff(texel[3] as u32));
+        bits |= (index as u64) << (3 * i);
+    }
+
+    out[2..].copy_from_slice(&bits.to_le_bytes()[..6]);
+    out
+}
+
+/// Returns alpha values of the BC3 block.
+/// Uses 6 interpolated values with 0 and 255 when `a0 <= a1`.
+fn bc3_alpha_palette(a0: u8, a1: u8) -> [u32; 8] {
+    let (a0, a1) = (a0 as u32, a1 as u32);
+
+    if a0 > a1 {
+        [
+            a0,
+            a1,
+            (6 * a0 + a1) / 7,
+            (5 * a0 + 2 * a1) / 7,
+            (4 * a0 + 3 * a1) / 7,
+            (3 * a0 + 4 * a1) / 7,
+            (2 * a0 + 5 * a1) / 7,
+            (a0 + 6 * a1) / 7,
+        ]
+    } else {
+        [
+            a0,
+            a1,
+            (4 * a0 + a1) / 5,
+            (3 * a0 + 2 * a1) / 5,
+            (2 * a0 + 3 * a1) / 5,
+            (a0 + 4 * a1) / 5,
+            0,
+            255,
+        ]
+    }
+}
+
+/// Encodes color of the block in 4-color mode
+/// with endpoints at the corners of the color bounding box.
+fn bc1_color_block(block: &[[u8; 4]; 16]) -> [u8; 8] {
+    let mut min = [255u8; 3];
+    let mut max = [0u8; 3];
+    for texel in block {
+        for c in 0..3 {
+            min[c] = min[c].min(texel[c]);
+            max[c] = max[c].max(texel[c]);
+        }
+    }
+
+    let mut c0 = rgb565(max);
+    let mut c1 = rgb565(min);
+
+    if c0 < c1 {
+        std::mem::swap(&mut c0, &mut c1);
+    }
+
+    let mut out = [0u8; 8];
+    out[0..2].copy_from_slice(&c0.to_le_bytes());
+    out[2..4].copy_from_slice(&c1.to_le_bytes());
+
+    if c0 == c1 {
+        return out;
+    }
+
+    let palette = bc1_palette(unpack565(c0), unpack565(c1));
+
+    let mut bits = 0u32;
+    for (i, texel) in block.iter().enumerate() {
+        let index = nearest(&palette, |p| {
+            (0..3)
+                .map(|c| {
+                    let d = p[c].abs_diff(texel[c] as u32);
+                    d * d
+                })
+                .sum()
+        });
+        bits |= (index as u32) << (2 * i);
+    }
+
+    out[4..].copy_from_slice(&bits.to_le_bytes());
+    out
+}
+
+/// Returns colors of the block in 4-color mode.
+/// BC3 color blocks always use this mode.
+fn bc1_palette(e0: [u32; 3], e1: [u32; 3]) -> [[u32; 3]; 4] {
+    [
+        e0,
+        e1,
+        std::array::from_fn(|c| (2 * e0[c] + e1[c]) / 3),
+        std::array::from_fn(|c| (e0[c] + 2 * e1[c]) / 3),
+    ]
+}
+
+fn nearest<T>(palette: &[T], distance: impl Fn(&T) -> u32) -> usize {
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, p)| distance(p))
+        .map_or(0, |(idx, _)| idx)
+}
+
+fn rgb565(rgb: [u8; 3]) -> u16 {
+    ((rgb[0] as u16 >> 3) << 11) | ((rgb[1] as u16 >> 2) << 5) | (rgb[2] as u16 >> 3)
+}
+
+fn unpack565(c: u16) -> [u32; 3] {
+    let r = ((c >> 11) & 0x1f) as u32;
+    let g = ((c >> 5) & 0x3f) as u32;
+    let b = (c & 0x1f) as u32;
+    [
+        (r << 3) | (r >> 2),
+        (g << 2) | (g >> 4),
+        (b << 3) | (b >> 2),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mip_chain_levels() {
+        let pixels = vec![255u8; 8 * 4 * 4];
+        let texture = TextureData::from_rgba(8, 4, &pixels, ColorSpace::Linear);
+
+        assert_eq!(texture.format, TextureFormat::Rgba8);
+        assert_eq!(texture.levels.len(), 4);
+        for (level, data) in texture.levels.iter().enumerate() {
+            let (width, height) = texture.level_extent(level);
+            assert_eq!(data.len(), width as usize * height as usize * 4);
+        }
+        assert_eq!(texture.level_extent(3), (1, 1));
+    }
+
+    #[test]
+    fn artifact_round_trip() {
+        let pixels = (0..16 * 16 * 4).map(|i| i as u8).collect::<Vec<_>>();
+        let texture = TextureData::from_rgba(16, 16, &pixels, ColorSpace::Srgb);
+
+        let decoded = TextureData::decode(&texture.encode()).unwrap();
+        assert_eq!((decoded.width, decoded.height), (16, 16));
+        assert_eq!(decoded.format, TextureFormat::Rgba8);
+        assert!(decoded.srgb);
+        assert_eq!(decoded.levels, texture.levels);
+
+        assert!(matches!(
+            TextureData::decode(&pixels),
+            Err(TextureDecodeError::Outdated)
+        ));
+    }
+
+    #[test]
+    fn bc3_round_trip() {
+        let size = COMPRESS_MIN_SIZE;
+        let pixels = (0..size * size)
+            .flat_map(|i| {
+                if (i / 8) % 2 == 0 {
+                    [255, 0, 0, 255]
+                } else {
+                    [0, 0, 255, 0]
+                }
+            })
+            .collect::<Vec<u8>>();
+
+        let mut texture = TextureData::from_rgba(size, size, &pixels, ColorSpace::Linear);
+        assert_eq!(texture.format, TextureFormat::Bc3);
+        assert_eq!(texture.levels[0].len(), (size as usize / 4).pow(2) * 16);
+
+        texture.decompress();
+        assert_eq!(texture.format, TextureFormat::Rgba8);
+        assert_eq!(texture.levels[0], pixels);
+    }
+}
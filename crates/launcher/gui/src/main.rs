@@ -1,17 +1,15 @@
 use std::{path::PathBuf, process::Child, time::Duration};
 
 use arcana::{
-    blink_alloc::BlinkAlloc,
     edict::world::World,
     gametime::FrequencyNumExt,
     input::ViewportInput,
     mev,
     project::{Dependency, Profile, Project},
-    render::{render, RenderGraph, RenderResources},
     viewport::Viewport,
     Clock, ClockStep, Ident,
 };
-use arcana_egui::{Egui, EguiRender};
+use arcana_egui::{Egui, EguiGraph};
 use arcana_launcher::Start;
 use egui::vec2;
 use egui_file::FileDialog;
@@ -108,11 +106,9 @@ pub struct App {
     // App state is stored in World.
     world: World,
 
-    graph: RenderGraph,
-    resources: RenderResources,
+    graph: EguiGraph,
     device: mev::Device,
     queue: mev::Queue,
-    blink: BlinkAlloc,
     start: Start,
     profile: Profile,
     recent: HashMap<PathBuf, Result<Project, miette::Report>>,
@@ -141,7 +137,6 @@ impl App {
         builder.register_external::<mev::Surface>();
 
         let mut world = builder.build();
-        let mut graph = RenderGraph::new();
 
         let builder = Window::default_attributes().with_title("Arcana Launcher");
 
@@ -162,16 +157,14 @@ impl App {
         world.insert_resource(Viewport::new_window(window));
         world.insert_resource(egui);
 
-        let target = EguiRender::build(None, &mut graph);
-        graph.present(target);
+        // Draw egui from the resource.
+        let graph = EguiGraph::new(None);
 
         App {
             world: world.into(),
             graph,
-            resources: RenderResources::default(),
             device,
             queue,
-            blink: BlinkAlloc::new(),
             start: Start::new(),
             profile: Profile::Debug,
             recent: HashMap::new(),
@@ -613,15 +606,12 @@ impl App {
     }
 
     fn render(&mut self) {
-        render(
-            &mut self.graph,
-            &self.device,
-            &mut self.queue,
-            &self.blink,
-            None,
-            &mut self.world,
-            &mut self.resources,
-        );
+        if let Err(err) = self
+            .graph
+            .render(&self.device, &mut self.queue, &mut self.world)
+        {
+            tracing::error!(?err);
+        }
     }
 
     fn should_quit(&self) -> bool {
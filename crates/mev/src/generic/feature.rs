@@ -8,5 +8,10 @@ bitflags::bitflags! {
         ///
         /// See [`Device::new_surface`](crate::Device::new_surface).
         const SURFACE = 0x0000_0000_0000_0000_0000_0000_0000_0001;
+
+        /// If this feature is enabled, images can be created with BC formats.
+        ///
+        /// See [`PixelFormat::is_compressed`](crate::PixelFormat::is_compressed).
+        const TEXTURE_COMPRESSION_BC = 0x0000_0000_0000_0000_0000_0000_0000_0002;
     }
 }
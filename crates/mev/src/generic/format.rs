@@ -76,6 +76,14 @@ pub enum PixelFormat {
     D16UnormS8Uint,
     D24UnormS8Uint,
     D32FloatS8Uint,
+
+    /// BC1 block-compressed RGBA, 8 bytes per 4x4 block.
+    Bc1RgbaUnorm,
+    Bc1RgbaSrgb,
+
+    /// BC3 block-compressed RGBA, 16 bytes per 4x4 block.
+    Bc3RgbaUnorm,
+    Bc3RgbaSrgb,
 }
 
 impl PixelFormat {
@@ -158,6 +166,10 @@ impl PixelFormat {
             | PixelFormat::D16UnormS8Uint
             | PixelFormat::D24UnormS8Uint
             | PixelFormat::D32FloatS8Uint => false,
+            PixelFormat::Bc1RgbaUnorm
+            | PixelFormat::Bc1RgbaSrgb
+            | PixelFormat::Bc3RgbaUnorm
+            | PixelFormat::Bc3RgbaSrgb => true,
         }
     }
 
@@ -240,6 +252,10 @@ impl PixelFormat {
             | PixelFormat::D16UnormS8Uint
             | PixelFormat::D24UnormS8Uint
             | PixelFormat::D32FloatS8Uint => true,
+            PixelFormat::Bc1RgbaUnorm
+            | PixelFormat::Bc1RgbaSrgb
+            | PixelFormat::Bc3RgbaUnorm
+            | PixelFormat::Bc3RgbaSrgb => false,
         }
     }
 
@@ -321,9 +337,15 @@ impl PixelFormat {
             | PixelFormat::D16UnormS8Uint
             | PixelFormat::D24UnormS8Uint
             | PixelFormat::D32FloatS8Uint => true,
+            PixelFormat::Bc1RgbaUnorm
+            | PixelFormat::Bc1RgbaSrgb
+            | PixelFormat::Bc3RgbaUnorm
+            | PixelFormat::Bc3RgbaSrgb => false,
         }
     }
 
+    /// Returns size of the texel in bytes.
+    /// For block-compressed formats returns size of the block.
     #[cfg_attr(inline_more, inline(always))]
     pub fn size(&self) -> usize {
         match self {
@@ -403,9 +425,24 @@ impl PixelFormat {
             PixelFormat::D16UnormS8Uint => 3,
             PixelFormat::D24UnormS8Uint => 4,
             PixelFormat::D32FloatS8Uint => 5,
+            PixelFormat::Bc1RgbaUnorm | PixelFormat::Bc1RgbaSrgb => 8,
+            PixelFormat::Bc3RgbaUnorm | PixelFormat::Bc3RgbaSrgb => 16,
         }
     }
 
+    /// Returns true if format is block-compressed.
+    /// Such formats store 4x4 blocks of texels.
+    #[cfg_attr(inline_more, inline(always))]
+    pub fn is_compressed(&self) -> bool {
+        matches!(
+            self,
+            PixelFormat::Bc1RgbaUnorm
+                | PixelFormat::Bc1RgbaSrgb
+                | PixelFormat::Bc3RgbaUnorm
+                | PixelFormat::Bc3RgbaSrgb
+        )
+    }
+
     #[cfg_attr(inline_more, inline(always))]
     pub fn is_srgb(&self) -> bool {
         match self {
@@ -414,7 +451,9 @@ impl PixelFormat {
             | PixelFormat::Rgb8Srgb
             | PixelFormat::Rgba8Srgb
             | PixelFormat::Bgr8Srgb
-            | PixelFormat::Bgra8Srgb => true,
+            | PixelFormat::Bgra8Srgb
+            | PixelFormat::Bc1RgbaSrgb
+            | PixelFormat::Bc3RgbaSrgb => true,
             _ => false,
         }
     }
@@ -219,6 +219,11 @@ pub struct ImageDesc<'a> {
     pub usage: ImageUsage,
     pub layers: u32,
     pub levels: u32,
+
+    /// Image is compatible with cube views.
+    /// Requires square 2d image with number of layers multiple of 6.
+    /// Views of such image with 6 layers (or multiple of 6) are cube views.
+    pub cube: bool,
     pub name: &'a str,
 }
 
@@ -230,6 +235,7 @@ impl<'a> ImageDesc<'a> {
             usage,
             layers: 1,
             levels: 1,
+            cube: false,
             name: "",
         }
     }
@@ -266,6 +272,11 @@ impl<'a> ImageDesc<'a> {
         self
     }
 
+    pub fn cube(mut self, cube: bool) -> Self {
+        self.cube = cube;
+        self
+    }
+
     pub const fn new_d1_texture(width: u32, format: PixelFormat) -> Self {
         ImageDesc::new_d1(
             width,
@@ -15,7 +15,7 @@ use crate::{
 };
 
 use super::{
-    from::TryIntoMetal, out_of_bounds, shader::Bindings, Blas, Buffer, Frame, Image,
+    from::TryIntoMetal, out_of_bounds, shader::Bindings, Blas, Buffer, Frame, Image, QueryPool,
     RenderPipeline, Tlas,
 };
 
@@ -171,6 +171,12 @@ impl crate::traits::CommandEncoder for CommandEncoder {
         self.buffer.present_drawable(frame.drawable());
     }
 
+    #[inline(always)]
+    fn reset_queries(&mut self, _pool: &QueryPool, _range: Range<u32>) {}
+
+    #[inline(always)]
+    fn write_timestamp(&mut self, _pool: &QueryPool, _index: u32) {}
+
     #[inline(always)]
     fn finish(self) -> Result<CommandBuffer, OutOfMemory> {
         Ok(CommandBuffer {
@@ -18,9 +18,9 @@ use raw_window_handle::{
 use crate::{
     generic::{
         parse_shader, ArgumentKind, BlasDesc, BufferDesc, BufferInitDesc, ComputePipelineDesc,
-        CreateLibraryError, CreatePipelineError, ImageDesc, ImageExtent, LibraryDesc, LibraryInput,
-        Memory, OutOfMemory, RenderPipelineDesc, SamplerDesc, ShaderCompileError, ShaderLanguage,
-        SurfaceError, TlasDesc, VertexStepMode,
+        CreateLibraryError, CreatePipelineError, Features, ImageDesc, ImageExtent, LibraryDesc,
+        LibraryInput, Memory, OutOfMemory, RenderPipelineDesc, SamplerDesc, ShaderCompileError,
+        ShaderLanguage, SurfaceError, TlasDesc, VertexStepMode,
     },
     Extent3,
 };
@@ -28,13 +28,14 @@ use crate::{
 use super::{
     from::{IntoMetal, TryIntoMetal},
     shader::{Bindings, EntryPointData},
-    Blas, Buffer, ComputePipeline, CreatePipelineErrorKind, Image, Library, RenderPipeline,
-    Sampler, Surface, Tlas, MAX_VERTEX_BUFFERS,
+    Blas, Buffer, ComputePipeline, CreatePipelineErrorKind, Image, Library, QueryPool,
+    RenderPipeline, Sampler, Surface, Tlas, MAX_VERTEX_BUFFERS,
 };
 
 #[derive(Clone)]
 pub struct Device {
     device: metal::Device,
+    features: Features,
 }
 
 unsafe impl Sync for Device {}
@@ -57,13 +58,17 @@ impl PartialEq for Device {
 impl Eq for Device {}
 
 impl Device {
-    pub(super) fn new(device: metal::Device) -> Self {
-        Device { device }
+    pub(super) fn new(device: metal::Device, features: Features) -> Self {
+        Device { device, features }
     }
 }
 
 #[hidden_trait::expose]
 impl crate::traits::Device for Device {
+    fn features(&self) -> Features {
+        self.features
+    }
+
     fn new_shader_library(&self, desc: LibraryDesc) -> Result<Library, CreateLibraryError> {
         match desc.input {
             LibraryInput::Source(source) => {
@@ -325,7 +330,12 @@ impl crate::traits::Device for Device {
                 mdesc.set_width(extent.width() as _);
             }
             ImageExtent::D2(extent) => {
-                mdesc.set_texture_type(metal::MTLTextureType::D2);
+                mdesc.set_texture_type(match (desc.cube, desc.layers) {
+                    (true, 6) => metal::MTLTextureType::Cube,
+                    (true, _) => metal::MTLTextureType::CubeArray,
+                    (false, 1) => metal::MTLTextureType::D2,
+                    (false, _) => metal::MTLTextureType::D2Array,
+                });
                 mdesc.set_width(extent.width() as _);
                 mdesc.set_height(extent.height() as _);
             }
@@ -337,7 +347,13 @@ impl crate::traits::Device for Device {
             }
         }
         mdesc.set_mipmap_level_count(desc.levels as _);
-        mdesc.set_array_length(desc.layers as _);
+
+        // Array length of cube textures counts whole cubes.
+        if desc.cube {
+            mdesc.set_array_length((desc.layers / 6) as _);
+        } else {
+            mdesc.set_array_length(desc.layers as _);
+        }
         mdesc.set_sample_count(1);
         mdesc.set_usage(desc.usage.into_metal());
         mdesc.set_storage_mode(metal::MTLStorageMode::Private);
@@ -408,6 +424,14 @@ impl crate::traits::Device for Device {
         let tlas = self.device.new_acceleration_structure_with_size(size);
         Ok(Tlas::new(tlas))
     }
+
+    fn new_query_pool(&self, count: u32) -> Result<QueryPool, OutOfMemory> {
+        Ok(QueryPool::new(count))
+    }
+
+    fn timestamp_period(&self) -> Option<f32> {
+        None
+    }
 }
 
 unsafe fn layer_from_view(view: *mut Object) -> metal::MetalLayer {
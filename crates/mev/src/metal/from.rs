@@ -187,6 +187,10 @@ impl TryMetalFrom<PixelFormat> for metal::MTLPixelFormat {
             // PixelFormat::D16UnormS8Uint => metal::MTLPixelFormat::Depth16Unorm_Stencil8,
             PixelFormat::D24UnormS8Uint => metal::MTLPixelFormat::Depth24Unorm_Stencil8,
             PixelFormat::D32FloatS8Uint => metal::MTLPixelFormat::Depth32Float_Stencil8,
+            PixelFormat::Bc1RgbaUnorm => metal::MTLPixelFormat::BC1_RGBA,
+            PixelFormat::Bc1RgbaSrgb => metal::MTLPixelFormat::BC1_RGBA_sRGB,
+            PixelFormat::Bc3RgbaUnorm => metal::MTLPixelFormat::BC3_RGBA,
+            PixelFormat::Bc3RgbaSrgb => metal::MTLPixelFormat::BC3_RGBA_sRGB,
             _ => return None,
         })
     }
@@ -271,6 +275,10 @@ impl TryFromMetal<metal::MTLPixelFormat> for PixelFormat {
             // metal::MTLPixelFormat::Depth16Unorm_Stencil8 => PixelFormat::D16UnormS8Uint,
             metal::MTLPixelFormat::Depth24Unorm_Stencil8 => PixelFormat::D24UnormS8Uint,
             metal::MTLPixelFormat::Depth32Float_Stencil8 => PixelFormat::D32FloatS8Uint,
+            metal::MTLPixelFormat::BC1_RGBA => PixelFormat::Bc1RgbaUnorm,
+            metal::MTLPixelFormat::BC1_RGBA_sRGB => PixelFormat::Bc1RgbaSrgb,
+            metal::MTLPixelFormat::BC3_RGBA => PixelFormat::Bc3RgbaUnorm,
+            metal::MTLPixelFormat::BC3_RGBA_sRGB => PixelFormat::Bc3RgbaSrgb,
             _ => return None,
         })
     }
@@ -64,15 +64,16 @@ impl crate::traits::Image for Image {
                 let width = self.texture.width();
                 ImageExtent::D1(Extent1::new(width as u32))
             }
-            MTLTextureType::D2 | MTLTextureType::D2Array => {
+            MTLTextureType::D2
+            | MTLTextureType::D2Array
+            | MTLTextureType::Cube
+            | MTLTextureType::CubeArray => {
                 let width = self.texture.width();
                 let height = self.texture.height();
                 ImageExtent::D2(Extent2::new(width as u32, height as u32))
             }
             MTLTextureType::D2Multisample => unimplemented!(),
             MTLTextureType::D2MultisampleArray => unimplemented!(),
-            MTLTextureType::Cube => unimplemented!(),
-            MTLTextureType::CubeArray => unimplemented!(),
             MTLTextureType::D3 => {
                 let width = self.texture.width();
                 let height = self.texture.height();
@@ -83,7 +84,12 @@ impl crate::traits::Image for Image {
     }
 
     fn layers(&self) -> u32 {
-        self.texture.array_length() as u32
+        match self.texture.texture_type() {
+            MTLTextureType::Cube | MTLTextureType::CubeArray => {
+                self.texture.array_length() as u32 * 6
+            }
+            _ => self.texture.array_length() as u32,
+        }
     }
 
     fn levels(&self) -> u32 {
@@ -100,18 +106,23 @@ impl crate::traits::Image for Image {
 
         let pixel_format = desc.format.expect_into_metal();
         let root_texture = self.texture.parent_texture().unwrap_or(&self.texture);
+        let texture_type = view_type(self.texture.texture_type(), desc.layers);
 
         if desc.swizzle == Swizzle::IDENTITY {
-            if desc.base_layer == 0 && desc.base_level == 0 {
+            if desc.base_layer == 0
+                && desc.base_level == 0
+                && desc.layers == self.layers()
+                && desc.levels == self.levels()
+            {
                 let texture = root_texture.new_texture_view(desc.format.expect_into_metal());
                 Ok(Image { texture })
             } else {
                 let base_layer = self.texture.parent_relative_slice() as u32 + desc.base_layer;
-                let base_level = self.texture.mipmap_level_count() as u32 + desc.base_level;
+                let base_level = self.texture.parent_relative_level() as u32 + desc.base_level;
 
                 let texture = root_texture.new_texture_view_from_slice(
                     pixel_format,
-                    self.texture.texture_type(),
+                    texture_type,
                     metal::NSRange::new(base_level.into(), desc.levels.into()),
                     metal::NSRange::new(base_layer.into(), desc.layers.into()),
                 );
@@ -127,7 +138,7 @@ impl crate::traits::Image for Image {
 
             let texture = unsafe {
                 msg_send![root_texture.as_ptr(), newTextureViewWithPixelFormat:pixel_format
-                                                textureType:self.texture.texture_type()
+                                                textureType:texture_type
                                                 levels:metal::NSRange::new(base_level.into(), desc.levels.into())
                                                 slices:metal::NSRange::new(base_layer.into(), desc.layers.into())
                                                 swizzle:new_swizzle
@@ -149,6 +160,30 @@ impl crate::traits::Image for Image {
     }
 }
 
+/// Returns type of the view with specified number of layers.
+///
+/// Views of cube textures with multiple of 6 layers are cubes,
+/// views of single layer are 2d.
+fn view_type(texture_type: MTLTextureType, layers: u32) -> MTLTextureType {
+    match texture_type {
+        MTLTextureType::Cube | MTLTextureType::CubeArray if layers == 6 => MTLTextureType::Cube,
+        MTLTextureType::Cube | MTLTextureType::CubeArray if layers % 6 == 0 => {
+            MTLTextureType::CubeArray
+        }
+        MTLTextureType::Cube
+        | MTLTextureType::CubeArray
+        | MTLTextureType::D2
+        | MTLTextureType::D2Array => {
+            if layers > 1 {
+                MTLTextureType::D2Array
+            } else {
+                MTLTextureType::D2
+            }
+        }
+        other => other,
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
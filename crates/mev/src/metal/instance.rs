@@ -36,7 +36,12 @@ impl Instance {
         Ok(Instance {
             capabilities: Capabilities {
                 devices: vec![DeviceCapabilities {
-                    features: Features::empty(),
+                    features: match metal::Device::system_default() {
+                        Some(device) if device.supports_bc_texture_compression() => {
+                            Features::TEXTURE_COMPRESSION_BC
+                        }
+                        _ => Features::empty(),
+                    },
                     families: vec![FamilyCapabilities {
                         queue_flags: QueueFlags::GRAPHICS
                             | QueueFlags::COMPUTE
@@ -74,6 +79,6 @@ impl crate::traits::Instance for Instance {
             .map(|_| Queue::new(device.clone(), device.new_command_queue()))
             .collect();
 
-        Ok((Device::new(device), queues))
+        Ok((Device::new(device, info.features), queues))
     }
 }
@@ -7,6 +7,7 @@ mod device;
 mod from;
 mod image;
 mod instance;
+mod query;
 mod queue;
 mod render_pipeline;
 mod sampler;
@@ -24,6 +25,7 @@ pub use self::{
     device::Device,
     image::Image,
     instance::Instance,
+    query::QueryPool,
     queue::Queue,
     render_pipeline::RenderPipeline,
     sampler::Sampler,
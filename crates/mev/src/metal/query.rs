@@ -0,0 +1,29 @@
+use std::ops::Range;
+
+/// Pool of timestamp queries.
+///
+/// Timestamps are not supported by the metal backend yet,
+/// so queries never become available.
+#[derive(Clone, Debug)]
+pub struct QueryPool {
+    count: u32,
+}
+
+impl QueryPool {
+    pub(super) fn new(count: u32) -> Self {
+        QueryPool { count }
+    }
+}
+
+#[hidden_trait::expose]
+impl crate::traits::QueryPool for QueryPool {
+    #[inline(always)]
+    fn count(&self) -> u32 {
+        self.count
+    }
+
+    #[inline(always)]
+    fn timestamps(&self, _range: Range<u32>) -> Option<Vec<u64>> {
+        None
+    }
+}
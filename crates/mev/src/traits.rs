@@ -6,8 +6,8 @@ use crate::{
     generic::{
         Arguments, AsBufferSlice, BlasBuildDesc, BlasDesc, BufferDesc, BufferInitDesc, BufferSlice,
         Capabilities, ComputePipelineDesc, CreateError, CreateLibraryError, CreatePipelineError,
-        DeviceDesc, DeviceError, DeviceRepr, Extent2, Extent3, ImageDesc, ImageExtent, LibraryDesc,
-        Offset2, Offset3, OutOfMemory, PipelineStages, PixelFormat, RenderPassDesc,
+        DeviceDesc, DeviceError, DeviceRepr, Extent2, Extent3, Features, ImageDesc, ImageExtent,
+        LibraryDesc, Offset2, Offset3, OutOfMemory, PipelineStages, PixelFormat, RenderPassDesc,
         RenderPipelineDesc, SamplerDesc, SurfaceError, TlasBuildDesc, TlasDesc, ViewDesc,
     },
     ImageUsage, Shader,
@@ -22,6 +22,9 @@ pub trait Instance: Debug + Send + Sync + 'static {
 }
 
 pub trait Device: Clone + Debug + Eq + Send + Sync + 'static {
+    /// Returns features enabled for the device.
+    fn features(&self) -> Features;
+
     /// Create a new shader library.
     fn new_shader_library(
         &self,
@@ -64,6 +67,13 @@ pub trait Device: Clone + Debug + Eq + Send + Sync + 'static {
 
     /// Create a new top-level acceleration structure.
     fn new_tlas(&self, desc: TlasDesc) -> Result<crate::backend::Tlas, OutOfMemory>;
+
+    /// Create a new pool of `count` timestamp queries.
+    fn new_query_pool(&self, count: u32) -> Result<crate::backend::QueryPool, OutOfMemory>;
+
+    /// Returns number of nanoseconds per timestamp tick.
+    /// Returns `None` if timestamps are not supported.
+    fn timestamp_period(&self) -> Option<f32>;
 }
 
 pub trait Queue: Debug + Send + Sync + 'static {
@@ -112,6 +122,14 @@ pub trait CommandEncoder {
     /// Presents the frame to the surface.
     fn present(&mut self, frame: crate::backend::Frame, after: PipelineStages);
 
+    /// Resets queries in the pool.
+    /// Queries must be reset before timestamps are written to them.
+    fn reset_queries(&mut self, pool: &crate::backend::QueryPool, range: Range<u32>);
+
+    /// Writes timestamp into the query when
+    /// all previously recorded commands are finished.
+    fn write_timestamp(&mut self, pool: &crate::backend::QueryPool, index: u32);
+
     /// Finishes encoding and returns the command buffer.
     fn finish(self) -> Result<crate::backend::CommandBuffer, OutOfMemory>;
 
@@ -321,6 +339,18 @@ pub trait Buffer: Clone + Debug + Eq + Hash + Send + Sync + 'static {
     unsafe fn write_unchecked(&mut self, offset: usize, data: &[u8]);
 }
 
+pub trait QueryPool: Clone + Debug + Send + Sync + 'static {
+    /// Returns number of queries in the pool.
+    fn count(&self) -> u32;
+
+    /// Reads timestamps written to the queries.
+    /// Returns `None` if any of the queries is not available yet.
+    ///
+    /// Timestamps are in ticks, use [`Device::timestamp_period`]
+    /// to convert them to nanoseconds.
+    fn timestamps(&self, range: Range<u32>) -> Option<Vec<u64>>;
+}
+
 pub trait Library {
     /// Returns shader entry point.
     fn entry<'a>(&self, entry: &'a str) -> Shader<'a>;
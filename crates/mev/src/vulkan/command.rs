@@ -12,7 +12,7 @@ use crate::generic::{
 use super::{
     access::access_for_stages, format_aspect, from::IntoAsh, handle_host_oom,
     layout::PipelineLayout, refs::Refs, unexpected_error, Blas, Buffer, ComputePipeline, Device,
-    Frame, Image, RenderPipeline, Tlas,
+    Frame, Image, QueryPool, RenderPipeline, Tlas,
 };
 
 pub struct CommandBuffer {
@@ -90,6 +90,32 @@ impl crate::traits::CommandEncoder for CommandEncoder {
         self.present.push(frame);
     }
 
+    #[cfg_attr(inline_more, inline(always))]
+    fn reset_queries(&mut self, pool: &QueryPool, range: Range<u32>) {
+        unsafe {
+            self.device.ash().cmd_reset_query_pool(
+                self.handle,
+                pool.handle(),
+                range.start,
+                range.end - range.start,
+            )
+        }
+        self.refs.add_query_pool(pool.clone());
+    }
+
+    #[cfg_attr(inline_more, inline(always))]
+    fn write_timestamp(&mut self, pool: &QueryPool, index: u32) {
+        unsafe {
+            self.device.ash().cmd_write_timestamp(
+                self.handle,
+                vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                pool.handle(),
+                index,
+            )
+        }
+        self.refs.add_query_pool(pool.clone());
+    }
+
     #[cfg_attr(inline_more, inline(always))]
     fn finish(self) -> Result<CommandBuffer, OutOfMemory> {
         let result = unsafe { self.device.ash().end_command_buffer(self.handle) };
@@ -556,8 +582,17 @@ impl crate::traits::CopyCommandEncoder for CopyCommandEncoder<'_> {
         let texel_size = dst.format().size();
         debug_assert_eq!(bytes_per_line % texel_size, 0);
         debug_assert_eq!(bytes_per_plane % texel_size, 0);
-        let texel_per_line = bytes_per_line / texel_size;
-        let texel_per_plane = bytes_per_plane / texel_size;
+        let mut texel_per_line = bytes_per_line / texel_size;
+        let mut texel_per_plane = bytes_per_plane / texel_size;
+
+        if dst.format().is_compressed() {
+            // Size is per 4x4 block.
+            texel_per_line *= 4;
+            texel_per_plane = match bytes_per_line {
+                0 => 0,
+                _ => bytes_per_plane / bytes_per_line * 4,
+            };
+        }
 
         self.refs.add_buffer(src.clone());
         self.refs.add_image(dst.clone());
@@ -27,11 +27,13 @@ use super::{
     format_aspect,
     from::{IntoAsh, TryIntoAsh},
     handle_host_oom,
-    image::Image,
+    image::{view_type, Image},
     layout::{
         DescriptorSetLayout, DescriptorSetLayoutDesc, PipelineLayout, PipelineLayoutDesc,
         WeakDescriptorSetLayout, WeakPipelineLayout,
     },
+    map_oom,
+    query::QueryPool,
     queue::PendingEpochs,
     render_pipeline::RenderPipeline,
     sampler::WeakSampler,
@@ -506,6 +508,15 @@ impl WeakDevice {
             }
         }
     }
+
+    #[cfg_attr(inline_more, inline(always))]
+    pub fn drop_query_pool(&self, handle: vk::QueryPool) {
+        if let Some(inner) = self.inner.upgrade() {
+            unsafe {
+                inner.device.destroy_query_pool(handle, None);
+            }
+        }
+    }
 }
 
 pub(super) trait DeviceOwned {
@@ -913,6 +924,10 @@ impl Device {
 
 #[hidden_trait::expose]
 impl crate::traits::Device for Device {
+    fn features(&self) -> Features {
+        self.inner.features
+    }
+
     fn new_shader_library(&self, desc: LibraryDesc) -> Result<Library, CreateLibraryError> {
         let me = &*self.inner;
         match desc.input {
@@ -1338,6 +1353,11 @@ impl crate::traits::Device for Device {
         let image = unsafe {
             self.inner.device.create_image(
                 &vk::ImageCreateInfo::default()
+                    .flags(if desc.cube {
+                        vk::ImageCreateFlags::CUBE_COMPATIBLE
+                    } else {
+                        vk::ImageCreateFlags::empty()
+                    })
                     .image_type(desc.dimensions.into_ash())
                     .format(desc.format.try_into_ash().expect("Unsupported format"))
                     .extent(desc.dimensions.into_ash())
@@ -1407,7 +1427,7 @@ impl crate::traits::Device for Device {
 
         let result = self.new_image_view(
             image,
-            desc.dimensions.into_ash(),
+            view_type(desc.dimensions, desc.layers, desc.cube),
             ViewDesc {
                 format: desc.format,
                 base_layer: 0,
@@ -1445,6 +1465,7 @@ impl crate::traits::Device for Device {
             desc.usage,
             desc.layers,
             desc.levels,
+            desc.cube,
             block,
             idx,
         );
@@ -1580,6 +1601,29 @@ impl crate::traits::Device for Device {
     fn new_tlas(&self, desc: TlasDesc) -> Result<Tlas, OutOfMemory> {
         todo!()
     }
+
+    fn new_query_pool(&self, count: u32) -> Result<QueryPool, OutOfMemory> {
+        let result = unsafe {
+            self.inner.device.create_query_pool(
+                &vk::QueryPoolCreateInfo::default()
+                    .query_type(vk::QueryType::TIMESTAMP)
+                    .query_count(count),
+                None,
+            )
+        };
+
+        let handle = result.map_err(map_oom)?;
+        Ok(QueryPool::new(self.weak(), handle, count))
+    }
+
+    #[cfg_attr(inline_more, inline(always))]
+    fn timestamp_period(&self) -> Option<f32> {
+        let limits = &self.inner.properties.limits;
+        if limits.timestamp_compute_and_graphics == vk::FALSE {
+            return None;
+        }
+        Some(limits.timestamp_period)
+    }
 }
 
 fn memory_to_usage_flags(memory: Memory) -> gpu_alloc::UsageFlags {
@@ -159,17 +159,6 @@ impl AshFrom<ImageExtent> for vk::ImageType {
     }
 }
 
-impl AshFrom<ImageExtent> for vk::ImageViewType {
-    #[cfg_attr(inline_more, inline(always))]
-    fn ash_from(value: ImageExtent) -> Self {
-        match value {
-            ImageExtent::D1(_) => vk::ImageViewType::TYPE_1D,
-            ImageExtent::D2(_) => vk::ImageViewType::TYPE_2D,
-            ImageExtent::D3(_) => vk::ImageViewType::TYPE_3D,
-        }
-    }
-}
-
 impl TryAshFrom<PixelFormat> for vk::Format {
     #[cfg_attr(inline_more, inline(always))]
     fn try_ash_from(value: PixelFormat) -> Option<Self> {
@@ -250,6 +239,10 @@ impl TryAshFrom<PixelFormat> for vk::Format {
             PixelFormat::D16UnormS8Uint => vk::Format::D16_UNORM_S8_UINT,
             PixelFormat::D24UnormS8Uint => vk::Format::D24_UNORM_S8_UINT,
             PixelFormat::D32FloatS8Uint => vk::Format::D32_SFLOAT_S8_UINT,
+            PixelFormat::Bc1RgbaUnorm => vk::Format::BC1_RGBA_UNORM_BLOCK,
+            PixelFormat::Bc1RgbaSrgb => vk::Format::BC1_RGBA_SRGB_BLOCK,
+            PixelFormat::Bc3RgbaUnorm => vk::Format::BC3_UNORM_BLOCK,
+            PixelFormat::Bc3RgbaSrgb => vk::Format::BC3_SRGB_BLOCK,
             _ => return None,
         })
     }
@@ -335,6 +328,10 @@ impl TryFromAsh<vk::Format> for PixelFormat {
             vk::Format::D16_UNORM_S8_UINT => PixelFormat::D16UnormS8Uint,
             vk::Format::D24_UNORM_S8_UINT => PixelFormat::D24UnormS8Uint,
             vk::Format::D32_SFLOAT_S8_UINT => PixelFormat::D32FloatS8Uint,
+            vk::Format::BC1_RGBA_UNORM_BLOCK => PixelFormat::Bc1RgbaUnorm,
+            vk::Format::BC1_RGBA_SRGB_BLOCK => PixelFormat::Bc1RgbaSrgb,
+            vk::Format::BC3_UNORM_BLOCK => PixelFormat::Bc3RgbaUnorm,
+            vk::Format::BC3_SRGB_BLOCK => PixelFormat::Bc3RgbaSrgb,
             _ => return None,
         })
     }
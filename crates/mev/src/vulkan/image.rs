@@ -18,7 +18,6 @@ use crate::generic::{
 use super::{
     arguments::ArgumentsField,
     device::{DeviceOwned, WeakDevice},
-    from::IntoAsh,
     refs::Refs,
     Device,
 };
@@ -39,6 +38,7 @@ struct ImageData {
     dimensions: ImageExtent,
     layers: u32,
     levels: u32,
+    cube: bool,
     flavor: Flavor,
     views: Mutex<HashMap<ViewDesc, (vk::ImageView, usize)>>,
 }
@@ -115,6 +115,7 @@ impl Image {
         usage: ImageUsage,
         layers: u32,
         levels: u32,
+        cube: bool,
         flavor: Flavor,
     ) -> Self {
         let dimensions = dimensions.into();
@@ -141,6 +142,7 @@ impl Image {
                     usage,
                     layers,
                     levels,
+                    cube,
                     flavor,
                     views: Mutex::new(views),
                 }),
@@ -162,6 +164,7 @@ impl Image {
         usage: ImageUsage,
         layers: u32,
         levels: u32,
+        cube: bool,
         block: MemoryBlock<(vk::DeviceMemory, usize)>,
         idx: usize,
     ) -> Self {
@@ -175,6 +178,7 @@ impl Image {
             usage,
             layers,
             levels,
+            cube,
             Flavor::Device {
                 block: ManuallyDrop::new(block),
                 idx,
@@ -201,6 +205,7 @@ impl Image {
             usage,
             1,
             1,
+            false,
             Flavor::Swapchain,
         )
     }
@@ -220,8 +225,8 @@ impl Image {
         let view = match self.inner.data.views.lock().entry(desc) {
             Entry::Occupied(entry) => entry.get().0,
             Entry::Vacant(entry) => {
-                let (view, idx) =
-                    device.new_image_view(self.handle, self.inner.dimensions.into_ash(), desc)?;
+                let view_type = view_type(self.inner.dimensions, desc.layers, self.inner.data.cube);
+                let (view, idx) = device.new_image_view(self.handle, view_type, desc)?;
                 entry.insert((view, idx)).0
             }
         };
@@ -260,6 +265,21 @@ impl Image {
     }
 }
 
+/// Returns type of the view with specified number of layers.
+///
+/// Views of cube-compatible images with multiple of 6 layers are cubes.
+pub(super) fn view_type(dimensions: ImageExtent, layers: u32, cube: bool) -> vk::ImageViewType {
+    match dimensions {
+        ImageExtent::D1(_) if layers > 1 => vk::ImageViewType::TYPE_1D_ARRAY,
+        ImageExtent::D1(_) => vk::ImageViewType::TYPE_1D,
+        ImageExtent::D2(_) if cube && layers == 6 => vk::ImageViewType::CUBE,
+        ImageExtent::D2(_) if cube && layers % 6 == 0 => vk::ImageViewType::CUBE_ARRAY,
+        ImageExtent::D2(_) if layers > 1 => vk::ImageViewType::TYPE_2D_ARRAY,
+        ImageExtent::D2(_) => vk::ImageViewType::TYPE_2D,
+        ImageExtent::D3(_) => vk::ImageViewType::TYPE_3D,
+    }
+}
+
 #[hidden_trait::expose]
 impl crate::traits::Image for Image {
     #[cfg_attr(inline_more, inline(always))]
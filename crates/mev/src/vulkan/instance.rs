@@ -347,8 +347,14 @@ impl Instance {
                 continue;
             }
 
+            let texture_compression_bc = features.features.texture_compression_bc != 0;
+
             let mut features = Features::empty();
 
+            if texture_compression_bc {
+                features |= Features::TEXTURE_COMPRESSION_BC;
+            }
+
             if has_surface {
                 if unsafe { find_extension(&extensions, "VK_KHR_swapchain") }.is_some() {
                     features |= Features::SURFACE;
@@ -542,6 +548,10 @@ impl crate::traits::Instance for Instance {
             enabled_extension_names.push(extension_name!("VK_KHR_swapchain"));
         }
 
+        if desc.features.contains(Features::TEXTURE_COMPRESSION_BC) {
+            features.features.texture_compression_bc = 1;
+        }
+
         let mut info = vk::DeviceCreateInfo::default()
             .enabled_extension_names(&enabled_extension_names)
             .queue_create_infos(&queue_create_infos);
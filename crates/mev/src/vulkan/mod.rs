@@ -13,6 +13,7 @@ mod from;
 mod image;
 mod instance;
 mod layout;
+mod query;
 mod queue;
 mod refs;
 mod render_pipeline;
@@ -33,6 +34,7 @@ pub use self::{
     device::Device,
     image::Image,
     instance::Instance,
+    query::QueryPool,
     queue::Queue,
     render_pipeline::RenderPipeline,
     sampler::Sampler,
@@ -0,0 +1,85 @@
+use std::{fmt, ops::Range, sync::Arc};
+
+use ash::vk;
+
+use super::device::{DeviceOwned, WeakDevice};
+
+struct Inner {
+    owner: WeakDevice,
+    handle: vk::QueryPool,
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        self.owner.drop_query_pool(self.handle);
+    }
+}
+
+/// Pool of timestamp queries.
+#[derive(Clone)]
+pub struct QueryPool {
+    handle: vk::QueryPool,
+    count: u32,
+    inner: Arc<Inner>,
+}
+
+impl fmt::Debug for QueryPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "QueryPool({:p}[{}])", self.handle, self.count)
+    }
+}
+
+impl DeviceOwned for QueryPool {
+    #[cfg_attr(inline_more, inline(always))]
+    fn owner(&self) -> &WeakDevice {
+        &self.inner.owner
+    }
+}
+
+impl QueryPool {
+    #[cfg_attr(inline_more, inline(always))]
+    pub(super) fn new(owner: WeakDevice, handle: vk::QueryPool, count: u32) -> Self {
+        QueryPool {
+            handle,
+            count,
+            inner: Arc::new(Inner { owner, handle }),
+        }
+    }
+
+    #[cfg_attr(inline_more, inline(always))]
+    pub(super) fn handle(&self) -> vk::QueryPool {
+        self.handle
+    }
+}
+
+#[hidden_trait::expose]
+impl crate::traits::QueryPool for QueryPool {
+    #[cfg_attr(inline_more, inline(always))]
+    fn count(&self) -> u32 {
+        self.count
+    }
+
+    fn timestamps(&self, range: Range<u32>) -> Option<Vec<u64>> {
+        assert!(range.start <= range.end && range.end <= self.count);
+
+        let device = self.inner.owner.upgrade()?;
+        let mut data = vec![0u64; (range.end - range.start) as usize];
+
+        let result = unsafe {
+            device.ash().get_query_pool_results(
+                self.handle,
+                range.start,
+                &mut data,
+                vk::QueryResultFlags::TYPE_64,
+            )
+        };
+
+        match result {
+            Ok(()) => Some(data),
+            Err(vk::Result::NOT_READY) => None,
+            // Device lost or out of memory.
+            // Timestamps are not critical, so just report absence.
+            Err(_) => None,
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use super::{Buffer, CommandBuffer, ComputePipeline, Image, RenderPipeline, Sampler};
+use super::{Buffer, CommandBuffer, ComputePipeline, Image, QueryPool, RenderPipeline, Sampler};
 
 /// Stores references to vulkan objects
 /// to keep them alive.
@@ -8,6 +8,7 @@ pub struct Refs {
     samplers: Vec<Sampler>,
     render_pipelines: Vec<RenderPipeline>,
     compute_pipelines: Vec<ComputePipeline>,
+    query_pools: Vec<QueryPool>,
     // cbufs: Vec<CommandBuffer>,
     // refs: Vec<Refs>,
 }
@@ -20,6 +21,7 @@ impl Refs {
             samplers: Vec::new(),
             render_pipelines: Vec::new(),
             compute_pipelines: Vec::new(),
+            query_pools: Vec::new(),
             // cbufs: Vec::new(),
             // refs: Vec::new(),
         }
@@ -30,6 +32,7 @@ impl Refs {
         self.images.clear();
         self.samplers.clear();
         self.render_pipelines.clear();
+        self.query_pools.clear();
         // self.cbufs.clear();
         // self.refs.clear();
     }
@@ -66,6 +69,10 @@ impl Refs {
         self.compute_pipelines.push(pipeline);
     }
 
+    pub fn add_query_pool(&mut self, pool: QueryPool) {
+        self.query_pools.push(pool);
+    }
+
     // pub fn add_cbuf(&mut self, cbuf: CommandBuffer) {
     //     self.cbufs.push(cbuf);
     // }
@@ -3,25 +3,33 @@ use arcana::{
     flow::sleep,
     gametime::{timespan, TimeSpan},
     na,
-    render::RenderGraph,
     viewport::Viewport,
     ClockStep,
 };
-use camera::Camera2;
+use camera::{Camera2, MainCamera};
 use cursor::MainCursor;
 use motion::dim2::{Motion, Motor, MoveAfter, MoveTo};
-use physics::dim2::{Collider, ContactForceEvents, FlowEntityExt, PhysicsResource, RigidBody};
+use physics::dim2::{
+    Collider, ContactForceEvents, FlowEntityExt, Group, InteractionGroups, PhysicsFilter,
+    PhysicsLayers, PhysicsResource, RigidBody,
+};
 use scene::dim2::Global;
-use sdf::SdfRender;
 
 #[derive(Component)]
 pub struct BallComponent;
 
+// Colliders use physics layers defined by the plugin.
+// Project may configure layers with the same names to override them.
+// "paddle" - ball that follows the cursor,
+// "ball" - balls that burst on hit,
+// "wall" - walls around the field.
+
 arcana::export_arcana_plugin! {
     ArcanoidPlugin {
         dependencies: [
             scene ...,
             physics ...,
+            camera ...,
             sdf ...,
             input ...,
             motion ...,
@@ -57,26 +65,19 @@ arcana::export_arcana_plugin! {
         ],
 
         in world => {
-            let camera = world
-                .spawn((Global::identity(), Camera2::new().with_fovy(15.0)))
-                .id();
-
             {
-                let world = world.local();
-                let mut graph = world.expect_resource_mut::<RenderGraph>();
-
-                // Create main pass.
-                // It returns target id that it renders to.
-                let target = SdfRender::build(camera, &mut graph);
-
-                // if world.get_resource::<EguiResource>().is_some() {
-                //     target = EguiRender::build_overlay(target, &mut graph, window);
-                // }
-
-                // Use window's surface for the render target.
-                graph.present(target);
+                let mut layers = world.expect_resource_mut::<PhysicsLayers>();
+                layers.add_default_layer("paddle", &["ball", "wall"]);
+                layers.add_default_layer("ball", &["paddle", "ball", "wall"]);
+                layers.add_default_layer("wall", &["paddle", "ball"]);
             }
 
+            // `DrawSdf` job renders through the main camera
+            // unless its camera param is bound in the work graph.
+            let camera = world
+                .spawn((Global::identity(), Camera2::new().with_fovy(15.0), MainCamera))
+                .id();
+
             let target = world.allocate().id();
             let mut last_ball = target;
 
@@ -91,7 +92,7 @@ arcana::export_arcana_plugin! {
                     ]),
                     Global::identity(),
                     RigidBody::dynamic(),
-                    Collider::ball(1.0),
+                    Collider::ball(1.0).layer("paddle"),
                     Motion::to(na::Point2::new(0.0, 0.0)),
                     Motor::new(30.0, 100.0),
                     BallComponent,
@@ -100,10 +101,10 @@ arcana::export_arcana_plugin! {
 
             // insert_global_entity_controller(PaddleTranslator, paddle, world).unwrap();
 
-            let left_side = Collider::halfspace(na::UnitVector2::new_unchecked(na::Vector2::x())).position(na::Translation2::new(-15.0, 0.0).into());
-            let right_side = Collider::halfspace(na::UnitVector2::new_unchecked(-na::Vector2::x())).position(na::Translation2::new(15.0, 0.0).into());
-            let top_side = Collider::halfspace(na::UnitVector2::new_unchecked(-na::Vector2::y())).position(na::Translation2::new(0.0, 15.0).into());
-            let bottom_side = Collider::halfspace(na::UnitVector2::new_unchecked(na::Vector2::y())).position(na::Translation2::new(0.0, -15.0).into());
+            let left_side = Collider::halfspace(na::UnitVector2::new_unchecked(na::Vector2::x())).position(na::Translation2::new(-15.0, 0.0).into()).layer("wall");
+            let right_side = Collider::halfspace(na::UnitVector2::new_unchecked(-na::Vector2::x())).position(na::Translation2::new(15.0, 0.0).into()).layer("wall");
+            let top_side = Collider::halfspace(na::UnitVector2::new_unchecked(-na::Vector2::y())).position(na::Translation2::new(0.0, 15.0).into()).layer("wall");
+            let bottom_side = Collider::halfspace(na::UnitVector2::new_unchecked(na::Vector2::y())).position(na::Translation2::new(0.0, -15.0).into()).layer("wall");
 
 
             world.spawn_one(left_side);
@@ -129,7 +130,7 @@ arcana::export_arcana_plugin! {
                         ]),
                         RigidBody::dynamic().position(global.iso),
                         global,
-                        Collider::ball(1.0).enable_contact_force_events().contact_force_event_threshold(2000.0),
+                        Collider::ball(1.0).layer("ball").enable_contact_force_events().contact_force_event_threshold(2000.0),
                         Motion::After(MoveAfter::new(last_ball).with_distance(2.0)),
                         Motor::new(10.0, 100.0),
                         ContactForceEvents::new(),
@@ -168,7 +169,14 @@ fn burst_system(
     clock: Res<ClockStep>,
     mut encoder: ActionEncoder,
     physics: Res<PhysicsResource>,
+    layers: Res<PhysicsLayers>,
 ) {
+    // Burst pushes only balls.
+    let mut filter = PhysicsFilter::new();
+    if let Some(ball) = layers.group("ball") {
+        filter = filter.groups(InteractionGroups::new(Group::ALL, ball));
+    }
+
     for (e, burst, shape, global) in burst {
         if burst.span == TimeSpan::ZERO {
             let [r, g, b, _] = shape.color;
@@ -182,6 +190,7 @@ fn burst_system(
             physics.intersections_with_shape(
                 &global.iso,
                 &physics::dim2::Ball::new(30.0),
+                &filter,
                 |_collider, body| {
                     if let Some(body) = body {
                         if let Ok((body, body_global)) = bodies.try_get_mut(body) {
@@ -1,11 +1,41 @@
 use arcana::{
-    edict::{self, Component},
-    export_arcana_plugin, na,
+    edict::{self, Component, Entities, EntityId, World},
+    export_arcana_plugin,
+    model::{Model, Value},
+    na,
+    work::component_param,
 };
 
 export_arcana_plugin! {
     CameraPlugin {
-        components: [Camera2],
+        components: [Camera2, MainCamera],
+        param_sources: [
+            main_camera: Model::Entity => component_param(|e, _: &MainCamera| Value::Entity(e)),
+        ],
+    }
+}
+
+/// Marker component for the camera entity
+/// that renders the main view.
+///
+/// Bind job's camera param to `main_camera` source
+/// to render through this camera.
+/// Jobs with unbound camera param use it as well.
+#[derive(Clone, Copy, Component)]
+pub struct MainCamera;
+
+/// Returns camera entity the job renders through.
+///
+/// Camera param bound to an entity takes precedence.
+/// Otherwise entity with [`MainCamera`] component is used.
+pub fn job_camera(param: &Value, world: &World) -> Option<EntityId> {
+    match param {
+        Value::Entity(camera) => Some(*camera),
+        _ => world
+            .view::<(Entities, &MainCamera)>()
+            .into_iter()
+            .next()
+            .map(|(e, _)| e.id()),
     }
 }
 
@@ -8,16 +8,27 @@ use arcana::{
     gametime::TimeStamp,
     input::InputFilter,
     mev::{self, Arguments, DeviceRepr},
-    render::{Render, RenderBuilderContext, RenderContext, RenderError, RenderGraph, TargetId},
+    model::{Model, Value},
+    plugin::PluginsHub,
     texture::Texture,
+    work::{
+        run_viewport, Exec, Image2D, Job, JobDesc, JobId, JobIdx, PinId, Planner, PresentError,
+        WorkGraph,
+    },
     Blink, Component, EntityId, World,
 };
 use egui::epaint::{ClippedShape, Primitive, Vertex};
 
-use hashbrown::{hash_map::Entry, HashMap};
+use hashbrown::{hash_map::Entry, HashMap, HashSet};
 
 mod event;
 
+arcana::export_arcana_plugin! {
+    EguiPlugin {
+        jobs: [DrawEgui, egui_overlay: DrawEgui::overlay_desc() => DrawEgui::overlay()],
+    }
+}
+
 pub use egui::*;
 
 #[derive(Clone, Copy)]
@@ -127,62 +138,119 @@ struct EguiConstants {
     scale: f32,
 }
 
-pub struct EguiRender {
-    id: Option<EntityId>,
-    target: TargetId<mev::Image>,
+/// Vertex and index buffers of a single job instance.
+#[derive(Default)]
+struct EguiBuffers {
+    vertex: Option<mev::Buffer>,
+    index: Option<mev::Buffer>,
+}
+
+/// Draws egui from [`Egui`] component of the entity
+/// or [`Egui`] resource if entity does not have one.
+pub struct DrawEgui {
+    overlay: bool,
     samplers: Option<[mev::Sampler; 4]>,
     library: Option<mev::Library>,
     linear_pipeline: Option<mev::RenderPipeline>,
     srgb_pipeline: Option<mev::RenderPipeline>,
-
-    vertex_buffer: Option<mev::Buffer>,
-    index_buffer: Option<mev::Buffer>,
-    load_op: mev::LoadOp<mev::ClearColor>,
+    buffers: HashMap<JobIdx, EguiBuffers>,
 }
 
-impl EguiRender {
-    fn new(
-        id: Option<EntityId>,
-        target: TargetId<mev::Image>,
-        load_op: mev::LoadOp<mev::ClearColor>,
-    ) -> Self {
-        EguiRender {
-            id,
-            target,
+impl DrawEgui {
+    /// Job that creates new target and draws egui onto it.
+    pub fn desc() -> JobDesc {
+        arcana::job_desc! [
+            egui: in Model::Entity,
+            main: +Image2D,
+        ]
+    }
+
+    /// Job that draws egui over existing target.
+    pub fn overlay_desc() -> JobDesc {
+        arcana::job_desc! [
+            egui: in Model::Entity,
+            main: mut Image2D,
+        ]
+    }
+
+    pub fn new() -> Self {
+        DrawEgui {
+            overlay: false,
             samplers: None,
             library: None,
             linear_pipeline: None,
             srgb_pipeline: None,
-            vertex_buffer: None,
-            index_buffer: None,
-            load_op,
+            buffers: HashMap::new(),
         }
     }
 
-    pub fn build_overlay(
-        id: Option<EntityId>,
-        target: TargetId<mev::Image>,
-        graph: &mut RenderGraph,
-    ) -> TargetId<mev::Image> {
-        let mut builder = RenderBuilderContext::new("egui", graph);
-        let new_target = builder.write_target(target, mev::PipelineStages::COLOR_OUTPUT);
-        builder.build(EguiRender::new(id, new_target, mev::LoadOp::Load));
-        new_target
+    pub fn overlay() -> Self {
+        DrawEgui {
+            overlay: true,
+            ..DrawEgui::new()
+        }
     }
+}
 
-    pub fn build(id: Option<EntityId>, graph: &mut RenderGraph) -> TargetId<mev::Image> {
-        let mut builder = RenderBuilderContext::new("egui", graph);
-        let new_target = builder.create_target("egui-surface", mev::PipelineStages::COLOR_OUTPUT);
-        builder.build(EguiRender::new(id, new_target, mev::LoadOp::DontCare));
-        new_target
+impl Job for DrawEgui {
+    fn plan(&mut self, mut planner: Planner<'_>, _world: &mut World) {
+        if self.overlay {
+            planner.update::<Image2D>();
+        } else {
+            planner.create::<Image2D>();
+        }
+    }
+
+    fn exec(&mut self, runner: Exec<'_>, world: &mut World) {
+        let target = if self.overlay {
+            runner.update::<Image2D>()
+        } else {
+            runner.create::<Image2D>()
+        };
+
+        let Some(target) = target else {
+            return;
+        };
+
+        let id = match runner.param("egui") {
+            Value::Entity(id) => Some(*id),
+            _ => None,
+        };
+
+        let encoder = runner.new_encoder();
+
+        if let Err(err) = self.draw(id, target, runner.idx(), runner.device(), encoder, world) {
+            tracing::error!("Failed to draw egui: {err}");
+        }
     }
 }
 
-impl Render for EguiRender {
-    fn render(&mut self, world: &World, mut cx: RenderContext<'_, '_>) -> Result<(), RenderError> {
+impl DrawEgui {
+    fn draw(
+        &mut self,
+        id: Option<EntityId>,
+        target: &mev::Image,
+        idx: JobIdx,
+        device: &mev::Device,
+        encoder: &mut mev::CommandEncoder,
+        world: &World,
+    ) -> Result<(), mev::OutOfMemory> {
+        let load_op = match self.overlay {
+            true => mev::LoadOp::Load,
+            false => mev::LoadOp::DontCare,
+        };
+
+        // Prefer component of the entity, fall back to resource.
         let mut egui_res;
         let mut egui_view;
-        let egui = match self.id {
+        let egui = match id.and_then(|id| world.try_view_one::<&mut Egui>(id).ok()) {
+            Some(view) => {
+                egui_view = view;
+                match egui_view.get_mut() {
+                    Some(egui) => egui,
+                    None => return Ok(()),
+                }
+            }
             None => {
                 egui_res = match world.get_resource_mut::<Egui>() {
                     Some(egui) => egui,
@@ -190,40 +258,30 @@ impl Render for EguiRender {
                 };
                 &mut *egui_res
             }
-            Some(id) => {
-                egui_view = match world.try_view_one::<&mut Egui>(id) {
-                    Ok(egui) => egui,
-                    Err(_) => return Ok(()),
-                };
-                match egui_view.get_mut() {
-                    Some(egui) => egui,
-                    None => return Ok(()),
-                }
-            }
         };
 
         let samplers = match &mut self.samplers {
             Some(samplers) => &*samplers,
             none => {
-                let sampler_nn = cx.device().new_sampler(mev::SamplerDesc {
+                let sampler_nn = device.new_sampler(mev::SamplerDesc {
                     min_filter: mev::Filter::Nearest,
                     mag_filter: mev::Filter::Nearest,
                     address_mode: [mev::AddressMode::ClampToEdge; 3],
                     ..mev::SamplerDesc::new()
                 })?;
-                let sampler_nl = cx.device().new_sampler(mev::SamplerDesc {
+                let sampler_nl = device.new_sampler(mev::SamplerDesc {
                     min_filter: mev::Filter::Nearest,
                     mag_filter: mev::Filter::Linear,
                     address_mode: [mev::AddressMode::ClampToEdge; 3],
                     ..mev::SamplerDesc::new()
                 })?;
-                let sampler_ln = cx.device().new_sampler(mev::SamplerDesc {
+                let sampler_ln = device.new_sampler(mev::SamplerDesc {
                     min_filter: mev::Filter::Linear,
                     mag_filter: mev::Filter::Nearest,
                     address_mode: [mev::AddressMode::ClampToEdge; 3],
                     ..mev::SamplerDesc::new()
                 })?;
-                let sampler_ll = cx.device().new_sampler(mev::SamplerDesc {
+                let sampler_ll = device.new_sampler(mev::SamplerDesc {
                     min_filter: mev::Filter::Linear,
                     mag_filter: mev::Filter::Linear,
                     address_mode: [mev::AddressMode::ClampToEdge; 3],
@@ -233,9 +291,7 @@ impl Render for EguiRender {
             }
         };
 
-        let mut encoder = cx.new_command_encoder()?;
-
-        let target = cx.write_target(self.target, &mut encoder).clone();
+        let buffers = self.buffers.entry(idx).or_default();
 
         {
             let mut copy_encoder = encoder.copy();
@@ -253,7 +309,7 @@ impl Render for EguiRender {
                     }
                 });
 
-                let mut upload_buffer = cx.device().new_buffer(mev::BufferDesc {
+                let mut upload_buffer = device.new_buffer(mev::BufferDesc {
                     size: delta_size,
                     usage: mev::BufferUsage::TRANSFER_SRC,
                     memory: mev::Memory::Upload,
@@ -296,7 +352,7 @@ impl Render for EguiRender {
 
                     match egui.textures.entry(*id) {
                         Entry::Vacant(entry) => {
-                            let mut new_image = cx.device().new_image(mev::ImageDesc {
+                            let mut new_image = device.new_image(mev::ImageDesc {
                                 dimensions: mev::Extent2::new(size[0] as u32, size[1] as u32)
                                     .into(),
                                 format,
@@ -304,6 +360,7 @@ impl Render for EguiRender {
                                     | mev::ImageUsage::TRANSFER_DST
                                     | mev::ImageUsage::TRANSFER_SRC,
                                 layers: 1,
+                                cube: false,
                                 levels: 1,
                                 name: &format!("egui-texture-{id:?}"),
                             })?;
@@ -316,7 +373,7 @@ impl Render for EguiRender {
 
                             if let ImageData::Font(_) = &delta.image {
                                 new_image = new_image.view(
-                                    cx.device(),
+                                    device,
                                     mev::ViewDesc::new(format).swizzle(mev::Swizzle::RRRR),
                                 )?;
                             }
@@ -333,7 +390,7 @@ impl Render for EguiRender {
                             if (extent.width() as usize) < size[0]
                                 || (extent.height() as usize) < size[1]
                             {
-                                let mut new_image = cx.device().new_image(mev::ImageDesc {
+                                let mut new_image = device.new_image(mev::ImageDesc {
                                     dimensions: mev::Extent2::new(size[0] as u32, size[1] as u32)
                                         .into(),
                                     format,
@@ -341,6 +398,7 @@ impl Render for EguiRender {
                                         | mev::ImageUsage::TRANSFER_DST
                                         | mev::ImageUsage::TRANSFER_SRC,
                                     layers: 1,
+                                    cube: false,
                                     levels: 1,
                                     name: &format!("egui-texture-{id:?}"),
                                 })?;
@@ -353,7 +411,7 @@ impl Render for EguiRender {
 
                                 if let ImageData::Font(_) = &delta.image {
                                     new_image = new_image.view(
-                                        cx.device(),
+                                        device,
                                         mev::ViewDesc::new(format).swizzle(mev::Swizzle::RRRR),
                                     )?;
                                 }
@@ -423,11 +481,11 @@ impl Render for EguiRender {
                         }
                     }
 
-                    let vertex_buffer = match &mut self.vertex_buffer {
+                    let vertex_buffer = match &mut buffers.vertex {
                         Some(buffer) if buffer.size() >= total_vertex_size => buffer,
                         slot => {
                             *slot = None;
-                            slot.get_or_insert(cx.device().new_buffer(mev::BufferDesc {
+                            slot.get_or_insert(device.new_buffer(mev::BufferDesc {
                                 size: total_vertex_size,
                                 usage: mev::BufferUsage::VERTEX | mev::BufferUsage::TRANSFER_DST,
                                 memory: mev::Memory::Device,
@@ -436,11 +494,11 @@ impl Render for EguiRender {
                         }
                     };
 
-                    let index_buffer = match &mut self.index_buffer {
+                    let index_buffer = match &mut buffers.index {
                         Some(buffer) if buffer.size() >= total_index_size => buffer,
                         slot => {
                             *slot = None;
-                            slot.get_or_insert(cx.device().new_buffer(mev::BufferDesc {
+                            slot.get_or_insert(device.new_buffer(mev::BufferDesc {
                                 size: total_index_size,
                                 usage: mev::BufferUsage::INDEX | mev::BufferUsage::TRANSFER_DST,
                                 memory: mev::Memory::Device,
@@ -478,7 +536,7 @@ impl Render for EguiRender {
                     );
 
                     let library = self.library.get_or_insert_with(|| {
-                        cx.device()
+                        device
                             .new_shader_library(mev::LibraryDesc {
                                 name: "egui",
                                 input: mev::include_library!(
@@ -490,7 +548,7 @@ impl Render for EguiRender {
 
                     let pipeline = if target.format().is_srgb() {
                         self.srgb_pipeline.get_or_insert_with(|| {
-                            cx.device()
+                            device
                                 .new_render_pipeline(mev::RenderPipelineDesc {
                                     name: "egui",
                                     vertex_shader: mev::Shader {
@@ -539,7 +597,7 @@ impl Render for EguiRender {
                         })
                     } else {
                         self.linear_pipeline.get_or_insert_with(|| {
-                            cx.device()
+                            device
                                 .new_render_pipeline(mev::RenderPipelineDesc {
                                     name: "egui",
                                     vertex_shader: mev::Shader {
@@ -593,9 +651,7 @@ impl Render for EguiRender {
                     let dims = target.dimensions().expect_2d();
 
                     let mut render = encoder.render(mev::RenderPassDesc {
-                        color_attachments: &[
-                            mev::AttachmentDesc::new(&target).load_op(self.load_op)
-                        ],
+                        color_attachments: &[mev::AttachmentDesc::new(&target).load_op(load_op)],
                         ..Default::default()
                     });
 
@@ -701,12 +757,70 @@ impl Render for EguiRender {
         }
         egui.textures_delta.free.clear();
 
-        cx.commit(encoder.finish()?);
-
         Ok(())
     }
 }
 
+/// Work graph with single [`DrawEgui`] job.
+///
+/// Used by applications that draw nothing but egui into their windows.
+pub struct EguiGraph {
+    workgraph: WorkGraph,
+    hub: PluginsHub,
+
+    /// Entity with egui and viewport.
+    id: Option<EntityId>,
+}
+
+impl EguiGraph {
+    /// Creates graph that draws egui of the entity
+    /// or egui resource if `id` is `None`.
+    pub fn new(id: Option<EntityId>) -> Self {
+        let job_id: JobId = arcana::local_name_hash_id!(DrawEgui);
+        let desc = DrawEgui::desc();
+
+        let mut params = desc.default_params();
+        if let Some(id) = id {
+            params.insert(arcana::name!(egui), Value::Entity(id));
+        }
+
+        let mut jobs = HashMap::new();
+        jobs.insert(JobIdx(0), (job_id, desc, params));
+
+        let workgraph =
+            WorkGraph::new(jobs, HashSet::new()).expect("Single job can't form a cycle");
+
+        let mut hub = PluginsHub::new();
+        hub.add_job(job_id, DrawEgui::new());
+
+        EguiGraph { workgraph, hub, id }
+    }
+
+    /// Draws egui into the viewport and presents it.
+    ///
+    /// Viewport is taken from the same entity as egui,
+    /// or from the viewport resource if `id` is `None`.
+    pub fn render(
+        &mut self,
+        device: &mev::Device,
+        queue: &mut mev::Queue,
+        world: &mut World,
+    ) -> Result<(), PresentError> {
+        run_viewport(
+            &mut self.workgraph,
+            PinId {
+                job: JobIdx(0),
+                pin: 0,
+            },
+            self.id,
+            device,
+            queue,
+            world,
+            &mut self.hub,
+        )
+    }
+}
+
 pub struct EguiFilter;
 
 impl InputFilter for EguiFilter {
@@ -1,34 +1,47 @@
 use std::{
     collections::VecDeque,
+    num::NonZeroUsize,
     sync::atomic::{AtomicU64, Ordering::Relaxed},
     task::{Context, Poll, Waker},
 };
 
 use amity::flip_queue::FlipQueue;
 use arcana::{
-    edict::{self, action::LocalActionEncoder, Component, EntityId, ResMut, State, View},
+    config::{config_field, Config},
+    edict::{self, action::LocalActionEncoder, Component, EntityId, Res, ResMut, State, View},
     flow::FlowEntity,
-    ActionEncoder, Entities, Modified, With, World,
+    hashbrown::HashMap,
+    model::{Model, TypeModel, Value},
+    name, ActionEncoder, ClockStep, Entities, Modified, With, World,
 };
 
 use rapier::{
     dynamics::{
-        CCDSolver, ImpulseJointSet, IntegrationParameters, IslandManager, MultibodyJointSet,
-        RigidBodyBuilder, RigidBodyHandle, RigidBodySet,
+        CCDSolver, FixedJointBuilder, ImpulseJointHandle, ImpulseJointSet, IntegrationParameters,
+        IslandManager, MultibodyJointSet, PrismaticJointBuilder, RevoluteJointBuilder,
+        RigidBodyBuilder, RigidBodyHandle, RigidBodySet, RopeJointBuilder,
     },
     geometry::{
-        BroadPhaseMultiSap, ColliderBuilder, ColliderHandle, ColliderSet, ContactPair, NarrowPhase,
+        BroadPhaseMultiSap, ColliderBuilder, ColliderHandle, ColliderMassProps, ColliderSet,
+        ContactPair, NarrowPhase,
+    },
+    math::{Isometry, Point, Vector, DIM},
+    parry::query::ShapeCastOptions,
+    pipeline::{
+        ContactModificationContext, PhysicsHooks, PhysicsPipeline, QueryFilter, QueryPipeline,
     },
-    math::{Isometry, Point, Vector},
-    pipeline::{PhysicsPipeline, QueryFilter, QueryPipeline},
 };
 
+with_dim3! {
+    use rapier::dynamics::SphericalJointBuilder;
+}
+
 use super::UserData;
 
 pub use rapier::{
-    dynamics::RigidBodyType,
-    geometry::{Ball, Group, InteractionGroups, Shape, SharedShape},
-    pipeline::ActiveEvents,
+    dynamics::{GenericJoint, JointAxis, MotorModel, RigidBodyType},
+    geometry::{Ball, Group, InteractionGroups, Ray, Shape, SharedShape},
+    pipeline::{ActiveEvents, ActiveHooks},
 };
 
 static COUNTER: AtomicU64 = AtomicU64::new(0);
@@ -111,12 +124,83 @@ impl From<ContactForce> for CollisionEvent {
     }
 }
 
+/// Surface and mass properties of the collider.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhysicsMaterial {
+    pub friction: f32,
+    pub restitution: f32,
+    pub density: f32,
+}
+
+impl Default for PhysicsMaterial {
+    fn default() -> Self {
+        PhysicsMaterial {
+            friction: ColliderBuilder::default_friction(),
+            restitution: 0.0,
+            density: ColliderBuilder::default_density(),
+        }
+    }
+}
+
+impl TypeModel for PhysicsMaterial {
+    fn model() -> Model {
+        Model::Record(vec![
+            (name!(friction), Some(Model::Float)),
+            (name!(restitution), Some(Model::Float)),
+            (name!(density), Some(Model::Float)),
+        ])
+    }
+
+    fn model_dyn(&self) -> Model {
+        Self::model()
+    }
+}
+
+impl PhysicsMaterial {
+    /// Returns value that represents the material.
+    pub fn to_value(&self) -> Value {
+        Value::Map(
+            [
+                ("friction".to_owned(), Value::Float(self.friction.into())),
+                (
+                    "restitution".to_owned(),
+                    Value::Float(self.restitution.into()),
+                ),
+                ("density".to_owned(), Value::Float(self.density.into())),
+            ]
+            .into_iter()
+            .collect(),
+        )
+    }
+
+    /// Updates material from the value.
+    /// Fields that are missing or have unexpected kind are left unchanged.
+    pub fn set_value(&mut self, value: &Value) {
+        if let Some(&Value::Float(friction)) = config_field(value, "friction") {
+            self.friction = friction as f32;
+        }
+        if let Some(&Value::Float(restitution)) = config_field(value, "restitution") {
+            self.restitution = restitution as f32;
+        }
+        if let Some(&Value::Float(density)) = config_field(value, "density") {
+            self.density = density as f32;
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Collider {
     builder: ColliderBuilder,
     handle: Option<ColliderHandle>,
     body: Option<EntityId>,
 
+    /// Name of the collision layer.
+    /// Resolved with [`PhysicsLayers`] when collider is inserted.
+    layer: Option<String>,
+
+    /// Local normal of the one-way platform.
+    one_way: Option<Vector<f32>>,
+
     /// Unique ID of the component instance.
     /// Used to detect when component is replaced.
     id: u64,
@@ -133,11 +217,29 @@ impl Component for Collider {
                 let ref mut res = *world.expect_resource_mut::<PhysicsResource>();
                 res.colliders
                     .remove(collider, &mut res.islands, &mut res.bodies, true);
+                res.one_way.remove(&collider);
             });
         }
     }
 }
 
+impl TypeModel for Collider {
+    fn model() -> Model {
+        Model::Record(vec![
+            (name!(sensor), Some(Model::Bool)),
+            (
+                name!(layer),
+                Some(Model::Option(Some(Box::new(Model::String)))),
+            ),
+            (name!(material), Some(PhysicsMaterial::model())),
+        ])
+    }
+
+    fn model_dyn(&self) -> Model {
+        Self::model()
+    }
+}
+
 impl Collider {
     pub fn new(shape: SharedShape) -> Self {
         let id = COUNTER.fetch_add(1, Relaxed);
@@ -146,6 +248,8 @@ impl Collider {
             builder: ColliderBuilder::new(shape),
             handle: None,
             body: None,
+            layer: None,
+            one_way: None,
             id,
         }
     }
@@ -203,27 +307,21 @@ impl Collider {
     pub fn sensor(self, is_sensor: bool) -> Self {
         Collider {
             builder: self.builder.sensor(is_sensor),
-            handle: self.handle,
-            body: self.body,
-            id: self.id,
+            ..self
         }
     }
 
     pub fn position(self, pos: Isometry<f32>) -> Self {
         Collider {
             builder: self.builder.position(pos),
-            handle: self.handle,
-            body: self.body,
-            id: self.id,
+            ..self
         }
     }
 
     pub fn active_events(self, active_events: ActiveEvents) -> Self {
         Collider {
             builder: self.builder.active_events(active_events),
-            handle: self.handle,
-            body: self.body,
-            id: self.id,
+            ..self
         }
     }
 
@@ -231,9 +329,7 @@ impl Collider {
         let active_events = self.builder.active_events | ActiveEvents::COLLISION_EVENTS;
         Collider {
             builder: self.builder.active_events(active_events),
-            handle: self.handle,
-            body: self.body,
-            id: self.id,
+            ..self
         }
     }
 
@@ -241,31 +337,173 @@ impl Collider {
         let active_events = self.builder.active_events | ActiveEvents::CONTACT_FORCE_EVENTS;
         Collider {
             builder: self.builder.active_events(active_events),
-            handle: self.handle,
-            body: self.body,
-            id: self.id,
+            ..self
         }
     }
 
     pub fn contact_force_event_threshold(self, treshold: f32) -> Self {
         Collider {
             builder: self.builder.contact_force_event_threshold(treshold),
-            handle: self.handle,
-            body: self.body,
-            id: self.id,
+            ..self
+        }
+    }
+
+    pub fn friction(self, friction: f32) -> Self {
+        Collider {
+            builder: self.builder.friction(friction),
+            ..self
+        }
+    }
+
+    pub fn restitution(self, restitution: f32) -> Self {
+        Collider {
+            builder: self.builder.restitution(restitution),
+            ..self
+        }
+    }
+
+    pub fn density(self, density: f32) -> Self {
+        Collider {
+            builder: self.builder.density(density),
+            ..self
+        }
+    }
+
+    pub fn material(self, material: PhysicsMaterial) -> Self {
+        self.friction(material.friction)
+            .restitution(material.restitution)
+            .density(material.density)
+    }
+
+    /// Returns material of the collider.
+    /// Density is default if mass is set explicitly.
+    pub fn get_material(&self) -> PhysicsMaterial {
+        let density = match self.builder.mass_properties {
+            ColliderMassProps::Density(density) => density,
+            _ => ColliderBuilder::default_density(),
+        };
+
+        PhysicsMaterial {
+            friction: self.builder.friction,
+            restitution: self.builder.restitution,
+            density,
+        }
+    }
+
+    /// Returns value that represents editable properties of the collider.
+    pub fn to_value(&self) -> Value {
+        let layer = self
+            .layer
+            .as_ref()
+            .map(|layer| Box::new(Value::String(layer.clone())));
+
+        Value::Map(
+            [
+                ("sensor".to_owned(), Value::Bool(self.builder.is_sensor)),
+                ("layer".to_owned(), Value::Option(layer)),
+                ("material".to_owned(), self.get_material().to_value()),
+            ]
+            .into_iter()
+            .collect(),
+        )
+    }
+
+    /// Updates editable properties of the collider from the value.
+    /// Fields that are missing or have unexpected kind are left unchanged.
+    pub fn set_value(&mut self, value: &Value) {
+        if let Some(&Value::Bool(sensor)) = config_field(value, "sensor") {
+            self.builder.is_sensor = sensor;
+        }
+        match config_field(value, "layer") {
+            Some(Value::Option(None)) => self.layer = None,
+            Some(Value::Option(Some(layer))) => {
+                if let Value::String(layer) = &**layer {
+                    self.layer = Some(layer.clone());
+                }
+            }
+            _ => {}
+        }
+        if let Some(material) = config_field(value, "material") {
+            let old_material = self.get_material();
+            let mut new_material = old_material;
+            new_material.set_value(material);
+
+            self.builder.friction = new_material.friction;
+            self.builder.restitution = new_material.restitution;
+
+            // Keep explicit mass unless density is changed.
+            if new_material.density != old_material.density {
+                self.builder.mass_properties = ColliderMassProps::Density(new_material.density);
+            }
+        }
+    }
+
+    pub fn collision_groups(self, groups: InteractionGroups) -> Self {
+        Collider {
+            builder: self.builder.collision_groups(groups),
+            ..self
+        }
+    }
+
+    pub fn solver_groups(self, groups: InteractionGroups) -> Self {
+        Collider {
+            builder: self.builder.solver_groups(groups),
+            ..self
+        }
+    }
+
+    /// Sets collision groups from the named layer configured in [`PhysicsLayers`].
+    /// Overrides groups set with [`Collider::collision_groups`].
+    pub fn layer(self, layer: impl Into<String>) -> Self {
+        Collider {
+            layer: Some(layer.into()),
+            ..self
+        }
+    }
+
+    pub fn active_hooks(self, active_hooks: ActiveHooks) -> Self {
+        Collider {
+            builder: self.builder.active_hooks(active_hooks),
+            ..self
+        }
+    }
+
+    /// Makes collider a one-way platform.
+    /// Contacts are kept only when other collider comes from the side of `normal`
+    /// given in local space of the collider.
+    pub fn one_way(self, normal: Vector<f32>) -> Self {
+        let active_hooks = self.builder.active_hooks | ActiveHooks::MODIFY_SOLVER_CONTACTS;
+        Collider {
+            builder: self.builder.active_hooks(active_hooks),
+            one_way: Some(normal),
+            ..self
         }
     }
 }
 
+/// Collects items ordered by entity id.
+/// Used in determinism mode where archetype order must not affect simulation.
+#[cfg(feature = "determinism")]
+fn by_entity<T>(items: impl IntoIterator<Item = T>, id: impl Fn(&T) -> EntityId) -> Vec<T> {
+    let mut items: Vec<T> = items.into_iter().collect();
+    items.sort_by_key(id);
+    items
+}
+
 /// Initializes newly added or modified colliders.
 fn init_colliders(
     mut res: ResMut<PhysicsResource>,
+    layers: Res<PhysicsLayers>,
     modified_colliders: View<(Entities, Modified<&mut Collider>)>,
     mut bodies: View<&mut RigidBody>,
     mut encoder: ActionEncoder,
 ) {
     let res = &mut *res;
 
+    // Creation order affects simulation.
+    #[cfg(feature = "determinism")]
+    let modified_colliders = by_entity(modified_colliders, |(e, ..)| e.id());
+
     // Update colliders.
     // Set user data and attach to parent body.
     for (e, collider) in modified_colliders {
@@ -294,6 +532,21 @@ fn init_colliders(
                 let mut col = collider.builder.build();
                 col.user_data = UserData::new(e, collider.id).bits();
 
+                if let Some(layer) = &collider.layer {
+                    match layers.groups(layer) {
+                        Some(groups) => col.set_collision_groups(groups),
+                        None => {
+                            // Collider would interact with everything otherwise.
+                            tracing::error!(
+                                "Physics layer '{layer}' is not defined, collider of {:?} is removed",
+                                e.id()
+                            );
+                            encoder.drop::<Collider>(e);
+                            continue;
+                        }
+                    }
+                }
+
                 if let Some(body) = body {
                     let handle = res.colliders.insert_with_parent(
                         col,
@@ -309,11 +562,45 @@ fn init_colliders(
                     let handle = res.colliders.insert(col);
                     collider.handle = Some(handle);
                 }
+
+                if let Some(normal) = collider.one_way {
+                    res.one_way.insert(collider.handle.unwrap(), normal);
+                }
             }
             Some(col) => {
+                let parent = col.parent();
+                let handle = collider.handle.unwrap();
+
+                let groups = match &collider.layer {
+                    None => collider.builder.collision_groups,
+                    Some(layer) => match layers.groups(layer) {
+                        Some(groups) => groups,
+                        None => {
+                            tracing::error!(
+                                "Physics layer '{layer}' is not defined, collider of {:?} is removed",
+                                e.id()
+                            );
+                            encoder.drop::<Collider>(e);
+                            continue;
+                        }
+                    },
+                };
+
+                // Apply properties that may be edited after insertion.
+                let col = res.colliders.get_mut(handle).unwrap();
+                col.set_sensor(collider.builder.is_sensor);
+                col.set_friction(collider.builder.friction);
+                col.set_restitution(collider.builder.restitution);
+                col.set_collision_groups(groups);
+                match &collider.builder.mass_properties {
+                    ColliderMassProps::Density(density) => col.set_density(*density),
+                    ColliderMassProps::Mass(mass) => col.set_mass(*mass),
+                    ColliderMassProps::MassProperties(props) => col.set_mass_properties(**props),
+                }
+
                 match body {
                     None => {
-                        if let Some(old_parent) = col.parent() {
+                        if let Some(old_parent) = parent {
                             res.colliders.set_parent(
                                 collider.handle.unwrap(),
                                 None,
@@ -331,16 +618,18 @@ fn init_colliders(
                         }
                     }
                     Some(body) => {
-                        if col.parent() != Some(body.handle.unwrap()) {
+                        if parent != Some(body.handle.unwrap()) {
                             res.colliders.set_parent(
                                 collider.handle.unwrap(),
                                 Some(body.handle.unwrap()),
                                 &mut res.bodies,
                             );
-                            if let Some(rb) = res.bodies.get_mut(body.handle.unwrap()) {
-                                rb.recompute_mass_properties_from_colliders(&res.colliders);
-                                body.mass = rb.mass();
-                            }
+                        }
+
+                        // Density of the collider may have changed.
+                        if let Some(rb) = res.bodies.get_mut(body.handle.unwrap()) {
+                            rb.recompute_mass_properties_from_colliders(&res.colliders);
+                            body.mass = rb.mass();
                         }
                     }
                 };
@@ -387,6 +676,13 @@ impl Component for RigidBody {
 
                 let ref mut res = *world.expect_resource_mut::<PhysicsResource>();
 
+                // Joints are removed with the body.
+                for (_, _, _, joint) in res.impulse_joints.attached_joints(body) {
+                    if let Some(e) = UserData::from_bits(joint.data.user_data).entity {
+                        world.drop_defer::<Joint>(e);
+                    }
+                }
+
                 if let Some(rb) = res.bodies.get(body) {
                     for &c in rb.colliders() {
                         if let Some(col) = res.colliders.get(c) {
@@ -505,6 +801,10 @@ fn init_bodies(
     )>,
     mut encoder: ActionEncoder,
 ) {
+    // Creation order affects simulation.
+    #[cfg(feature = "determinism")]
+    let modified_bodies = by_entity(modified_bodies, |(e, ..)| e.id());
+
     // Update bodies.
     // Set user data and kinematic state.
     // This is cold path as it only touches bodies that were modified (including newly inserted).
@@ -553,6 +853,298 @@ fn init_bodies(
     }
 }
 
+/// Component that represents a joint between two bodies.
+/// Both entities must have [`RigidBody`] component.
+/// Joint component may be attached to any entity, including one of the bodies.
+///
+/// Adding it to the entity will cause joint to appear in physical world.
+/// Removing it will remove joint from the world.
+#[derive(Clone, Debug)]
+pub struct Joint {
+    data: GenericJoint,
+    body1: EntityId,
+    body2: EntityId,
+
+    /// Force that breaks the joint.
+    break_force: Option<f32>,
+
+    /// Handle to the joint in the physics world.
+    handle: Option<ImpulseJointHandle>,
+
+    /// Unique ID of the component instance.
+    /// Used to detect when component is replaced.
+    id: u64,
+}
+
+impl Component for Joint {
+    fn name() -> &'static str {
+        "Joint"
+    }
+
+    fn on_drop(&mut self, _entity: EntityId, mut encoder: LocalActionEncoder) {
+        if let Some(joint) = self.handle {
+            encoder.closure(move |world: &mut World| {
+                let ref mut res = *world.expect_resource_mut::<PhysicsResource>();
+                res.impulse_joints.remove(joint, true);
+            });
+        }
+    }
+}
+
+impl Joint {
+    pub fn new(body1: EntityId, body2: EntityId, data: impl Into<GenericJoint>) -> Self {
+        let id = COUNTER.fetch_add(1, Relaxed);
+
+        Joint {
+            data: data.into(),
+            body1,
+            body2,
+            break_force: None,
+            handle: None,
+            id,
+        }
+    }
+
+    /// Joint that locks all relative motion of the bodies.
+    pub fn fixed(body1: EntityId, body2: EntityId) -> Self {
+        Joint::new(body1, body2, FixedJointBuilder::new())
+    }
+
+    with_dim2! {
+        /// Joint that allows only relative rotation of the bodies.
+        pub fn revolute(body1: EntityId, body2: EntityId) -> Self {
+            Joint::new(body1, body2, RevoluteJointBuilder::new())
+        }
+    }
+
+    with_dim3! {
+        /// Joint that allows only relative rotation of the bodies around the axis.
+        pub fn revolute(body1: EntityId, body2: EntityId, axis: na::Unit<Vector<f32>>) -> Self {
+            Joint::new(body1, body2, RevoluteJointBuilder::new(axis))
+        }
+
+        /// Joint that allows only relative rotation of the bodies around the anchor.
+        pub fn spherical(body1: EntityId, body2: EntityId) -> Self {
+            Joint::new(body1, body2, SphericalJointBuilder::new())
+        }
+    }
+
+    /// Joint that allows only relative translation of the bodies along the axis.
+    pub fn prismatic(body1: EntityId, body2: EntityId, axis: na::Unit<Vector<f32>>) -> Self {
+        Joint::new(body1, body2, PrismaticJointBuilder::new(axis))
+    }
+
+    /// Joint that limits distance between anchors of the bodies.
+    pub fn rope(body1: EntityId, body2: EntityId, max_dist: f32) -> Self {
+        Joint::new(body1, body2, RopeJointBuilder::new(max_dist))
+    }
+
+    pub fn local_anchor1(mut self, anchor: Point<f32>) -> Self {
+        self.data.set_local_anchor1(anchor);
+        self
+    }
+
+    pub fn local_anchor2(mut self, anchor: Point<f32>) -> Self {
+        self.data.set_local_anchor2(anchor);
+        self
+    }
+
+    /// Enables or disables contacts between jointed bodies.
+    pub fn contacts_enabled(mut self, enabled: bool) -> Self {
+        self.data.set_contacts_enabled(enabled);
+        self
+    }
+
+    /// Limits relative motion along the axis.
+    pub fn limits(mut self, axis: JointAxis, limits: [f32; 2]) -> Self {
+        self.data.set_limits(axis, limits);
+        self
+    }
+
+    /// Joint is removed when force applied by it exceeds the limit.
+    /// [`JointBroken`] event is sent to [`JointEvents`] of the joint and both bodies.
+    pub fn break_force(mut self, force: f32) -> Self {
+        self.break_force = Some(force);
+        self
+    }
+
+    pub fn motor_velocity(mut self, axis: JointAxis, target_vel: f32, factor: f32) -> Self {
+        self.set_motor_velocity(axis, target_vel, factor);
+        self
+    }
+
+    pub fn motor_position(
+        mut self,
+        axis: JointAxis,
+        target_pos: f32,
+        stiffness: f32,
+        damping: f32,
+    ) -> Self {
+        self.set_motor_position(axis, target_pos, stiffness, damping);
+        self
+    }
+
+    pub fn motor_max_force(mut self, axis: JointAxis, max_force: f32) -> Self {
+        self.set_motor_max_force(axis, max_force);
+        self
+    }
+
+    pub fn motor_model(mut self, axis: JointAxis, model: MotorModel) -> Self {
+        self.data.set_motor_model(axis, model);
+        self
+    }
+
+    pub fn body1(&self) -> EntityId {
+        self.body1
+    }
+
+    pub fn body2(&self) -> EntityId {
+        self.body2
+    }
+
+    /// Sets target velocity of the motor.
+    /// Change is applied to the physics world on next step.
+    pub fn set_motor_velocity(&mut self, axis: JointAxis, target_vel: f32, factor: f32) {
+        self.data.set_motor_velocity(axis, target_vel, factor);
+    }
+
+    /// Sets target position of the motor.
+    /// Change is applied to the physics world on next step.
+    pub fn set_motor_position(
+        &mut self,
+        axis: JointAxis,
+        target_pos: f32,
+        stiffness: f32,
+        damping: f32,
+    ) {
+        self.data
+            .set_motor_position(axis, target_pos, stiffness, damping);
+    }
+
+    /// Sets maximum force the motor can apply.
+    /// Change is applied to the physics world on next step.
+    pub fn set_motor_max_force(&mut self, axis: JointAxis, max_force: f32) {
+        self.data.set_motor_max_force(axis, max_force);
+    }
+}
+
+/// Marks joints waiting for their bodies as modified
+/// so that [`init_joints`] retries them.
+fn retry_joints(mut res: ResMut<PhysicsResource>, mut joints: View<&mut Joint>) {
+    for e in res.pending_joints.drain(..) {
+        // Mutable access marks the component as modified.
+        let _ = joints.try_get_mut(e);
+    }
+}
+
+/// Initializes newly added or modified joints.
+///
+/// Joints with bodies that are not initialized yet are retried on next step.
+/// Joints with despawned bodies are removed.
+fn init_joints(
+    mut res: ResMut<PhysicsResource>,
+    modified_joints: View<(Entities, Modified<&mut Joint>)>,
+    bodies: View<Option<&RigidBody>>,
+    mut encoder: ActionEncoder,
+) {
+    let res = &mut *res;
+
+    // Creation order affects simulation.
+    #[cfg(feature = "determinism")]
+    let modified_joints = by_entity(modified_joints, |(e, ..)| e.id());
+
+    // Returns `None` if body entity is despawned.
+    let body_handle = |body: EntityId| match bodies.try_get(body) {
+        Err(_) => None,
+        Ok(body) => Some(body.and_then(|b| b.handle)),
+    };
+
+    for (e, joint) in modified_joints {
+        let (body1, body2) = match (body_handle(joint.body1), body_handle(joint.body2)) {
+            (Some(Some(body1)), Some(Some(body2))) => (body1, body2),
+            (None, _) | (_, None) => {
+                tracing::warn!("Body of joint {:?} is despawned, removing joint", e.id());
+                encoder.drop::<Joint>(e);
+                continue;
+            }
+            _ => {
+                // Body is not initialized yet.
+                res.pending_joints.push(e.id());
+                continue;
+            }
+        };
+
+        let mut data = joint.data.clone();
+        data.user_data = UserData::new(e, joint.id).bits();
+
+        match joint
+            .handle
+            .filter(|&handle| match res.impulse_joints.get(handle) {
+                None => false,
+                Some(j) => UserData::from_bits(j.data.user_data).id == joint.id,
+            }) {
+            None => {
+                // No handle or outdated.
+                let handle = res.impulse_joints.insert(body1, body2, data, true);
+                joint.handle = Some(handle);
+            }
+            Some(handle) => {
+                let j = res.impulse_joints.get_mut(handle).unwrap();
+
+                if j.body1 != body1 || j.body2 != body2 {
+                    res.impulse_joints.remove(handle, true);
+                    let handle = res.impulse_joints.insert(body1, body2, data, true);
+                    joint.handle = Some(handle);
+                } else {
+                    j.data = data;
+                }
+            }
+        }
+    }
+}
+
+/// Removes joints that applied force above their limit.
+fn break_joints(
+    mut res: ResMut<PhysicsResource>,
+    joints: View<(Entities, &Joint)>,
+    mut joint_events: View<&mut JointEvents>,
+    mut encoder: ActionEncoder,
+) {
+    let res = &mut *res;
+    let dt = res.parameters.dt;
+
+    for (e, joint) in joints {
+        let (Some(break_force), Some(handle)) = (joint.break_force, joint.handle) else {
+            continue;
+        };
+
+        let Some(j) = res.impulse_joints.get(handle) else {
+            continue;
+        };
+
+        let force = j.impulses.fixed_rows::<DIM>(0).norm() / dt;
+        if force <= break_force {
+            continue;
+        }
+
+        res.impulse_joints.remove(handle, true);
+        encoder.drop::<Joint>(e);
+
+        let event = JointBroken {
+            joint: e.id(),
+            body1: joint.body1,
+            body2: joint.body2,
+            force,
+        };
+
+        for entity in [e.id(), joint.body1, joint.body2] {
+            if let Ok(events) = joint_events.try_get_mut(entity) {
+                events.enque(event);
+            }
+        }
+    }
+}
+
 #[derive(Debug, Component)]
 #[edict(name = "CollisionEvents")]
 pub struct CollisionEvents {
@@ -643,29 +1235,150 @@ impl ContactForceEvents {
     }
 }
 
-#[allow(async_fn_in_trait)]
-pub trait FlowEntityExt {
-    async fn next_collision_event(&mut self) -> CollisionEvent;
+/// Payload of the joint broken event.
+#[derive(Clone, Copy, Debug)]
+pub struct JointBroken {
+    /// Entity with the joint component.
+    pub joint: EntityId,
 
-    async fn next_contact_force_event(&mut self) -> ContactForce;
+    /// First body of the joint.
+    pub body1: EntityId,
+
+    /// Second body of the joint.
+    pub body2: EntityId,
+
+    /// Force that broke the joint.
+    pub force: f32,
 }
 
-impl FlowEntityExt for FlowEntity<'_> {
-    #[cfg_attr(inline_more, inline(always))]
-    async fn next_collision_event(&mut self) -> CollisionEvent {
-        self.poll_view_mut::<&mut CollisionEvents, _, _>(|events, cx| events.poll_deque(cx))
-            .await
-    }
+#[derive(Debug, Component)]
+#[edict(name = "JointEvents")]
+pub struct JointEvents {
+    queue: VecDeque<JointBroken>,
+    waker: Option<Waker>,
+}
 
-    #[cfg_attr(inline_more, inline(always))]
-    async fn next_contact_force_event(&mut self) -> ContactForce {
-        self.poll_view_mut::<&mut ContactForceEvents, _, _>(|events, cx| events.poll_deque(cx))
-            .await
+impl Drop for JointEvents {
+    fn drop(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
     }
 }
 
-pub struct PhysicsResource {
-    pipeline: PhysicsPipeline,
+impl JointEvents {
+    pub fn new() -> Self {
+        JointEvents {
+            queue: VecDeque::new(),
+            waker: None,
+        }
+    }
+
+    pub fn enque(&mut self, event: JointBroken) {
+        self.queue.push_back(event);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    pub fn deque(&mut self) -> Option<JointBroken> {
+        self.queue.pop_front()
+    }
+
+    #[cfg_attr(inline_more, inline)]
+    pub fn poll_deque(&mut self, cx: &mut Context) -> Poll<JointBroken> {
+        if let Some(event) = self.queue.pop_front() {
+            Poll::Ready(event)
+        } else {
+            self.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+#[allow(async_fn_in_trait)]
+pub trait FlowEntityExt {
+    async fn next_collision_event(&mut self) -> CollisionEvent;
+
+    async fn next_contact_force_event(&mut self) -> ContactForce;
+
+    async fn next_joint_broken(&mut self) -> JointBroken;
+
+    /// Casts a ray ignoring colliders of this entity.
+    async fn cast_ray(&mut self, ray: &Ray, max_toi: f32, filter: PhysicsFilter) -> Option<RayHit>;
+
+    /// Casts a shape ignoring colliders of this entity.
+    async fn cast_shape(
+        &mut self,
+        pos: &Isometry<f32>,
+        vel: &Vector<f32>,
+        shape: &dyn Shape,
+        max_toi: f32,
+        filter: PhysicsFilter,
+    ) -> Option<ShapeHit>;
+
+    /// Projects a point ignoring colliders of this entity.
+    async fn project_point(
+        &mut self,
+        point: &Point<f32>,
+        filter: PhysicsFilter,
+    ) -> Option<PointHit>;
+}
+
+impl FlowEntityExt for FlowEntity<'_> {
+    #[cfg_attr(inline_more, inline(always))]
+    async fn next_collision_event(&mut self) -> CollisionEvent {
+        self.poll_view_mut::<&mut CollisionEvents, _, _>(|events, cx| events.poll_deque(cx))
+            .await
+    }
+
+    #[cfg_attr(inline_more, inline(always))]
+    async fn next_contact_force_event(&mut self) -> ContactForce {
+        self.poll_view_mut::<&mut ContactForceEvents, _, _>(|events, cx| events.poll_deque(cx))
+            .await
+    }
+
+    #[cfg_attr(inline_more, inline(always))]
+    async fn next_joint_broken(&mut self) -> JointBroken {
+        self.poll_view_mut::<&mut JointEvents, _, _>(|events, cx| events.poll_deque(cx))
+            .await
+    }
+
+    async fn cast_ray(&mut self, ray: &Ray, max_toi: f32, filter: PhysicsFilter) -> Option<RayHit> {
+        let filter = filter.exclude(self.id());
+        self.world()
+            .expect_resource::<PhysicsResource>()
+            .cast_ray_and_get_normal(ray, max_toi, true, &filter)
+    }
+
+    async fn cast_shape(
+        &mut self,
+        pos: &Isometry<f32>,
+        vel: &Vector<f32>,
+        shape: &dyn Shape,
+        max_toi: f32,
+        filter: PhysicsFilter,
+    ) -> Option<ShapeHit> {
+        let filter = filter.exclude(self.id());
+        self.world()
+            .expect_resource::<PhysicsResource>()
+            .cast_shape(pos, vel, shape, max_toi, &filter)
+    }
+
+    async fn project_point(
+        &mut self,
+        point: &Point<f32>,
+        filter: PhysicsFilter,
+    ) -> Option<PointHit> {
+        let filter = filter.exclude(self.id());
+        self.world()
+            .expect_resource::<PhysicsResource>()
+            .project_point(point, true, &filter)
+    }
+}
+
+pub struct PhysicsResource {
+    pipeline: PhysicsPipeline,
     parameters: IntegrationParameters,
     islands: IslandManager,
     broad_phase: BroadPhaseMultiSap,
@@ -676,6 +1389,12 @@ pub struct PhysicsResource {
     multibody_joints: MultibodyJointSet,
     ccd_solver: CCDSolver,
     query_pipeline: QueryPipeline,
+
+    /// Normals of one-way platform colliders.
+    one_way: HashMap<ColliderHandle, Vector<f32>>,
+
+    /// Joints waiting for their bodies to be initialized.
+    pending_joints: Vec<EntityId>,
 }
 
 impl PhysicsResource {
@@ -692,6 +1411,8 @@ impl PhysicsResource {
             multibody_joints: MultibodyJointSet::new(),
             ccd_solver: CCDSolver::new(),
             query_pipeline: QueryPipeline::new(),
+            one_way: HashMap::new(),
+            pending_joints: Vec::new(),
         }
     }
 
@@ -699,29 +1420,760 @@ impl PhysicsResource {
         &self,
         pos: &Isometry<f32>,
         shape: &impl Shape,
+        filter: &PhysicsFilter,
         mut f: impl FnMut(EntityId, Option<EntityId>),
     ) {
-        self.query_pipeline.intersections_with_shape(
-            &self.bodies,
-            &self.colliders,
-            pos,
-            shape,
-            QueryFilter::default(),
-            |collider| {
-                if let Some(col) = self.colliders.get(collider) {
-                    if let Some(collider) = UserData::from_bits(col.user_data).entity {
-                        let body = col
-                            .parent()
-                            .and_then(|b| self.bodies.get(b))
-                            .and_then(|b| UserData::from_bits(b.user_data).entity);
+        self.with_filter(filter, |filter| {
+            self.query_pipeline.intersections_with_shape(
+                &self.bodies,
+                &self.colliders,
+                pos,
+                shape,
+                filter,
+                |collider| {
+                    if let Some((collider, body)) = self.collider_entities(collider) {
+                        f(collider, body);
+                    }
+                    true
+                },
+            )
+        })
+    }
 
+    /// Calls `f` for each collider that contains the point.
+    pub fn intersections_with_point(
+        &self,
+        point: &Point<f32>,
+        filter: &PhysicsFilter,
+        mut f: impl FnMut(EntityId, Option<EntityId>),
+    ) {
+        self.with_filter(filter, |filter| {
+            self.query_pipeline.intersections_with_point(
+                &self.bodies,
+                &self.colliders,
+                point,
+                filter,
+                |collider| {
+                    if let Some((collider, body)) = self.collider_entities(collider) {
                         f(collider, body);
                     }
-                }
-                true
-            },
+                    true
+                },
+            )
+        })
+    }
+
+    /// Finds closest collider hit by the ray.
+    ///
+    /// If `solid` is true, rays starting inside a collider hit it at the origin.
+    pub fn cast_ray(
+        &self,
+        ray: &Ray,
+        max_toi: f32,
+        solid: bool,
+        filter: &PhysicsFilter,
+    ) -> Option<RayHit> {
+        let (collider, toi) = self.with_filter(filter, |filter| {
+            self.query_pipeline
+                .cast_ray(&self.bodies, &self.colliders, ray, max_toi, solid, filter)
+        })?;
+
+        let (collider, body) = self.collider_entities(collider)?;
+        Some(RayHit {
+            collider,
+            body,
+            toi,
+            normal: None,
+        })
+    }
+
+    /// Finds closest collider hit by the ray and computes normal at the hit point.
+    pub fn cast_ray_and_get_normal(
+        &self,
+        ray: &Ray,
+        max_toi: f32,
+        solid: bool,
+        filter: &PhysicsFilter,
+    ) -> Option<RayHit> {
+        let (collider, intersection) = self.with_filter(filter, |filter| {
+            self.query_pipeline.cast_ray_and_get_normal(
+                &self.bodies,
+                &self.colliders,
+                ray,
+                max_toi,
+                solid,
+                filter,
+            )
+        })?;
+
+        let (collider, body) = self.collider_entities(collider)?;
+        Some(RayHit {
+            collider,
+            body,
+            toi: intersection.time_of_impact,
+            normal: Some(intersection.normal),
+        })
+    }
+
+    /// Finds first collider hit by the shape moving with velocity `vel`.
+    pub fn cast_shape(
+        &self,
+        pos: &Isometry<f32>,
+        vel: &Vector<f32>,
+        shape: &dyn Shape,
+        max_toi: f32,
+        filter: &PhysicsFilter,
+    ) -> Option<ShapeHit> {
+        let (collider, hit) = self.with_filter(filter, |filter| {
+            self.query_pipeline.cast_shape(
+                &self.bodies,
+                &self.colliders,
+                pos,
+                vel,
+                shape,
+                ShapeCastOptions::with_max_time_of_impact(max_toi),
+                filter,
+            )
+        })?;
+
+        // Witness and normal are in collider's local space.
+        let collider_pos = *self.colliders.get(collider)?.position();
+
+        let (collider, body) = self.collider_entities(collider)?;
+        Some(ShapeHit {
+            collider,
+            body,
+            toi: hit.time_of_impact,
+            point: collider_pos * hit.witness1,
+            normal: collider_pos.rotation * hit.normal1.into_inner(),
+        })
+    }
+
+    /// Finds collider closest to the point.
+    ///
+    /// If `solid` is true, point inside a collider is projected onto itself.
+    pub fn project_point(
+        &self,
+        point: &Point<f32>,
+        solid: bool,
+        filter: &PhysicsFilter,
+    ) -> Option<PointHit> {
+        let (collider, projection) = self.with_filter(filter, |filter| {
+            self.query_pipeline
+                .project_point(&self.bodies, &self.colliders, point, solid, filter)
+        })?;
+
+        let (collider, body) = self.collider_entities(collider)?;
+        Some(PointHit {
+            collider,
+            body,
+            point: projection.point,
+            is_inside: projection.is_inside,
+        })
+    }
+
+    /// Returns collider entity and its body entity if any.
+    fn collider_entities(&self, collider: ColliderHandle) -> Option<(EntityId, Option<EntityId>)> {
+        let col = self.colliders.get(collider)?;
+        let collider = UserData::from_bits(col.user_data).entity?;
+
+        let body = col
+            .parent()
+            .and_then(|b| self.bodies.get(b))
+            .and_then(|b| UserData::from_bits(b.user_data).entity);
+
+        Some((collider, body))
+    }
+
+    /// Calls `f` with rapier filter built from the physics filter.
+    fn with_filter<R>(&self, filter: &PhysicsFilter, f: impl FnOnce(QueryFilter) -> R) -> R {
+        let predicate = |collider: ColliderHandle, _: &rapier::geometry::Collider| match self
+            .collider_entities(collider)
+        {
+            None => true,
+            Some((collider, body)) => {
+                !filter.exclude.contains(&collider)
+                    && body.map_or(true, |body| !filter.exclude.contains(&body))
+            }
+        };
+
+        let mut query_filter = QueryFilter::new();
+        if let Some(groups) = filter.groups {
+            query_filter = query_filter.groups(groups);
+        }
+        if filter.exclude_sensors {
+            query_filter = query_filter.exclude_sensors();
+        }
+        if !filter.exclude.is_empty() {
+            query_filter = query_filter.predicate(&predicate);
+        }
+
+        f(query_filter)
+    }
+}
+
+/// Snapshot of the physics world state.
+///
+/// Restore it with [`restore_physics`] to roll simulation back.
+#[derive(Clone)]
+pub struct PhysicsSnapshot {
+    data: Vec<u8>,
+}
+
+impl PhysicsSnapshot {
+    /// Returns serialized state.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Wraps serialized state, e.g. received over network.
+    pub fn from_bytes(data: Vec<u8>) -> Self {
+        PhysicsSnapshot { data }
+    }
+}
+
+/// State of the physics world that is saved in snapshot.
+type SnapshotState = (
+    IntegrationParameters,
+    IslandManager,
+    BroadPhaseMultiSap,
+    NarrowPhase,
+    RigidBodySet,
+    ColliderSet,
+    ImpulseJointSet,
+    MultibodyJointSet,
+    HashMap<ColliderHandle, Vector<f32>>,
+);
+
+impl PhysicsResource {
+    /// Saves physics world state.
+    pub fn snapshot(&self) -> PhysicsSnapshot {
+        let data = bincode::serialize(&(
+            &self.parameters,
+            &self.islands,
+            &self.broad_phase,
+            &self.narrow_phase,
+            &self.bodies,
+            &self.colliders,
+            &self.impulse_joints,
+            &self.multibody_joints,
+            &self.one_way,
+        ))
+        .expect("Physics state must be serializable");
+
+        PhysicsSnapshot { data }
+    }
+
+    /// Restores physics world state from the snapshot.
+    ///
+    /// Components keep handles they had before.
+    /// Use [`restore_physics`] to update them and [`Global`] of the bodies.
+    pub fn restore(&mut self, snapshot: &PhysicsSnapshot) -> bincode::Result<()> {
+        let (
+            parameters,
+            islands,
+            broad_phase,
+            narrow_phase,
+            bodies,
+            colliders,
+            impulse_joints,
+            multibody_joints,
+            one_way,
+        ): SnapshotState = bincode::deserialize(&snapshot.data)?;
+
+        self.parameters = parameters;
+        self.islands = islands;
+        self.broad_phase = broad_phase;
+        self.narrow_phase = narrow_phase;
+        self.bodies = bodies;
+        self.colliders = colliders;
+        self.impulse_joints = impulse_joints;
+        self.multibody_joints = multibody_joints;
+        self.one_way = one_way;
+
+        self.ccd_solver = CCDSolver::new();
+        self.query_pipeline = QueryPipeline::new();
+        self.query_pipeline.update(&self.bodies, &self.colliders);
+
+        Ok(())
+    }
+}
+
+/// Restores physics world state from the snapshot
+/// and points components to restored bodies, colliders and joints.
+///
+/// Objects of entities that lost their components since snapshot are removed.
+/// Components that were added since snapshot are inserted on next physics step.
+/// Positions of restored bodies are written to their [`Global`].
+pub fn restore_physics(world: &mut World, snapshot: &PhysicsSnapshot) -> bincode::Result<()> {
+    let mut bodies = HashMap::new();
+    let mut colliders = HashMap::new();
+    let mut joints = HashMap::new();
+
+    {
+        let ref mut res = *world.expect_resource_mut::<PhysicsResource>();
+        res.restore(snapshot)?;
+
+        for (handle, rb) in res.bodies.iter() {
+            if let Some(entity) = UserData::from_bits(rb.user_data).entity {
+                bodies.insert(entity, (handle, *rb.position()));
+            }
+        }
+        for (handle, col) in res.colliders.iter() {
+            if let Some(entity) = UserData::from_bits(col.user_data).entity {
+                colliders.insert(entity, handle);
+            }
+        }
+        for (handle, joint) in res.impulse_joints.iter() {
+            if let Some(entity) = UserData::from_bits(joint.data.user_data).entity {
+                joints.insert(entity, handle);
+            }
+        }
+    }
+
+    // Restored objects get ids of current components.
+    let mut body_ids = Vec::new();
+    let mut collider_ids = Vec::new();
+    let mut joint_ids = Vec::new();
+
+    for (e, global) in world.view_mut::<(Entities, &mut Global)>() {
+        if let Some(&(_, pos)) = bodies.get(&e.id()) {
+            global.iso = pos;
+        }
+    }
+    for (e, body) in world.view_mut::<(Entities, &mut RigidBody)>() {
+        body.handle = bodies.remove(&e.id()).map(|(handle, _)| handle);
+        if let Some(handle) = body.handle {
+            body_ids.push((handle, UserData::new(e, body.id).bits()));
+        }
+    }
+    for (e, collider) in world.view_mut::<(Entities, &mut Collider)>() {
+        collider.handle = colliders.remove(&e.id());
+        if let Some(handle) = collider.handle {
+            collider_ids.push((handle, UserData::new(e, collider.id).bits()));
+        }
+    }
+    for (e, joint) in world.view_mut::<(Entities, &mut Joint)>() {
+        joint.handle = joints.remove(&e.id());
+        if let Some(handle) = joint.handle {
+            joint_ids.push((handle, UserData::new(e, joint.id).bits()));
+        }
+    }
+
+    let ref mut res = *world.expect_resource_mut::<PhysicsResource>();
+
+    for (handle, user_data) in body_ids {
+        if let Some(rb) = res.bodies.get_mut(handle) {
+            rb.user_data = user_data;
+        }
+    }
+    for (handle, user_data) in collider_ids {
+        if let Some(col) = res.colliders.get_mut(handle) {
+            col.user_data = user_data;
+        }
+    }
+    for (handle, user_data) in joint_ids {
+        if let Some(joint) = res.impulse_joints.get_mut(handle) {
+            joint.data.user_data = user_data;
+        }
+    }
+
+    // Remove objects without components.
+    for (_, handle) in joints {
+        res.impulse_joints.remove(handle, true);
+    }
+    for (_, handle) in colliders {
+        res.one_way.remove(&handle);
+        res.colliders
+            .remove(handle, &mut res.islands, &mut res.bodies, true);
+    }
+    for (_, (handle, _)) in bodies {
+        res.bodies.remove(
+            handle,
+            &mut res.islands,
+            &mut res.colliders,
+            &mut res.impulse_joints,
+            &mut res.multibody_joints,
+            false,
+        );
+    }
+
+    res.query_pipeline.update(&res.bodies, &res.colliders);
+
+    Ok(())
+}
+
+/// Filter for physics queries.
+#[derive(Clone, Debug, Default)]
+pub struct PhysicsFilter {
+    groups: Option<InteractionGroups>,
+    exclude: Vec<EntityId>,
+    exclude_sensors: bool,
+}
+
+impl PhysicsFilter {
+    pub fn new() -> Self {
+        PhysicsFilter::default()
+    }
+
+    /// Only colliders with compatible collision groups are queried.
+    pub fn groups(mut self, groups: InteractionGroups) -> Self {
+        self.groups = Some(groups);
+        self
+    }
+
+    /// Excludes collider entity or all colliders of the body entity.
+    pub fn exclude(mut self, entity: EntityId) -> Self {
+        self.exclude.push(entity);
+        self
+    }
+
+    pub fn exclude_sensors(mut self) -> Self {
+        self.exclude_sensors = true;
+        self
+    }
+}
+
+/// Result of the ray cast.
+#[derive(Clone, Copy, Debug)]
+pub struct RayHit {
+    /// Collider entity hit by the ray.
+    pub collider: EntityId,
+
+    /// Body to which hit collider belongs if any.
+    pub body: Option<EntityId>,
+
+    /// Ray parameter of the hit point.
+    /// Hit point is `ray.point_at(toi)`.
+    pub toi: f32,
+
+    /// Normal at the hit point.
+    /// None if normal was not requested.
+    pub normal: Option<Vector<f32>>,
+}
+
+/// Result of the shape cast.
+#[derive(Clone, Copy, Debug)]
+pub struct ShapeHit {
+    /// Collider entity hit by the shape.
+    pub collider: EntityId,
+
+    /// Body to which hit collider belongs if any.
+    pub body: Option<EntityId>,
+
+    /// Time at which shape hits the collider.
+    pub toi: f32,
+
+    /// Contact point on the collider in world space.
+    pub point: Point<f32>,
+
+    /// Contact normal on the collider in world space.
+    pub normal: Vector<f32>,
+}
+
+/// Result of the point projection.
+#[derive(Clone, Copy, Debug)]
+pub struct PointHit {
+    /// Collider entity closest to the point.
+    pub collider: EntityId,
+
+    /// Body to which collider belongs if any.
+    pub body: Option<EntityId>,
+
+    /// Projection of the point on the collider.
+    pub point: Point<f32>,
+
+    /// True if point is inside the collider.
+    pub is_inside: bool,
+}
+
+/// Configuration of the physics simulation.
+///
+/// Simulation steps with the fixed update step,
+/// split into `substeps` equal steps.
+#[derive(Clone, Debug)]
+pub struct PhysicsConfig {
+    /// Gravity acceleration applied to dynamic bodies.
+    pub gravity: Vector<f32>,
+
+    /// Number of solver iterations per substep.
+    pub solver_iterations: NonZeroUsize,
+
+    /// Enables continuous collision detection for bodies with CCD.
+    pub ccd: bool,
+
+    /// Number of simulation steps per fixed update.
+    pub substeps: NonZeroUsize,
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        let mut gravity: Vector<f32> = Vector::zeros();
+        gravity.y = -9.81;
+
+        PhysicsConfig {
+            gravity,
+            solver_iterations: IntegrationParameters::default().num_solver_iterations,
+            ccd: true,
+            substeps: NonZeroUsize::MIN,
+        }
+    }
+}
+
+impl TypeModel for PhysicsConfig {
+    fn model() -> Model {
+        with_dim2! {
+            let gravity = Model::Vec2;
+        }
+        with_dim3! {
+            let gravity = Model::Vec3;
+        }
+
+        Model::Record(vec![
+            (name!(gravity), Some(gravity)),
+            (name!(solver_iterations), Some(Model::Int)),
+            (name!(ccd), Some(Model::Bool)),
+            (name!(substeps), Some(Model::Int)),
+        ])
+    }
+
+    fn model_dyn(&self) -> Model {
+        Self::model()
+    }
+}
+
+impl Config for PhysicsConfig {
+    fn to_value(&self) -> Value {
+        with_dim2! {
+            let gravity = Value::Vec2(self.gravity.cast());
+        }
+        with_dim3! {
+            let gravity = Value::Vec3(self.gravity.cast());
+        }
+
+        Value::Map(
+            [
+                ("gravity".to_owned(), gravity),
+                (
+                    "solver_iterations".to_owned(),
+                    Value::Int(self.solver_iterations.get() as i64),
+                ),
+                ("ccd".to_owned(), Value::Bool(self.ccd)),
+                (
+                    "substeps".to_owned(),
+                    Value::Int(self.substeps.get() as i64),
+                ),
+            ]
+            .into_iter()
+            .collect(),
         )
     }
+
+    fn set_value(&mut self, value: &Value) {
+        with_dim2! {
+            if let Some(Value::Vec2(gravity)) = config_field(value, "gravity") {
+                self.gravity = gravity.cast();
+            }
+        }
+        with_dim3! {
+            if let Some(Value::Vec3(gravity)) = config_field(value, "gravity") {
+                self.gravity = gravity.cast();
+            }
+        }
+
+        if let Some(n) = config_count(value, "solver_iterations") {
+            self.solver_iterations = n;
+        }
+        if let Some(Value::Bool(ccd)) = config_field(value, "ccd") {
+            self.ccd = *ccd;
+        }
+        if let Some(n) = config_count(value, "substeps") {
+            self.substeps = n;
+        }
+    }
+}
+
+/// Named collision layers of the project.
+///
+/// Index of the layer in the list is the bit of its collision group.
+/// Default layers added by plugins follow layers configured in the project
+/// unless project configures layer with the same name.
+/// At most 32 layers are supported.
+#[derive(Clone, Debug, Default)]
+pub struct PhysicsLayers {
+    layers: Vec<PhysicsLayer>,
+    defaults: Vec<PhysicsLayer>,
+}
+
+/// Collision layer.
+#[derive(Clone, Debug, Default)]
+pub struct PhysicsLayer {
+    /// Name of the layer.
+    pub name: String,
+
+    /// Names of layers this layer collides with.
+    /// Colliders interact only if both layers list each other.
+    pub collides_with: Vec<String>,
+}
+
+impl PhysicsLayers {
+    pub fn new() -> Self {
+        PhysicsLayers {
+            layers: Vec::new(),
+            defaults: Vec::new(),
+        }
+    }
+
+    /// Adds a layer.
+    pub fn with_layer(mut self, name: impl Into<String>, collides_with: &[&str]) -> Self {
+        self.layers.push(PhysicsLayer {
+            name: name.into(),
+            collides_with: collides_with.iter().map(|&name| name.to_owned()).collect(),
+        });
+        self
+    }
+
+    /// Adds a default layer.
+    ///
+    /// Plugins use it to define layers they rely on.
+    /// Layer configured in the project with the same name takes precedence.
+    pub fn add_default_layer(&mut self, name: impl Into<String>, collides_with: &[&str]) {
+        let name = name.into();
+        self.defaults.retain(|layer| layer.name != name);
+        self.defaults.push(PhysicsLayer {
+            name,
+            collides_with: collides_with.iter().map(|&name| name.to_owned()).collect(),
+        });
+    }
+
+    /// Returns configured layers followed by default layers
+    /// not overridden by configuration.
+    fn all(&self) -> impl Iterator<Item = &PhysicsLayer> + '_ {
+        let defaults = self
+            .defaults
+            .iter()
+            .filter(|default| !self.layers.iter().any(|layer| layer.name == default.name));
+
+        self.layers.iter().chain(defaults)
+    }
+
+    /// Returns collision group bit of the layer.
+    pub fn group(&self, name: &str) -> Option<Group> {
+        let idx = self.all().position(|layer| layer.name == name)?;
+        Group::from_bits(1u32.checked_shl(idx as u32)?)
+    }
+
+    /// Returns interaction groups of the layer.
+    pub fn groups(&self, name: &str) -> Option<InteractionGroups> {
+        let layer = self.all().find(|layer| layer.name == name)?;
+        let memberships = self.group(name)?;
+
+        let filter = layer
+            .collides_with
+            .iter()
+            .filter_map(|name| self.group(name))
+            .fold(Group::NONE, |filter, group| filter | group);
+
+        Some(InteractionGroups::new(memberships, filter))
+    }
+}
+
+impl TypeModel for PhysicsLayers {
+    fn model() -> Model {
+        let layer = Model::Record(vec![
+            (name!(name), Some(Model::String)),
+            (
+                name!(collides_with),
+                Some(Model::Array {
+                    elem: Some(Box::new(Model::String)),
+                    len: None,
+                }),
+            ),
+        ]);
+
+        Model::Record(vec![(
+            name!(layers),
+            Some(Model::Array {
+                elem: Some(Box::new(layer)),
+                len: None,
+            }),
+        )])
+    }
+
+    fn model_dyn(&self) -> Model {
+        Self::model()
+    }
+}
+
+impl Config for PhysicsLayers {
+    fn to_value(&self) -> Value {
+        let layers = self
+            .layers
+            .iter()
+            .map(|layer| {
+                let collides_with = layer
+                    .collides_with
+                    .iter()
+                    .map(|name| Value::String(name.clone()))
+                    .collect();
+
+                Value::Map(
+                    [
+                        ("name".to_owned(), Value::String(layer.name.clone())),
+                        ("collides_with".to_owned(), Value::Array(collides_with)),
+                    ]
+                    .into_iter()
+                    .collect(),
+                )
+            })
+            .collect();
+
+        Value::Map(
+            [("layers".to_owned(), Value::Array(layers))]
+                .into_iter()
+                .collect(),
+        )
+    }
+
+    fn set_value(&mut self, value: &Value) {
+        let Some(Value::Array(layers)) = config_field(value, "layers") else {
+            return;
+        };
+
+        self.layers = layers
+            .iter()
+            .map(|layer| {
+                let name = match config_field(layer, "name") {
+                    Some(Value::String(name)) => name.clone(),
+                    _ => String::new(),
+                };
+
+                let collides_with = match config_field(layer, "collides_with") {
+                    Some(Value::Array(names)) => names
+                        .iter()
+                        .filter_map(|name| match name {
+                            Value::String(name) => Some(name.clone()),
+                            _ => None,
+                        })
+                        .collect(),
+                    _ => Vec::new(),
+                };
+
+                PhysicsLayer {
+                    name,
+                    collides_with,
+                }
+            })
+            .collect();
+    }
+}
+
+/// Returns positive integer field of the config value.
+fn config_count(value: &Value, name: &str) -> Option<NonZeroUsize> {
+    match config_field(value, name) {
+        Some(&Value::Int(n)) => usize::try_from(n).ok().and_then(NonZeroUsize::new),
+        _ => None,
+    }
 }
 
 #[derive(Default)]
@@ -741,31 +2193,41 @@ fn update_kinematic(
 
 fn run_simulation(
     mut res: ResMut<PhysicsResource>,
+    config: Res<PhysicsConfig>,
+    clock: Res<ClockStep>,
     mut collision_events: View<&mut CollisionEvents>,
     mut contact_force_events: View<&mut ContactForceEvents>,
     mut state: State<PhysicsState>,
 ) {
     let res = &mut *res;
 
-    let mut gravity: Vector<f32> = Vector::zeros();
-    gravity.y = -9.81;
-    res.pipeline.step(
-        &gravity,
-        &res.parameters,
-        &mut res.islands,
-        &mut res.broad_phase,
-        &mut res.narrow_phase,
-        &mut res.bodies,
-        &mut res.colliders,
-        &mut res.impulse_joints,
-        &mut res.multibody_joints,
-        &mut res.ccd_solver,
-        None,
-        &(),
-        &EventHandler {
-            new_events: &state.new_events,
-        },
-    );
+    let substeps = config.substeps.get();
+
+    res.parameters.dt = clock.step.as_secs_f32() / substeps as f32;
+    res.parameters.num_solver_iterations = config.solver_iterations;
+    res.parameters.max_ccd_substeps = if config.ccd { 1 } else { 0 };
+
+    for _ in 0..substeps {
+        res.pipeline.step(
+            &config.gravity,
+            &res.parameters,
+            &mut res.islands,
+            &mut res.broad_phase,
+            &mut res.narrow_phase,
+            &mut res.bodies,
+            &mut res.colliders,
+            &mut res.impulse_joints,
+            &mut res.multibody_joints,
+            &mut res.ccd_solver,
+            None,
+            &Hooks {
+                one_way: &res.one_way,
+            },
+            &EventHandler {
+                new_events: &state.new_events,
+            },
+        );
+    }
 
     for event in state.new_events.drain() {
         match event {
@@ -903,6 +2365,24 @@ enum RawEvent {
     },
 }
 
+/// Physics hooks for colliders with active hooks.
+struct Hooks<'a> {
+    one_way: &'a HashMap<ColliderHandle, Vector<f32>>,
+}
+
+impl PhysicsHooks for Hooks<'_> {
+    fn modify_solver_contacts(&self, context: &mut ContactModificationContext) {
+        // Allowed angle between contact normal and platform normal.
+        const ALLOWED_ANGLE: f32 = 0.1;
+
+        if let Some(normal) = self.one_way.get(&context.collider1) {
+            context.update_as_oneway_platform(normal, ALLOWED_ANGLE);
+        } else if let Some(normal) = self.one_way.get(&context.collider2) {
+            context.update_as_oneway_platform(&-normal, ALLOWED_ANGLE);
+        }
+    }
+}
+
 struct EventHandler<'a> {
     new_events: &'a FlipQueue<RawEvent>,
 }
@@ -981,9 +2461,401 @@ pub(crate) fn make_physics_system() -> impl arcana::System {
     (
         init_bodies.into_system(),
         init_colliders.into_system(),
+        retry_joints.into_system(),
+        init_joints.into_system(),
         update_kinematic.into_system(),
         run_simulation.into_system(),
+        break_joints.into_system(),
         update_active.into_system(),
     )
         .into_system()
 }
+
+#[cfg(test)]
+mod tests {
+    use arcana::edict::prelude::ActionBufferSliceExt;
+
+    use super::*;
+
+    /// Physics world without gravity unless set.
+    struct TestWorld {
+        world: World,
+        system: Box<dyn arcana::System>,
+    }
+
+    impl TestWorld {
+        fn new(gravity: Vector<f32>) -> Self {
+            let mut world = World::new();
+            world.insert_resource(PhysicsResource::new());
+            world.insert_resource(PhysicsConfig {
+                gravity,
+                ..PhysicsConfig::default()
+            });
+            world.insert_resource(PhysicsLayers::default());
+
+            let mut rate = arcana::gametime::ClockRate::new();
+            world.insert_resource(rate.step(arcana::gametime::timespan!(16 ms)));
+
+            TestWorld {
+                world,
+                system: Box::new(make_physics_system()),
+            }
+        }
+
+        fn step(&mut self, steps: usize) {
+            for _ in 0..steps {
+                let mut buffers = Vec::new();
+                self.system.run(&mut self.world, &mut buffers);
+                buffers.execute_all(&mut self.world);
+            }
+        }
+
+        fn joint(&self, entity: EntityId) -> Option<Joint> {
+            self.world.try_get_cloned::<Joint>(entity).unwrap()
+        }
+
+        fn joint_count(&self) -> usize {
+            self.world
+                .expect_resource::<PhysicsResource>()
+                .impulse_joints
+                .len()
+        }
+
+        fn dynamic_body(&mut self) -> EntityId {
+            self.world
+                .spawn((
+                    Global::identity(),
+                    RigidBody::dynamic(),
+                    Collider::ball(0.5),
+                ))
+                .id()
+        }
+    }
+
+    #[test]
+    fn joint_waits_for_body() {
+        let mut test = TestWorld::new(Vector::zeros());
+
+        let body1 = test.world.spawn((RigidBody::fixed(),)).id();
+        let body2 = test.world.spawn((Global::identity(),)).id();
+        let joint = test.world.spawn((Joint::fixed(body1, body2),)).id();
+
+        test.step(2);
+        assert!(test.joint(joint).unwrap().handle.is_none());
+        assert_eq!(test.joint_count(), 0);
+
+        test.world
+            .insert_bundle(body2, (RigidBody::dynamic(), Collider::ball(0.5)))
+            .unwrap();
+
+        // Body is initialized on first step, joint is inserted on the same step.
+        test.step(1);
+        assert!(test.joint(joint).unwrap().handle.is_some());
+        assert_eq!(test.joint_count(), 1);
+    }
+
+    #[test]
+    fn joint_removed_with_body() {
+        let mut test = TestWorld::new(Vector::zeros());
+
+        let body1 = test.world.spawn((RigidBody::fixed(),)).id();
+        let body2 = test.dynamic_body();
+        test.world.despawn(body2).unwrap();
+
+        let joint = test.world.spawn((Joint::fixed(body1, body2),)).id();
+
+        test.step(1);
+        assert!(test.joint(joint).is_none());
+        assert_eq!(test.joint_count(), 0);
+    }
+
+    #[test]
+    fn joint_motor_drives_body() {
+        let mut test = TestWorld::new(Vector::zeros());
+
+        let body1 = test.world.spawn((RigidBody::fixed(),)).id();
+        let body2 = test.dynamic_body();
+        let joint = Joint::prismatic(body1, body2, Vector::x_axis()).motor_velocity(
+            JointAxis::LinX,
+            1.0,
+            1.0,
+        );
+        test.world.spawn((joint,));
+
+        test.step(60);
+
+        let res = test.world.expect_resource::<PhysicsResource>();
+        let handle = test.world.get::<&RigidBody>(body2).unwrap().handle.unwrap();
+        let rb = res.bodies.get(handle).unwrap();
+
+        assert!(rb.linvel().x > 0.5, "Motor did not move the body");
+        assert!(rb.translation().x > 0.0);
+    }
+
+    #[test]
+    fn joint_breaks_above_force() {
+        let mut test = TestWorld::new(-Vector::y() * 9.81);
+
+        let body1 = test.world.spawn((RigidBody::fixed(),)).id();
+        let body2 = test.dynamic_body();
+        let joint = test
+            .world
+            .spawn((
+                Joint::fixed(body1, body2).break_force(0.1),
+                JointEvents::new(),
+            ))
+            .id();
+
+        test.step(10);
+
+        assert!(test.joint(joint).is_none());
+        assert_eq!(test.joint_count(), 0);
+
+        let event = test
+            .world
+            .get::<&mut JointEvents>(joint)
+            .unwrap()
+            .deque()
+            .unwrap();
+
+        assert_eq!(event.joint, joint);
+        assert_eq!(event.body1, body1);
+        assert_eq!(event.body2, body2);
+        assert!(event.force > 0.1);
+    }
+
+    #[test]
+    fn cast_shape_hit_in_world_space() {
+        let mut test = TestWorld::new(Vector::zeros());
+
+        let mut offset: Vector<f32> = Vector::zeros();
+        offset.x = 5.0;
+
+        with_dim2! {
+            let rotation = na::UnitComplex::new(std::f32::consts::FRAC_PI_2);
+        }
+        with_dim3! {
+            let rotation = na::UnitQuaternion::from_axis_angle(
+                &Vector::z_axis(),
+                std::f32::consts::FRAC_PI_2,
+            );
+        }
+
+        let pos = Isometry::from_parts(na::Translation::from(offset), rotation);
+        let collider = test.world.spawn((Collider::ball(1.0).position(pos),)).id();
+
+        test.step(1);
+
+        let mut vel: Vector<f32> = Vector::zeros();
+        vel.x = 1.0;
+
+        let res = test.world.expect_resource::<PhysicsResource>();
+        let hit = res
+            .cast_shape(
+                &Isometry::identity(),
+                &vel,
+                &Ball::new(0.5),
+                100.0,
+                &PhysicsFilter::new(),
+            )
+            .unwrap();
+
+        assert_eq!(hit.collider, collider);
+        assert!((hit.toi - 3.5).abs() < 1e-3);
+        assert!((hit.point.x - 4.0).abs() < 1e-3);
+        assert!(hit.point.y.abs() < 1e-3);
+        assert!((hit.normal.x + 1.0).abs() < 1e-3);
+        assert!(hit.normal.y.abs() < 1e-3);
+    }
+
+    #[test]
+    fn default_layers_overridden_by_config() {
+        let mut layers = PhysicsLayers::new().with_layer("ball", &["ball"]);
+        layers.add_default_layer("ball", &["wall"]);
+        layers.add_default_layer("wall", &["ball"]);
+
+        // Configured layer takes precedence and keeps its bit.
+        let ball = layers.groups("ball").unwrap();
+        assert_eq!(ball.memberships, Group::GROUP_1);
+        assert_eq!(ball.filter, Group::GROUP_1);
+
+        // Default layer follows configured ones.
+        let wall = layers.groups("wall").unwrap();
+        assert_eq!(wall.memberships, Group::GROUP_2);
+        assert_eq!(wall.filter, Group::GROUP_1);
+
+        assert!(layers.groups("paddle").is_none());
+
+        // Project config replaces configured layers only.
+        let config = PhysicsLayers::new().with_layer("paddle", &["wall"]);
+        layers.set_value(&config.to_value());
+
+        assert_eq!(layers.group("paddle"), Some(Group::GROUP_1));
+        assert_eq!(layers.group("ball"), Some(Group::GROUP_2));
+        assert_eq!(layers.group("wall"), Some(Group::GROUP_3));
+    }
+
+    #[test]
+    fn collider_with_unknown_layer_removed() {
+        let mut test = TestWorld::new(Vector::zeros());
+
+        let e = test
+            .world
+            .spawn((Collider::ball(1.0).layer("missing"),))
+            .id();
+
+        test.step(1);
+
+        assert!(test.world.try_get_cloned::<Collider>(e).unwrap().is_none());
+        assert!(test
+            .world
+            .expect_resource::<PhysicsResource>()
+            .colliders
+            .is_empty());
+    }
+
+    #[test]
+    fn collider_value_roundtrip() {
+        let collider = Collider::ball(1.0)
+            .sensor(true)
+            .layer("ball")
+            .friction(0.25)
+            .restitution(0.75)
+            .density(2.0);
+
+        let mut copy = Collider::ball(1.0);
+        copy.set_value(&collider.to_value());
+
+        assert_eq!(copy.to_value(), collider.to_value());
+        assert_eq!(copy.get_material(), collider.get_material());
+        assert_eq!(copy.layer.as_deref(), Some("ball"));
+    }
+
+    #[test]
+    fn collider_edits_applied() {
+        let mut test = TestWorld::new(Vector::zeros());
+
+        let e = test.dynamic_body();
+        test.step(1);
+
+        let material = PhysicsMaterial {
+            friction: 0.25,
+            restitution: 0.75,
+            density: 2.0,
+        };
+
+        let value = Value::Map(
+            [
+                ("sensor".to_owned(), Value::Bool(true)),
+                ("material".to_owned(), material.to_value()),
+            ]
+            .into_iter()
+            .collect(),
+        );
+
+        test.world
+            .get::<&mut Collider>(e)
+            .unwrap()
+            .set_value(&value);
+
+        test.step(1);
+
+        let handle = test.world.get::<&Collider>(e).unwrap().handle.unwrap();
+        let res = test.world.expect_resource::<PhysicsResource>();
+        let col = res.colliders.get(handle).unwrap();
+
+        assert!(col.is_sensor());
+        assert_eq!(col.friction(), 0.25);
+        assert_eq!(col.restitution(), 0.75);
+        assert_eq!(col.density(), 2.0);
+    }
+
+    /// Physics world with a pile of balls falling to the ground.
+    #[cfg(feature = "determinism")]
+    struct Replay {
+        test: TestWorld,
+        balls: Vec<EntityId>,
+    }
+
+    #[cfg(feature = "determinism")]
+    impl Replay {
+        fn new() -> Self {
+            let mut test = TestWorld::new(PhysicsConfig::default().gravity);
+
+            test.world.spawn((Collider::halfspace(Vector::y_axis()),));
+
+            let balls = (0..10)
+                .map(|i| {
+                    let mut offset: Vector<f32> = Vector::zeros();
+                    offset.x = i as f32 * 0.1;
+                    offset.y = 1.0 + i as f32 * 1.1;
+
+                    let pos = Isometry::from(na::Translation::from(offset));
+
+                    test.world
+                        .spawn((
+                            Global::identity(),
+                            RigidBody::dynamic().position(pos),
+                            Collider::ball(0.5).restitution(0.5),
+                        ))
+                        .id()
+                })
+                .collect();
+
+            Replay { test, balls }
+        }
+
+        /// Runs physics steps and returns bits of ball positions after each step.
+        fn run(&mut self, steps: usize) -> Vec<Vec<u32>> {
+            (0..steps)
+                .map(|_| {
+                    self.test.step(1);
+
+                    self.balls
+                        .iter()
+                        .flat_map(|&ball| {
+                            let global = self
+                                .test
+                                .world
+                                .try_get_cloned::<Global>(ball)
+                                .unwrap()
+                                .unwrap();
+                            let matrix = global.iso.to_homogeneous();
+                            matrix.iter().map(|v| v.to_bits()).collect::<Vec<_>>()
+                        })
+                        .collect()
+                })
+                .collect()
+        }
+    }
+
+    #[cfg(feature = "determinism")]
+    #[test]
+    fn replays_are_identical() {
+        let first = Replay::new().run(1000);
+        let second = Replay::new().run(1000);
+
+        assert!(first == second, "Replays diverged");
+    }
+
+    #[cfg(feature = "determinism")]
+    #[test]
+    fn restored_snapshot_replays_identically() {
+        let mut replay = Replay::new();
+
+        // Inserts bodies and colliders.
+        replay.run(1);
+
+        let snapshot = replay
+            .test
+            .world
+            .expect_resource::<PhysicsResource>()
+            .snapshot();
+
+        let first = replay.run(1000);
+        restore_physics(&mut replay.test.world, &snapshot).unwrap();
+        let second = replay.run(1000);
+
+        assert!(first == second, "Replay after restore diverged");
+    }
+}
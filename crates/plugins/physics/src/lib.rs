@@ -61,8 +61,9 @@ arcana::export_arcana_plugin! {
     PhysicsPlugin {
         dependencies: [scene ...],
         resources: [dim2::PhysicsResource::new()],
-        components: [dim2::RigidBody],
+        components: [dim2::RigidBody, dim2::Collider],
         systems: [physics_system_2d: dim2::make_physics_system()],
+        configs: [physics_config_2d: dim2::PhysicsConfig, physics_layers_2d: dim2::PhysicsLayers],
     }
 }
 
@@ -71,8 +72,9 @@ arcana::export_arcana_plugin! {
     PhysicsPlugin {
         dependencies: [scene ...],
         resources: [dim3::PhysicsResource::new()],
-        components: [dim3::RigidBody],
+        components: [dim3::RigidBody, dim3::Collider],
         systems: [physics_system_3d: dim3::make_physics_system()],
+        configs: [physics_config_3d: dim3::PhysicsConfig, physics_layers_3d: dim3::PhysicsLayers],
     }
 }
 
@@ -81,7 +83,13 @@ arcana::export_arcana_plugin! {
     PhysicsPlugin {
         dependencies: [scene ...],
         resources: [dim2::PhysicsResource::new(), dim3::PhysicsResource::new()],
-        components: [dim2::RigidBody, dim3::RigidBody],
+        components: [dim2::RigidBody, dim2::Collider, dim3::RigidBody, dim3::Collider],
         systems: [physics_system_2d: dim2::make_physics_system(), physics_system_3d: dim3::make_physics_system()],
+        configs: [
+            physics_config_2d: dim2::PhysicsConfig,
+            physics_layers_2d: dim2::PhysicsLayers,
+            physics_config_3d: dim3::PhysicsConfig,
+            physics_layers_3d: dim3::PhysicsLayers,
+        ],
     }
 }
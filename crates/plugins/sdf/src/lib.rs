@@ -1,9 +1,10 @@
 use std::mem::size_of;
 
 use arcana::{
-    edict::{self, Component, EntityId, World},
+    edict::{self, Component, World},
     mev::{self, Arguments, DeviceRepr},
-    render::{Render, RenderBuilderContext, RenderContext, RenderError, RenderGraph, TargetId},
+    model::Model,
+    work::{Exec, Image2D, Job, JobDesc, Planner},
 };
 
 // macro_rules! print_layout {
@@ -21,13 +22,14 @@ use arcana::{
 //     };
 // }
 
-use camera::Camera2;
+use camera::{job_camera, Camera2};
 use scene::dim2::Global;
 
 arcana::export_arcana_plugin! {
     SdfPlugin {
         dependencies: [scene ..., camera ...],
         components: [Shape],
+        jobs: [DrawSdf],
     }
 }
 
@@ -104,9 +106,7 @@ pub struct MainConstants {
     pub shape_count: u32,
 }
 
-pub struct SdfRender {
-    camera: EntityId,
-    target: TargetId<mev::Image>,
+pub struct DrawSdf {
     pipeline: Option<mev::RenderPipeline>,
     arguments: Option<MainArguments>,
     constants: MainConstants,
@@ -116,19 +116,16 @@ pub struct SdfRender {
     rects_device: Vec<<RectDevice as DeviceRepr>::Repr>,
 }
 
-impl SdfRender {
-    pub fn build(camera: EntityId, graph: &mut RenderGraph) -> TargetId<mev::Image> {
-        // Start building render.
-        let mut builder = RenderBuilderContext::new("main_pass", graph);
-
-        // This render defines a single render target.
-        let target = builder.create_target("main", mev::PipelineStages::COLOR_OUTPUT);
+impl DrawSdf {
+    pub fn desc() -> JobDesc {
+        arcana::job_desc! [
+            camera: in Model::Entity,
+            main: +Image2D,
+        ]
+    }
 
-        // Build the render with SdfRender as `Render` impl.
-        // `SdfRender::render` will be called every frame to encode commands for this render.
-        builder.build(SdfRender {
-            camera,
-            target,
+    pub fn new() -> Self {
+        DrawSdf {
             pipeline: None,
             arguments: None,
             constants: MainConstants {
@@ -139,17 +136,22 @@ impl SdfRender {
             shapes_device: Vec::new(),
             circles_device: Vec::new(),
             rects_device: Vec::new(),
-        });
-        target
+        }
     }
 }
 
-impl Render for SdfRender {
-    fn render(&mut self, world: &World, mut cx: RenderContext<'_, '_>) -> Result<(), RenderError> {
-        let mut encoder = cx.new_command_encoder()?;
-        let target = cx.write_target(self.target, &mut encoder).clone();
+impl Job for DrawSdf {
+    fn plan(&mut self, mut planner: Planner<'_>, _world: &mut World) {
+        planner.create::<Image2D>();
+    }
+
+    fn exec(&mut self, runner: Exec<'_>, world: &mut World) {
+        let Some(target) = runner.create::<Image2D>() else {
+            return;
+        };
+
         let pipeline = self.pipeline.get_or_insert_with(|| {
-            let main_library = cx
+            let main_library = runner
                 .device()
                 .new_shader_library(mev::LibraryDesc {
                     name: "main",
@@ -157,7 +159,8 @@ impl Render for SdfRender {
                 })
                 .unwrap();
 
-            cx.device()
+            runner
+                .device()
                 .new_render_pipeline(mev::RenderPipelineDesc {
                     name: "main",
                     vertex_shader: mev::Shader {
@@ -188,25 +191,26 @@ impl Render for SdfRender {
 
         let dims = target.dimensions().expect_2d();
 
-        let camera = world
-            .try_view_one::<(&Global, &Camera2)>(self.camera)
-            .expect("Camera is missing");
-
-        let camera = {
-            let (g, c) = camera.get().unwrap();
+        // Without camera only background is drawn.
+        let camera = job_camera(runner.param("camera"), world).and_then(|camera| {
+            let view = world.try_view_one::<(&Global, &Camera2)>(camera).ok()?;
+            let (g, c) = view.get()?;
 
             let viewport = c
                 .viewport
                 .transform(1.0, dims.width() as f32 / dims.height() as f32);
 
-            <[[f32; 3]; 3]>::from((g.iso * viewport).to_homogeneous())
-        };
+            Some(<[[f32; 3]; 3]>::from((g.iso * viewport).to_homogeneous()))
+        });
 
         let shapes = world.view::<(&Global, &Shape)>();
-        let shapes_count = shapes.iter().count();
+        let shapes_count = match camera {
+            Some(_) => shapes.iter().count(),
+            None => 0,
+        };
 
         let arguments = self.arguments.get_or_insert_with(|| {
-            let shapes = cx
+            let shapes = runner
                 .device()
                 .new_buffer(mev::BufferDesc {
                     size: size_of::<<ShapeDevice as DeviceRepr>::Repr>()
@@ -217,7 +221,7 @@ impl Render for SdfRender {
                 })
                 .unwrap();
 
-            let circles = cx
+            let circles = runner
                 .device()
                 .new_buffer(mev::BufferDesc {
                     size: size_of::<<CirleDevice as DeviceRepr>::Repr>()
@@ -228,7 +232,7 @@ impl Render for SdfRender {
                 })
                 .unwrap();
 
-            let rects = cx
+            let rects = runner
                 .device()
                 .new_buffer(mev::BufferDesc {
                     size: size_of::<<RectDevice as DeviceRepr>::Repr>()
@@ -246,7 +250,7 @@ impl Render for SdfRender {
         });
 
         if arguments.shapes.size() < size_of::<<ShapeDevice as DeviceRepr>::Repr>() * shapes_count {
-            arguments.shapes = cx
+            arguments.shapes = runner
                 .device()
                 .new_buffer(mev::BufferDesc {
                     size: size_of::<<ShapeDevice as DeviceRepr>::Repr>()
@@ -260,7 +264,7 @@ impl Render for SdfRender {
 
         if arguments.circles.size() < size_of::<<CirleDevice as DeviceRepr>::Repr>() * shapes_count
         {
-            arguments.circles = cx
+            arguments.circles = runner
                 .device()
                 .new_buffer(mev::BufferDesc {
                     size: size_of::<<CirleDevice as DeviceRepr>::Repr>()
@@ -273,7 +277,7 @@ impl Render for SdfRender {
         }
 
         if arguments.rects.size() < size_of::<<RectDevice as DeviceRepr>::Repr>() * shapes_count {
-            arguments.rects = cx
+            arguments.rects = runner
                 .device()
                 .new_buffer(mev::BufferDesc {
                     size: size_of::<<RectDevice as DeviceRepr>::Repr>()
@@ -287,14 +291,18 @@ impl Render for SdfRender {
 
         self.constants = MainConstants {
             background: mev::vec4(0.5, 0.2, 0.1, 1.0),
-            camera: mev::mat3::from(camera),
+            camera: mev::mat3::from(camera.unwrap_or([
+                [1.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0],
+                [0.0, 0.0, 1.0],
+            ])),
             shape_count: shapes_count as u32,
         };
 
         self.shapes_device.clear();
         self.circles_device.clear();
         self.rects_device.clear();
-        for (global, shape) in shapes.iter() {
+        for (global, shape) in shapes.iter().take(shapes_count) {
             let tr = global.iso.to_homogeneous() * shape.transform.matrix();
             let inv_tr = tr.try_inverse().unwrap();
 
@@ -331,6 +339,8 @@ impl Render for SdfRender {
             }
         }
 
+        let encoder = runner.new_encoder();
+
         {
             let mut copy = encoder.copy();
             copy.write_buffer_slice(&arguments.shapes, &self.shapes_device);
@@ -355,7 +365,5 @@ impl Render for SdfRender {
         render.with_scissor(mev::Offset2::ZERO, dims);
         render.draw(0..3, 0..1);
         drop(render);
-        cx.commit(encoder.finish()?);
-        Ok(())
     }
 }
@@ -0,0 +1,351 @@
+use std::mem::size_of;
+
+use arcana::{
+    edict::{self, Component, World},
+    font::Font,
+    mev::{self, Arguments, DeviceRepr},
+    model::Model,
+    na,
+    work::{Exec, Image2D, Job, JobDesc, Planner},
+};
+use camera::{job_camera, Camera2};
+use scene::dim2::Global;
+
+arcana::export_arcana_plugin! {
+    TextPlugin {
+        dependencies: [scene ..., camera ...],
+        components: [Text],
+        jobs: [DrawText],
+    }
+}
+
+/// Space in which text is placed.
+#[derive(Clone, Copy, Debug)]
+pub enum TextSpace {
+    /// Text is placed on the target at given position in pixels
+    /// from top-left corner.
+    /// Size of the text is in pixels.
+    Screen { position: [f32; 2] },
+
+    /// Text is placed in the world at entity's [`Global`] transform
+    /// and viewed through the camera.
+    /// Size of the text is in world units.
+    World,
+}
+
+/// Text rendered by [`DrawText`] job.
+#[derive(Clone, Component)]
+pub struct Text {
+    pub font: Font,
+    pub string: String,
+
+    /// Height of the em square.
+    pub size: f32,
+
+    /// Linear color with straight alpha.
+    pub color: [f32; 4],
+
+    pub space: TextSpace,
+}
+
+impl Text {
+    pub fn new(font: Font, string: impl Into<String>) -> Self {
+        Text {
+            font,
+            string: string.into(),
+            size: 16.0,
+            color: [1.0, 1.0, 1.0, 1.0],
+            space: TextSpace::Screen {
+                position: [0.0, 0.0],
+            },
+        }
+    }
+
+    pub fn with_size(mut self, size: f32) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn on_screen(mut self, position: [f32; 2]) -> Self {
+        self.space = TextSpace::Screen { position };
+        self
+    }
+
+    pub fn in_world(mut self) -> Self {
+        self.space = TextSpace::World;
+        self
+    }
+}
+
+#[derive(DeviceRepr)]
+struct GlyphDevice {
+    color: mev::vec4,
+    origin: mev::vec2,
+    axis_x: mev::vec2,
+    axis_y: mev::vec2,
+    uv_min: mev::vec2,
+    uv_max: mev::vec2,
+}
+
+#[derive(mev::Arguments)]
+pub struct TextArguments {
+    #[mev(fragment)]
+    pub sampler: mev::Sampler,
+    #[mev(fragment)]
+    pub atlas: mev::Image,
+    #[mev(storage, vertex)]
+    pub glyphs: mev::Buffer,
+}
+
+/// Glyphs of the same font drawn with single draw call.
+struct Batch {
+    font: Font,
+    start: u32,
+    end: u32,
+}
+
+/// Draws [`Text`] components over the target image.
+///
+/// Screen-space text is placed in target pixels.
+/// World-space text is viewed through the camera entity.
+pub struct DrawText {
+    pipeline: Option<mev::RenderPipeline>,
+    sampler: Option<mev::Sampler>,
+    glyphs: Option<mev::Buffer>,
+    glyphs_device: Vec<<GlyphDevice as DeviceRepr>::Repr>,
+    batches: Vec<Batch>,
+}
+
+impl DrawText {
+    pub fn desc() -> JobDesc {
+        arcana::job_desc! [
+            camera: in Model::Entity,
+            main: mut Image2D,
+        ]
+    }
+
+    pub fn new() -> Self {
+        DrawText {
+            pipeline: None,
+            sampler: None,
+            glyphs: None,
+            glyphs_device: Vec::new(),
+            batches: Vec::new(),
+        }
+    }
+
+    /// Pushes glyphs of the text transformed by `tr` into normalized device coordinates.
+    fn push_text(&mut self, text: &Text, tr: &na::Matrix3<f32>) {
+        let start = self.glyphs_device.len() as u32;
+
+        for quad in text.font.layout(&text.string, text.size) {
+            let origin = tr * na::Vector3::new(quad.min[0], quad.min[1], 1.0);
+            let axis_x = tr * na::Vector3::new(quad.max[0] - quad.min[0], 0.0, 0.0);
+            let axis_y = tr * na::Vector3::new(0.0, quad.max[1] - quad.min[1], 0.0);
+
+            self.glyphs_device.push(
+                GlyphDevice {
+                    color: mev::vec(text.color),
+                    origin: mev::vec2(origin.x, origin.y),
+                    axis_x: mev::vec2(axis_x.x, axis_x.y),
+                    axis_y: mev::vec2(axis_y.x, axis_y.y),
+                    uv_min: mev::vec(quad.uv_min),
+                    uv_max: mev::vec(quad.uv_max),
+                }
+                .as_repr(),
+            );
+        }
+
+        let end = self.glyphs_device.len() as u32;
+        if start == end {
+            return;
+        }
+
+        match self.batches.last_mut() {
+            Some(batch) if batch.font.same(&text.font) && batch.end == start => batch.end = end,
+            _ => self.batches.push(Batch {
+                font: text.font.clone(),
+                start,
+                end,
+            }),
+        }
+    }
+}
+
+impl Job for DrawText {
+    fn plan(&mut self, mut planner: Planner<'_>, _world: &mut World) {
+        planner.update::<Image2D>();
+    }
+
+    fn exec(&mut self, runner: Exec<'_>, world: &mut World) {
+        let Some(target) = runner.update::<Image2D>() else {
+            return;
+        };
+
+        let dims = target.dimensions().expect_2d();
+        let width = dims.width() as f32;
+        let height = dims.height() as f32;
+
+        self.glyphs_device.clear();
+        self.batches.clear();
+
+        // Maps pixels with Y axis pointing down into normalized device coordinates.
+        let screen = na::Matrix3::new(
+            2.0 / width,
+            0.0,
+            -1.0,
+            0.0,
+            -2.0 / height,
+            1.0,
+            0.0,
+            0.0,
+            1.0,
+        );
+
+        for text in world.view::<&Text>().iter() {
+            if let TextSpace::Screen { position } = text.space {
+                let tr = screen * na::Matrix3::new_translation(&position.into());
+                self.push_text(text, &tr);
+            }
+        }
+
+        let camera = job_camera(runner.param("camera"), world).and_then(|camera| {
+            let view = world.try_view_one::<(&Global, &Camera2)>(camera).ok()?;
+            let (g, c) = view.get()?;
+            let viewport = c.viewport.transform(1.0, width / height);
+            (g.iso * viewport).to_homogeneous().try_inverse()
+        });
+
+        if let Some(camera) = camera {
+            // Text is laid out with Y axis pointing down.
+            let flip = na::Matrix3::new_nonuniform_scaling(&na::Vector2::new(1.0, -1.0));
+
+            for (global, text) in world.view::<(&Global, &Text)>().iter() {
+                if let TextSpace::World = text.space {
+                    let tr = camera * global.iso.to_homogeneous() * flip;
+                    self.push_text(text, &tr);
+                }
+            }
+        }
+
+        if self.batches.is_empty() {
+            return;
+        }
+
+        let pipeline = self.pipeline.get_or_insert_with(|| {
+            let library = runner
+                .device()
+                .new_shader_library(mev::LibraryDesc {
+                    name: "text",
+                    input: mev::include_library!("shaders/text.wgsl" as mev::ShaderLanguage::Wgsl),
+                })
+                .unwrap();
+
+            runner
+                .device()
+                .new_render_pipeline(mev::RenderPipelineDesc {
+                    name: "text",
+                    vertex_shader: mev::Shader {
+                        library: library.clone(),
+                        entry: "vs_main".into(),
+                    },
+                    vertex_attributes: vec![],
+                    vertex_layouts: vec![],
+                    primitive_topology: mev::PrimitiveTopology::Triangle,
+                    raster: Some(mev::RasterDesc {
+                        fragment_shader: Some(mev::Shader {
+                            library,
+                            entry: "fs_main".into(),
+                        }),
+                        color_targets: vec![mev::ColorTargetDesc {
+                            format: target.format(),
+                            blend: Some(mev::BlendDesc::default()),
+                        }],
+                        depth_stencil: None,
+                        front_face: mev::FrontFace::default(),
+                        culling: mev::Culling::None,
+                    }),
+                    arguments: &[TextArguments::LAYOUT],
+                    constants: 0,
+                })
+                .unwrap()
+        });
+
+        let sampler = self.sampler.get_or_insert_with(|| {
+            runner
+                .device()
+                .new_sampler(mev::SamplerDesc {
+                    min_filter: mev::Filter::Linear,
+                    mag_filter: mev::Filter::Linear,
+                    mip_map_mode: mev::MipMapMode::Linear,
+                    address_mode: [mev::AddressMode::ClampToEdge; 3],
+                    ..mev::SamplerDesc::new()
+                })
+                .unwrap()
+        });
+
+        let size = size_of::<<GlyphDevice as DeviceRepr>::Repr>() * self.glyphs_device.len();
+
+        if self
+            .glyphs
+            .as_ref()
+            .map_or(true, |glyphs| glyphs.size() < size)
+        {
+            self.glyphs = Some(
+                runner
+                    .device()
+                    .new_buffer(mev::BufferDesc {
+                        size: size.next_power_of_two(),
+                        name: "glyphs",
+                        usage: mev::BufferUsage::STORAGE | mev::BufferUsage::TRANSFER_DST,
+                        memory: mev::Memory::Shared,
+                    })
+                    .unwrap(),
+            );
+        }
+
+        let glyphs = self.glyphs.as_ref().unwrap();
+
+        let encoder = runner.new_encoder();
+
+        encoder.barrier(
+            mev::PipelineStages::VERTEX_SHADER,
+            mev::PipelineStages::TRANSFER,
+        );
+
+        encoder
+            .copy()
+            .write_buffer_slice(glyphs, &self.glyphs_device);
+
+        encoder.barrier(
+            mev::PipelineStages::TRANSFER,
+            mev::PipelineStages::VERTEX_SHADER,
+        );
+
+        let mut render = encoder.render(mev::RenderPassDesc {
+            color_attachments: &[mev::AttachmentDesc::new(&target)],
+            ..Default::default()
+        });
+
+        render.with_pipeline(pipeline);
+        render.with_viewport(mev::Offset3::ZERO, mev::Extent3::new(width, height, 1.0));
+        render.with_scissor(mev::Offset2::ZERO, dims);
+
+        for batch in &self.batches {
+            render.with_arguments(
+                0,
+                &TextArguments {
+                    sampler: sampler.clone(),
+                    atlas: batch.font.atlas.image.clone(),
+                    glyphs: glyphs.clone(),
+                },
+            );
+            render.draw(0..6, batch.start..batch.end);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+
+struct Glyph {
+    color: vec4f,
+    origin: vec2f,
+    axis_x: vec2f,
+    axis_y: vec2f,
+    uv_min: vec2f,
+    uv_max: vec2f,
+}
+
+struct VertOutput {
+    @builtin(position)
+    position: vec4f,
+    @location(0)
+    uv: vec2f,
+    @location(1)
+    color: vec4f,
+}
+
+@group(0) @binding(0) var s: sampler;
+@group(0) @binding(1) var atlas: texture_2d<f32>;
+@group(0) @binding(2) var<storage> glyphs: array<Glyph>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex: u32, @builtin(instance_index) instance: u32) -> VertOutput {
+    let glyph = glyphs[instance];
+
+    // Two triangles covering the glyph quad.
+    var corners = array<vec2f, 6>(
+        vec2f(0f, 0f),
+        vec2f(1f, 0f),
+        vec2f(0f, 1f),
+        vec2f(0f, 1f),
+        vec2f(1f, 0f),
+        vec2f(1f, 1f),
+    );
+    let corner = corners[vertex];
+
+    let position = glyph.origin + glyph.axis_x * corner.x + glyph.axis_y * corner.y;
+    let uv = mix(glyph.uv_min, glyph.uv_max, corner);
+
+    return VertOutput(vec4f(position, 0f, 1f), uv, glyph.color);
+}
+
+@fragment
+fn fs_main(@location(0) uv: vec2f, @location(1) color: vec4f) -> @location(0) vec4f {
+    let distance = textureSample(atlas, s, uv).r;
+    let width = max(fwidth(distance), 0.0001f);
+    let alpha = smoothstep(0.5f - width, 0.5f + width, distance) * color.a;
+
+    // Output is premultiplied.
+    return vec4f(color.rgb * alpha, alpha);
+}
@@ -230,8 +230,8 @@ impl OpJob {
                 (name!(mul), Some(Model::Unit)),
                 (name!(div), Some(Model::Unit)),
             ]),
-            src: Image2D,
-            dst: mut Image2D,
+            src: Image2D as Storage,
+            dst: mut Image2D as Storage,
         ]
     }
 
@@ -295,8 +295,6 @@ impl Job for OpJob {
 
         let encoder = runner.new_encoder();
 
-        encoder.barrier(mev::PipelineStages::all(), mev::PipelineStages::all());
-
         let mut compute = encoder.compute();
 
         let dims = src.dimensions().expect_2d();
@@ -313,8 +311,6 @@ impl Job for OpJob {
 
         compute.dispatch(dims.to_3d());
         drop(compute);
-
-        encoder.barrier(mev::PipelineStages::all(), mev::PipelineStages::all());
     }
 }
 
@@ -486,7 +486,7 @@ arcana = {{ workspace = true }}
 //! [{gh_issue}]
 
 fn main() {{
-    const PLUGINS: [&'static dyn arcana::plugin::ArcanaPlugin; {plugins_count}] = ["#,
+    const PLUGINS: [(arcana::Ident, &'static dyn arcana::plugin::ArcanaPlugin); {plugins_count}] = ["#,
         gh_issue = github_autogen_issue_template("game/src/main.rs"),
         plugins_count = plugins.len(),
     );
@@ -496,7 +496,7 @@ fn main() {{
 
         for plugin in plugins {
             main_rs.push_str(&format!(
-                "        {name}::__arcana_plugin(),\n",
+                "        (arcana::Ident::from_ident_str(stringify!({name})), {name}::__arcana_plugin()),\n",
                 name = &plugin.name
             ));
         }
@@ -504,7 +504,7 @@ fn main() {{
 
     main_rs.push_str(
         r#"    ];
-    arcana::game::run(&PLUGINS);
+    arcana::game::run(&PLUGINS, env!("CARGO_MANIFEST_DIR").as_ref());
 }"#,
     );
 